@@ -0,0 +1,185 @@
+use std::cell::RefCell;
+
+use ratatui::backend::Backend;
+use ratatui::layout::Rect;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::cookies::{CookieEntry, CookieJar};
+use crate::ui::Component;
+
+pub struct CookiesComponent {
+    pub jar: CookieJar,
+    pub entries: Vec<CookieEntry>,
+    pub selected: usize,
+    pub list_state: RefCell<ListState>,
+    pub input: Input,
+    pub is_editing: bool,
+    // Picked up by the app and shown on the bottom line.
+    pub save_error: Option<String>,
+}
+
+impl CookiesComponent {
+    pub fn new(jar: CookieJar) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+        let entries = jar.entries();
+        Self {
+            jar,
+            entries,
+            selected: 0,
+            list_state: RefCell::new(list_state),
+            input: Input::default(),
+            is_editing: false,
+            save_error: None,
+        }
+    }
+
+    pub fn set_jar(&mut self, jar: CookieJar) {
+        self.jar = jar;
+        self.refresh();
+    }
+
+    // The jar is shared with the http client, so re-read it whenever the view
+    // is opened or changed.
+    pub fn refresh(&mut self) {
+        self.entries = self.jar.entries();
+        if self.selected >= self.entries.len() {
+            self.selected = self.entries.len().saturating_sub(1);
+        }
+        self.list_state.borrow_mut().select(Some(self.selected));
+    }
+
+    fn save(&mut self) {
+        if let Err(e) = self.jar.save() {
+            self.save_error = Some(format!("Failed to save cookies: {}", e));
+        }
+        self.refresh();
+    }
+
+    fn draw_modal(&self, f: &mut Frame, entry: &CookieEntry) {
+        let size = f.size();
+        let modal_width = 80.min(size.width);
+        let modal_height = 5;
+        let area = Rect::new(
+            (size.width - modal_width) / 2,
+            (size.height.saturating_sub(modal_height)) / 2,
+            modal_width,
+            modal_height,
+        );
+
+        let paragraph = Paragraph::new(self.input.value())
+            .block(Block::default().borders(Borders::ALL).title(format!("{} ({})", entry.name, entry.domain)))
+            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+        f.set_cursor(area.x + 1 + self.input.visual_cursor() as u16, area.y + 1);
+    }
+}
+
+impl Component for CookiesComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Cookies - {} (enter: edit, d: delete, D: clear domain, X: clear all)",
+                self.jar.environment
+            ))
+            .style(Style::default().fg(if is_active {
+                Color::Green
+            } else {
+                Color::White
+            }));
+
+        let items: Vec<ListItem> = if self.entries.is_empty() {
+            vec![ListItem::new("No cookies")]
+        } else {
+            self.entries
+                .iter()
+                .map(|entry| {
+                    ListItem::new(format!(
+                        "{}{}  {}={}  ({})",
+                        entry.domain, entry.path, entry.name, entry.value, entry.expires
+                    ))
+                })
+                .collect()
+        };
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">>");
+
+        f.render_stateful_widget(list, area, &mut self.list_state.borrow_mut());
+
+        if self.is_editing {
+            if let Some(entry) = self.entries.get(self.selected) {
+                self.draw_modal(f, entry);
+            }
+        }
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        if self.is_editing {
+            match key {
+                KeyCode::Enter => {
+                    if let Some(entry) = self.entries.get(self.selected).cloned() {
+                        self.jar.set_value(&entry, self.input.value());
+                        self.save();
+                    }
+                    self.is_editing = false;
+                }
+                KeyCode::Esc => {
+                    self.is_editing = false;
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(KeyEvent::new(
+                        key,
+                        crossterm::event::KeyModifiers::NONE,
+                    )));
+                }
+            }
+            return;
+        }
+
+        let count = self.entries.len();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                self.selected = if self.selected == 0 { count - 1 } else { self.selected - 1 };
+                self.list_state.borrow_mut().select(Some(self.selected));
+            }
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                self.selected = if self.selected + 1 >= count { 0 } else { self.selected + 1 };
+                self.list_state.borrow_mut().select(Some(self.selected));
+            }
+            KeyCode::Enter => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    self.input = Input::from(entry.value.clone());
+                    self.is_editing = true;
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(entry) = self.entries.get(self.selected).cloned() {
+                    self.jar.remove(&entry);
+                    self.save();
+                }
+            }
+            KeyCode::Char('D') => {
+                if let Some(entry) = self.entries.get(self.selected).cloned() {
+                    self.jar.clear_domain(&entry.domain);
+                    self.save();
+                }
+            }
+            KeyCode::Char('X') => {
+                self.jar.clear();
+                self.save();
+            }
+            _ => {}
+        }
+    }
+}
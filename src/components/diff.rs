@@ -0,0 +1,237 @@
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::diff::{self, Change, Row, Snapshot};
+use crate::ui::Component;
+
+// Compares two responses picked from the current one, those in history and
+// the saved examples.
+pub struct DiffComponent {
+    pub sources: Vec<(String, Snapshot)>,
+    pub left: usize,
+    pub right: usize,
+    pub unified: bool,
+    pub ignore: Vec<String>,
+    pub input: Input,
+    pub is_editing: bool,
+    pub scroll_y: u16,
+    // Shown instead of the diff while its responses are still coming in.
+    pub status: Option<String>,
+    changes: Vec<Change>,
+}
+
+impl DiffComponent {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            left: 0,
+            right: 0,
+            unified: false,
+            ignore: Vec::new(),
+            input: Input::default(),
+            is_editing: false,
+            scroll_y: 0,
+            status: None,
+            changes: Vec::new(),
+        }
+    }
+
+    // The newest source goes on the right and the one before it on the left,
+    // which is the usual "what changed since last time".
+    pub fn set_sources(&mut self, sources: Vec<(String, Snapshot)>, ignore: Vec<String>) {
+        self.sources = sources;
+        self.ignore = ignore;
+        self.status = None;
+        self.right = 0;
+        self.left = if self.sources.len() > 1 { 1 } else { 0 };
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        self.changes = match (self.sources.get(self.left), self.sources.get(self.right)) {
+            (Some((_, left)), Some((_, right))) => diff::diff(&left.normalized(&self.ignore), &right.normalized(&self.ignore)),
+            _ => Vec::new(),
+        };
+        self.scroll_y = 0;
+    }
+
+    fn step(index: usize, count: usize, forward: bool) -> usize {
+        match (count, forward) {
+            (0, _) => 0,
+            (_, true) => (index + 1) % count,
+            (_, false) => (index + count - 1) % count,
+        }
+    }
+
+    fn styled(change: &Change, marker: bool) -> Line<'static> {
+        let (prefix, text, style) = match change {
+            Change::Same(text) => ("  ", text, Style::default().fg(Color::White)),
+            Change::Removed(text) => ("- ", text, Style::default().fg(Color::Red)),
+            Change::Added(text) => ("+ ", text, Style::default().fg(Color::Green)),
+        };
+        let text = if marker { format!("{}{}", prefix, text) } else { text.clone() };
+        Line::from(Span::styled(text, style))
+    }
+
+    fn draw_modal(&self, f: &mut Frame) {
+        let size = f.size();
+        let modal_width = 80.min(size.width);
+        let modal_height = 3;
+        let area = Rect::new(
+            (size.width - modal_width) / 2,
+            (size.height.saturating_sub(modal_height)) / 2,
+            modal_width,
+            modal_height,
+        );
+
+        let paragraph = Paragraph::new(self.input.value())
+            .block(Block::default().borders(Borders::ALL).title("Ignore (comma separated, e.g. meta.timestamp, items[*].id, header:date)"))
+            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+        f.set_cursor(area.x + 1 + self.input.visual_cursor() as u16, area.y + 1);
+    }
+}
+
+impl Component for DiffComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let added = self.changes.iter().filter(|change| matches!(change, Change::Added(_))).count();
+        let removed = self.changes.iter().filter(|change| matches!(change, Change::Removed(_))).count();
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Diff +{} -{} (a/A: left, b/B: right, u: {}, i: ignore paths)",
+                added,
+                removed,
+                if self.unified { "side by side" } else { "unified" }
+            ))
+            .style(Style::default().fg(if is_active {
+                Color::Green
+            } else {
+                Color::White
+            }));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        if let Some(status) = &self.status {
+            f.render_widget(Paragraph::new(status.as_str()), inner);
+            return;
+        }
+        if self.sources.len() < 2 {
+            f.render_widget(
+                Paragraph::new("Send at least two requests (or one, with a response showing) to have something to compare."),
+                inner,
+            );
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(inner);
+        let ignoring = if self.ignore.is_empty() { "nothing".to_string() } else { self.ignore.join(", ") };
+        let header = vec![
+            Line::from(Span::styled(format!("- {}", self.sources[self.left].0), Style::default().fg(Color::Red))),
+            Line::from(Span::styled(format!("+ {}", self.sources[self.right].0), Style::default().fg(Color::Green))),
+            Line::from(format!("Ignoring: {}", ignoring)),
+        ];
+        f.render_widget(Paragraph::new(header), chunks[0]);
+
+        if self.unified {
+            let lines: Vec<Line> = self.changes.iter().map(|change| Self::styled(change, true)).collect();
+            f.render_widget(Paragraph::new(lines).scroll((self.scroll_y, 0)), chunks[1]);
+        } else {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .split(chunks[1]);
+            let rows = diff::side_by_side(&self.changes);
+            let column = |pick: fn(&Row) -> &Option<Change>| -> Vec<Line> {
+                rows.iter()
+                    .map(|row| pick(row).as_ref().map(|change| Self::styled(change, false)).unwrap_or_default())
+                    .collect()
+            };
+            f.render_widget(
+                Paragraph::new(column(|row| &row.0))
+                    .block(Block::default().borders(Borders::RIGHT))
+                    .scroll((self.scroll_y, 0)),
+                columns[0],
+            );
+            f.render_widget(Paragraph::new(column(|row| &row.1)).scroll((self.scroll_y, 0)), columns[1]);
+        }
+
+        if self.is_editing {
+            self.draw_modal(f);
+        }
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        if self.is_editing {
+            match key {
+                KeyCode::Enter => {
+                    self.ignore = self
+                        .input
+                        .value()
+                        .split(',')
+                        .map(|path| path.trim().to_string())
+                        .filter(|path| !path.is_empty())
+                        .collect();
+                    self.is_editing = false;
+                    self.refresh();
+                }
+                KeyCode::Esc => {
+                    self.is_editing = false;
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(KeyEvent::new(
+                        key,
+                        crossterm::event::KeyModifiers::NONE,
+                    )));
+                }
+            }
+            return;
+        }
+
+        let count = self.sources.len();
+        let lines = if self.unified { self.changes.len() } else { diff::side_by_side(&self.changes).len() };
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_y = self.scroll_y.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') if (self.scroll_y as usize) + 1 < lines => self.scroll_y += 1,
+            KeyCode::PageUp => self.scroll_y = self.scroll_y.saturating_sub(20),
+            KeyCode::PageDown => self.scroll_y = (self.scroll_y as usize + 20).min(lines.saturating_sub(1)) as u16,
+            KeyCode::Char('a') => {
+                self.left = Self::step(self.left, count, true);
+                self.refresh();
+            }
+            KeyCode::Char('A') => {
+                self.left = Self::step(self.left, count, false);
+                self.refresh();
+            }
+            KeyCode::Char('b') => {
+                self.right = Self::step(self.right, count, true);
+                self.refresh();
+            }
+            KeyCode::Char('B') => {
+                self.right = Self::step(self.right, count, false);
+                self.refresh();
+            }
+            KeyCode::Char('u') => {
+                self.unified = !self.unified;
+                self.scroll_y = 0;
+            }
+            KeyCode::Char('i') => {
+                self.input = Input::from(self.ignore.join(", "));
+                self.is_editing = true;
+            }
+            _ => {}
+        }
+    }
+}
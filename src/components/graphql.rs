@@ -0,0 +1,474 @@
+use std::cell::RefCell;
+
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Frame;
+use crossterm::event::KeyCode;
+
+use crate::components::textarea::TextArea;
+use crate::graphql::{self, Operation, Schema, Suggestion};
+use crate::ui::Component;
+
+// Completion shows at most this many candidates at once.
+const SUGGESTION_ROWS: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Query,
+    Variables,
+    Schema,
+}
+
+// Full-screen editor for the GraphQL body tab: query and variables, checked
+// and completed against the endpoint's schema, and a browser for the schema.
+pub struct GraphqlComponent {
+    pub url: String,
+    pub query: TextArea,
+    pub variables: TextArea,
+    pub focus: Pane,
+    pub schema: Option<Schema>,
+    pub schema_status: String,
+    pub problems: Vec<String>,
+    pub operations: Vec<Operation>,
+    pub selected_operation: usize,
+    pub picking_operation: bool,
+    pub suggestions: Vec<Suggestion>,
+    pub selected_suggestion: usize,
+    // Type names walked through in the schema browser; empty lists all types.
+    pub browser_path: Vec<String>,
+    pub browser_selected: usize,
+    pub browser_state: RefCell<ListState>,
+    // Set by a key; the app picks these up.
+    pub wants_introspection: bool,
+    pub done: bool,
+    leave_anyway: bool,
+}
+
+impl GraphqlComponent {
+    pub fn new() -> Self {
+        Self {
+            url: String::new(),
+            query: TextArea::from(""),
+            variables: TextArea::from(""),
+            focus: Pane::Query,
+            schema: None,
+            schema_status: String::new(),
+            problems: Vec::new(),
+            operations: Vec::new(),
+            selected_operation: 0,
+            picking_operation: false,
+            suggestions: Vec::new(),
+            selected_suggestion: 0,
+            browser_path: Vec::new(),
+            browser_selected: 0,
+            browser_state: RefCell::new(ListState::default()),
+            wants_introspection: false,
+            done: false,
+            leave_anyway: false,
+        }
+    }
+
+    // Picks up the query, variables and operation from a body built earlier.
+    pub fn open(&mut self, url: String, schema: Option<Schema>, body: Option<&str>) {
+        let body: serde_json::Value = body.and_then(|body| serde_json::from_str(body).ok()).unwrap_or_default();
+        let query = body["query"].as_str().unwrap_or("query {\n  \n}");
+        let variables = match &body["variables"] {
+            serde_json::Value::Null => String::new(),
+            variables => serde_json::to_string_pretty(variables).unwrap_or_default(),
+        };
+        self.query = TextArea::from(query);
+        self.variables = TextArea::from(&variables);
+        self.schema_status = match &schema {
+            Some(schema) => format!("{} types, cached (F5 fetches again)", schema.types.len()),
+            None => String::new(),
+        };
+        self.url = url;
+        self.schema = schema;
+        self.focus = Pane::Query;
+        self.suggestions.clear();
+        self.browser_path.clear();
+        self.browser_selected = 0;
+        self.done = false;
+        self.leave_anyway = false;
+        self.refresh();
+        let name = body["operationName"].as_str();
+        self.selected_operation = self
+            .operations
+            .iter()
+            .position(|operation| name.is_some() && operation.name.as_deref() == name)
+            .unwrap_or(0);
+    }
+
+    pub fn set_schema(&mut self, schema: Result<Schema, String>) {
+        match schema {
+            Ok(schema) => {
+                self.schema_status = format!("{} types, fetched just now", schema.types.len());
+                self.schema = Some(schema);
+            }
+            Err(message) => self.schema_status = format!("Introspection failed: {}", message),
+        }
+        self.browser_path.clear();
+        self.browser_selected = 0;
+        self.refresh();
+    }
+
+    // The request body: query, variables and, when there is a choice, the
+    // operation to run.
+    pub fn body(&self) -> Result<String, String> {
+        let operation_name = match self.operations.len() {
+            0 | 1 => None,
+            _ => self
+                .operations
+                .get(self.selected_operation)
+                .and_then(|operation| operation.name.as_deref()),
+        };
+        graphql::request_body(&self.query.value(), &self.variables.value(), operation_name)
+    }
+
+    fn refresh(&mut self) {
+        let query = self.query.value();
+        let empty = Schema::default();
+        self.problems = graphql::validate(&query, self.schema.as_ref().unwrap_or(&empty));
+        let variables = self.variables.value();
+        if !variables.trim().is_empty() {
+            if let Err(e) = serde_json::from_str::<serde_json::Value>(&variables) {
+                self.problems.push(format!("variables: {}", e));
+            }
+        }
+        self.operations = graphql::operations(&query);
+        if self.selected_operation >= self.operations.len() {
+            self.selected_operation = 0;
+        }
+        self.leave_anyway = false;
+    }
+
+    fn suggest(&mut self, always: bool) {
+        self.suggestions.clear();
+        self.selected_suggestion = 0;
+        let Some(schema) = &self.schema else {
+            return;
+        };
+        let query = self.query.value();
+        let offset = self.query.offset();
+        if always || graphql::word_start(&query, offset) < offset {
+            self.suggestions = graphql::complete(&query, offset, schema);
+        }
+    }
+
+    // Rows of the schema browser: the types, or the fields of the type
+    // last picked, each with the type it leads to.
+    fn browser_rows(&self) -> Vec<(String, String, Option<String>)> {
+        let Some(schema) = &self.schema else {
+            return Vec::new();
+        };
+        match self.browser_path.last().and_then(|name| schema.types.get(name)) {
+            None => schema
+                .types
+                .values()
+                .filter(|type_def| !type_def.name.starts_with("__"))
+                .map(|type_def| {
+                    let root = [&schema.query_type, &schema.mutation_type, &schema.subscription_type]
+                        .iter()
+                        .any(|root| root.as_deref() == Some(type_def.name.as_str()));
+                    let label = format!("{}{} ({})", if root { "* " } else { "" }, type_def.name, type_def.kind.to_lowercase());
+                    (label, type_def.description.clone().unwrap_or_default(), Some(type_def.name.clone()))
+                })
+                .collect(),
+            Some(type_def) => {
+                let fields = type_def.fields.iter().map(|field| {
+                    (
+                        field.signature(),
+                        field.description.clone().unwrap_or_default(),
+                        Some(field.type_ref.named().to_string()),
+                    )
+                });
+                let inputs = type_def.input_fields.iter().map(|input| {
+                    (
+                        format!("{}: {}", input.name, input.type_ref.display()),
+                        input.description.clone().unwrap_or_default(),
+                        Some(input.type_ref.named().to_string()),
+                    )
+                });
+                let values = type_def
+                    .enum_values
+                    .iter()
+                    .map(|value| (value.name.clone(), value.description.clone().unwrap_or_default(), None));
+                let possible = type_def
+                    .possible_types
+                    .iter()
+                    .map(|possible| (format!("... on {}", possible.name), String::new(), Some(possible.name.clone())));
+                fields.chain(inputs).chain(values).chain(possible).collect()
+            }
+        }
+    }
+
+    fn draw_browser(&self, f: &mut Frame, area: Rect) {
+        let title = match self.browser_path.last() {
+            Some(name) => format!("Schema: {} (enter: open, backspace: back)", name),
+            None => "Schema (enter: open type)".to_string(),
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(if self.focus == Pane::Schema { Color::Green } else { Color::White }));
+        let rows = self.browser_rows();
+        if rows.is_empty() {
+            let text = if self.schema_status.is_empty() { "No schema yet, F5 fetches it" } else { self.schema_status.as_str() };
+            f.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }).block(block), area);
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(4)].as_ref())
+            .split(area);
+        let items: Vec<ListItem> = rows.iter().map(|(label, _, _)| ListItem::new(label.as_str())).collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">>");
+        self.browser_state.borrow_mut().select(Some(self.browser_selected.min(rows.len() - 1)));
+        f.render_stateful_widget(list, chunks[0], &mut self.browser_state.borrow_mut());
+        let description = rows
+            .get(self.browser_selected)
+            .map(|(_, description, _)| description.as_str())
+            .unwrap_or_default();
+        f.render_widget(
+            Paragraph::new(description)
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title(self.schema_status.as_str())),
+            chunks[1],
+        );
+    }
+
+    fn draw_suggestions(&self, f: &mut Frame, cursor: (u16, u16)) {
+        let size = f.size();
+        let start = self.selected_suggestion.saturating_sub(SUGGESTION_ROWS - 1);
+        let items: Vec<ListItem> = self
+            .suggestions
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(SUGGESTION_ROWS)
+            .map(|(i, suggestion)| {
+                let style = if i == self.selected_suggestion {
+                    Style::default().fg(Color::Black).bg(Color::Yellow)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(suggestion.text.clone(), style),
+                    Span::styled(format!("  {}", suggestion.detail), Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+        let width = self
+            .suggestions
+            .iter()
+            .map(|suggestion| suggestion.text.len() + suggestion.detail.len() + 4)
+            .max()
+            .unwrap_or(10)
+            .min(size.width as usize) as u16;
+        let height = items.len() as u16 + 2;
+        let x = cursor.0.min(size.width.saturating_sub(width));
+        let y = if cursor.1 + 1 + height <= size.height { cursor.1 + 1 } else { cursor.1.saturating_sub(height) };
+        let area = Rect::new(x, y, width, height.min(size.height));
+        f.render_widget(Clear, area);
+        f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL)), area);
+    }
+
+    fn draw_operation_picker(&self, f: &mut Frame) {
+        let size = f.size();
+        let width = 60.min(size.width);
+        let height = (self.operations.len() as u16 + 2).min(size.height);
+        let area = Rect::new((size.width - width) / 2, size.height.saturating_sub(height) / 2, width, height);
+        let items: Vec<ListItem> = self
+            .operations
+            .iter()
+            .map(|operation| ListItem::new(format!("{} (line {})", operation.label(), operation.line)))
+            .collect();
+        let mut state = ListState::default();
+        state.select(Some(self.selected_operation));
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Operation to run"))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">>");
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+impl Component for GraphqlComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "GraphQL {} (tab: next pane, F1: complete, F2: operation, F5: fetch schema, esc: done)",
+                self.url
+            ))
+            .style(Style::default().fg(if is_active { Color::Green } else { Color::White }));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(inner);
+        let editors = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(8), Constraint::Length(5)].as_ref())
+            .split(columns[0]);
+
+        let operation = match (self.operations.len(), self.operations.get(self.selected_operation)) {
+            (0 | 1, _) | (_, None) => String::new(),
+            (count, Some(operation)) => format!(" - runs {} ({} of {})", operation.label(), self.selected_operation + 1, count),
+        };
+        let query_block = Block::default().borders(Borders::ALL).title(format!("Query{}", operation));
+        let cursor = self.query.draw(f, editors[0], query_block, self.focus == Pane::Query);
+        let variables_block = Block::default().borders(Borders::ALL).title("Variables (JSON)");
+        self.variables.draw(f, editors[1], variables_block, self.focus == Pane::Variables);
+
+        let problems: Vec<Line> = if self.problems.is_empty() {
+            vec![Line::from(Span::styled("No problems found", Style::default().fg(Color::Green)))]
+        } else {
+            self.problems
+                .iter()
+                .map(|problem| Line::from(Span::styled(problem.as_str(), Style::default().fg(Color::Red))))
+                .collect()
+        };
+        let problems_title = if self.leave_anyway { "Problems - esc again leaves without saving" } else { "Problems" };
+        f.render_widget(
+            Paragraph::new(problems)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(problems_title)),
+            editors[2],
+        );
+
+        self.draw_browser(f, columns[1]);
+
+        if self.focus == Pane::Query && !self.suggestions.is_empty() {
+            self.draw_suggestions(f, cursor);
+        }
+        if self.picking_operation {
+            self.draw_operation_picker(f);
+        }
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        if self.picking_operation {
+            let count = self.operations.len().max(1);
+            match key {
+                KeyCode::Up => self.selected_operation = (self.selected_operation + count - 1) % count,
+                KeyCode::Down => self.selected_operation = (self.selected_operation + 1) % count,
+                KeyCode::Enter | KeyCode::Esc => self.picking_operation = false,
+                _ => {}
+            }
+            return;
+        }
+
+        if !self.suggestions.is_empty() {
+            let count = self.suggestions.len();
+            match key {
+                KeyCode::Up => {
+                    self.selected_suggestion = (self.selected_suggestion + count - 1) % count;
+                    return;
+                }
+                KeyCode::Down => {
+                    self.selected_suggestion = (self.selected_suggestion + 1) % count;
+                    return;
+                }
+                KeyCode::Tab | KeyCode::Enter => {
+                    let text = self.suggestions[self.selected_suggestion].text.clone();
+                    let start = graphql::word_start(&self.query.value(), self.query.offset());
+                    self.query.replace_to_cursor(start, &text);
+                    self.suggestions.clear();
+                    self.refresh();
+                    return;
+                }
+                KeyCode::Esc => {
+                    self.suggestions.clear();
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        match key {
+            KeyCode::Esc => {
+                // A body with broken variables can't be built; say so once
+                // before throwing the edits away.
+                if self.body().is_err() && !self.leave_anyway {
+                    self.leave_anyway = true;
+                } else {
+                    self.done = true;
+                }
+                return;
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                let panes = [Pane::Query, Pane::Variables, Pane::Schema];
+                let i = panes.iter().position(|pane| *pane == self.focus).unwrap_or(0);
+                let step = if key == KeyCode::Tab { 1 } else { panes.len() - 1 };
+                self.focus = panes[(i + step) % panes.len()];
+                return;
+            }
+            KeyCode::F(1) if self.focus == Pane::Query => {
+                self.suggest(true);
+                return;
+            }
+            KeyCode::F(2) if self.operations.len() > 1 => {
+                self.picking_operation = true;
+                return;
+            }
+            KeyCode::F(5) => {
+                self.wants_introspection = true;
+                self.schema_status = "Fetching the schema...".to_string();
+                return;
+            }
+            _ => {}
+        }
+
+        match self.focus {
+            Pane::Query => {
+                self.query.keybinds(key);
+                self.refresh();
+                if matches!(key, KeyCode::Char(_) | KeyCode::Backspace) {
+                    self.suggest(false);
+                } else {
+                    self.suggestions.clear();
+                }
+            }
+            Pane::Variables => {
+                self.variables.keybinds(key);
+                self.refresh();
+            }
+            Pane::Schema => {
+                let rows = self.browser_rows();
+                match key {
+                    KeyCode::Up | KeyCode::Char('k') => self.browser_selected = self.browser_selected.saturating_sub(1),
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.browser_selected = (self.browser_selected + 1).min(rows.len().saturating_sub(1))
+                    }
+                    KeyCode::Enter => {
+                        let target = rows.get(self.browser_selected).and_then(|(_, _, target)| target.clone());
+                        let known = target
+                            .as_ref()
+                            .zip(self.schema.as_ref())
+                            .is_some_and(|(target, schema)| schema.types.contains_key(target));
+                        if let (true, Some(target)) = (known, target) {
+                            self.browser_path.push(target);
+                            self.browser_selected = 0;
+                        }
+                    }
+                    KeyCode::Backspace | KeyCode::Char('h') => {
+                        self.browser_path.pop();
+                        self.browser_selected = 0;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
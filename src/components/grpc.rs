@@ -0,0 +1,294 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use prost_reflect::{DescriptorPool, MethodDescriptor};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::components::textarea::TextArea;
+use crate::grpc;
+use crate::ui::Component;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Methods,
+    Message,
+}
+
+// Full-screen view for gRPC: the methods from server reflection or
+// `.proto` files, and the request message for the chosen one as JSON.
+pub struct GrpcComponent {
+    pub url: String,
+    pub methods: Vec<MethodDescriptor>,
+    // Highlighted in the list.
+    pub selected: usize,
+    // What `g` calls.
+    pub method: Option<MethodDescriptor>,
+    pub message: TextArea,
+    // Messages typed for other methods, so going back to one keeps it.
+    pub messages: HashMap<String, String>,
+    pub problem: Option<String>,
+    // Where the methods came from, or why they couldn't be loaded.
+    pub source: String,
+    pub protos: Vec<String>,
+    pub input: Input,
+    pub is_editing: bool,
+    pub focus: Pane,
+    pub list_state: RefCell<ListState>,
+    pub wants_reflection: bool,
+    pub done: bool,
+}
+
+impl GrpcComponent {
+    pub fn new() -> Self {
+        Self {
+            url: String::new(),
+            methods: Vec::new(),
+            selected: 0,
+            method: None,
+            message: TextArea::from(""),
+            messages: HashMap::new(),
+            problem: None,
+            source: "No services loaded (F3: .proto files, F5: server reflection)".to_string(),
+            protos: Vec::new(),
+            input: Input::default(),
+            is_editing: false,
+            focus: Pane::Methods,
+            list_state: RefCell::new(ListState::default()),
+            wants_reflection: false,
+            done: false,
+        }
+    }
+
+    pub fn open(&mut self, url: String) {
+        self.url = url;
+        self.done = false;
+        if self.methods.is_empty() && !self.protos.is_empty() {
+            self.load_protos();
+        }
+    }
+
+    pub fn load_protos(&mut self) {
+        let loaded = grpc::load_protos(&self.protos).map_err(|err| err.to_string());
+        self.set_pool(loaded, format!("From {}", self.protos.join(", ")));
+    }
+
+    pub fn set_pool(&mut self, pool: Result<DescriptorPool, String>, source: String) {
+        let pool = match pool {
+            Ok(pool) => pool,
+            Err(err) => {
+                self.source = format!("Could not load services: {}", err);
+                return;
+            }
+        };
+        self.methods = grpc::methods(&pool);
+        self.source = format!("{} ({} methods)", source, self.methods.len());
+        // The chosen method is looked up again, its descriptors may have
+        // changed with the new definitions.
+        let chosen = self.method.as_ref().map(grpc::method_name);
+        self.method = None;
+        self.selected = 0;
+        if let Some(i) = chosen.and_then(|name| self.methods.iter().position(|method| grpc::method_name(method) == name)) {
+            self.selected = i;
+            self.method = Some(self.methods[i].clone());
+            self.check();
+        }
+    }
+
+    pub fn label(&self) -> Option<String> {
+        self.method
+            .as_ref()
+            .map(|method| format!("{}/{}", method.parent_service().name(), method.name()))
+    }
+
+    fn choose(&mut self, i: usize) {
+        let Some(method) = self.methods.get(i).cloned() else {
+            return;
+        };
+        self.stash();
+        let text = self
+            .messages
+            .get(&grpc::method_name(&method))
+            .cloned()
+            .unwrap_or_else(|| grpc::template(&method.input()));
+        self.message = TextArea::from(&text);
+        self.method = Some(method);
+        self.focus = Pane::Message;
+        self.check();
+    }
+
+    fn stash(&mut self) {
+        if let Some(method) = &self.method {
+            self.messages.insert(grpc::method_name(method), self.message.value());
+        }
+    }
+
+    fn check(&mut self) {
+        self.problem = self
+            .method
+            .as_ref()
+            .and_then(|method| grpc::parse_message(&method.input(), &self.message.value()).err());
+    }
+
+    fn draw_modal(&self, f: &mut Frame) {
+        let size = f.size();
+        let modal_width = 80.min(size.width);
+        let modal_height = 3;
+        let area = Rect::new(
+            (size.width - modal_width) / 2,
+            (size.height.saturating_sub(modal_height)) / 2,
+            modal_width,
+            modal_height,
+        );
+
+        let paragraph = Paragraph::new(self.input.value())
+            .block(Block::default().borders(Borders::ALL).title(".proto files or folders (comma separated)"))
+            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+        f.set_cursor(area.x + 1 + self.input.visual_cursor() as u16, area.y + 1);
+    }
+}
+
+impl Component for GrpcComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "gRPC {} (tab: next pane, enter: pick method, F3: .proto files, F5: server reflection, esc: done)",
+                self.url
+            ))
+            .style(Style::default().fg(if is_active { Color::Green } else { Color::White }));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+            .split(inner);
+        f.render_widget(
+            Paragraph::new(Span::styled(self.source.as_str(), Style::default().fg(Color::Yellow))),
+            chunks[0],
+        );
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(chunks[1]);
+        let chosen = self.method.as_ref().map(grpc::method_name);
+        let items: Vec<ListItem> = self
+            .methods
+            .iter()
+            .map(|method| {
+                let name = grpc::method_name(method);
+                let marker = if chosen.as_ref() == Some(&name) { "* " } else { "  " };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{}{} ", marker, name)),
+                    Span::styled(grpc::streaming(method), Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Methods"))
+            .style(Style::default().fg(if self.focus == Pane::Methods { Color::Yellow } else { Color::White }))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED))
+            .highlight_symbol(">>");
+        self.list_state
+            .borrow_mut()
+            .select(if self.methods.is_empty() { None } else { Some(self.selected) });
+        f.render_stateful_widget(list, columns[0], &mut self.list_state.borrow_mut());
+
+        let editors = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(4)].as_ref())
+            .split(columns[1]);
+        let title = match &self.method {
+            Some(method) => format!("Request {} (JSON)", method.input().full_name()),
+            None => "Request (pick a method first)".to_string(),
+        };
+        self.message.draw(
+            f,
+            editors[0],
+            Block::default().borders(Borders::ALL).title(title),
+            self.focus == Pane::Message && !self.is_editing,
+        );
+        let problem = match &self.problem {
+            Some(problem) => Span::styled(problem.as_str(), Style::default().fg(Color::Red)),
+            None => Span::styled("No problems found", Style::default().fg(Color::Green)),
+        };
+        f.render_widget(
+            Paragraph::new(Line::from(problem))
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title("Problems")),
+            editors[1],
+        );
+
+        if self.is_editing {
+            self.draw_modal(f);
+        }
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        if self.is_editing {
+            match key {
+                KeyCode::Enter => {
+                    self.protos = self
+                        .input
+                        .value()
+                        .split(',')
+                        .map(|path| path.trim().to_string())
+                        .filter(|path| !path.is_empty())
+                        .collect();
+                    self.is_editing = false;
+                    self.load_protos();
+                }
+                KeyCode::Esc => {
+                    self.is_editing = false;
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(KeyEvent::new(
+                        key,
+                        crossterm::event::KeyModifiers::NONE,
+                    )));
+                }
+            }
+            return;
+        }
+
+        match key {
+            KeyCode::Esc => {
+                self.stash();
+                self.done = true;
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = if self.focus == Pane::Methods { Pane::Message } else { Pane::Methods };
+            }
+            KeyCode::F(3) => {
+                self.input = Input::from(self.protos.join(", "));
+                self.is_editing = true;
+            }
+            KeyCode::F(5) => self.wants_reflection = true,
+            _ if self.focus == Pane::Methods => match key {
+                KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.selected = (self.selected + 1).min(self.methods.len().saturating_sub(1))
+                }
+                KeyCode::Enter => self.choose(self.selected),
+                _ => {}
+            },
+            _ if self.method.is_some() => {
+                self.message.keybinds(key);
+                self.check();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::cell::RefCell;
+
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::components::textarea::TextArea;
+use crate::jsonrpc::{self, Call};
+use crate::ui::Component;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Calls,
+    Method,
+    Params,
+}
+
+// Full-screen editor for the JSON-RPC body tab: a method and params per
+// call, more than one call making a batch. Ids and `jsonrpc` are filled in
+// when the request is sent.
+pub struct JsonRpcComponent {
+    pub calls: Vec<Call>,
+    pub selected: usize,
+    pub method: Input,
+    pub params: TextArea,
+    pub focus: Pane,
+    pub problem: Option<String>,
+    pub list_state: RefCell<ListState>,
+    pub leave_anyway: bool,
+    pub done: bool,
+}
+
+impl JsonRpcComponent {
+    pub fn new() -> Self {
+        Self {
+            calls: vec![Call::new()],
+            selected: 0,
+            method: Input::default(),
+            params: TextArea::from(""),
+            focus: Pane::Method,
+            problem: None,
+            list_state: RefCell::new(ListState::default()),
+            leave_anyway: false,
+            done: false,
+        }
+    }
+
+    pub fn open(&mut self, body: Option<&str>) {
+        self.calls = body
+            .and_then(|body| jsonrpc::parse_calls(body).ok())
+            .unwrap_or_else(|| vec![Call::new()]);
+        self.load(0);
+        self.focus = Pane::Method;
+        self.leave_anyway = false;
+        self.done = false;
+        self.check();
+    }
+
+    // The body as stored in the tab; the ids in it are only placeholders.
+    pub fn body(&self) -> Result<String, String> {
+        jsonrpc::request_body(&self.calls, &mut 1).map(|(body, _)| body)
+    }
+
+    fn load(&mut self, i: usize) {
+        self.selected = i;
+        self.method = Input::from(self.calls[i].method.clone());
+        self.params = TextArea::from(&self.calls[i].params);
+    }
+
+    fn store(&mut self) {
+        let call = &mut self.calls[self.selected];
+        call.method = self.method.value().to_string();
+        call.params = self.params.value();
+    }
+
+    fn check(&mut self) {
+        self.problem = self.body().err();
+        self.leave_anyway = false;
+    }
+}
+
+impl Component for JsonRpcComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("JSON-RPC (tab: next pane, F2: add call, F3: remove call, F4: notification, esc: done)")
+            .style(Style::default().fg(if is_active { Color::Green } else { Color::White }));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+            .split(inner);
+
+        let items: Vec<ListItem> = self
+            .calls
+            .iter()
+            .map(|call| {
+                let method = if call.method.trim().is_empty() { "(no method)" } else { call.method.as_str() };
+                let mut spans = vec![Span::raw(method.to_string())];
+                if call.notification {
+                    spans.push(Span::styled(" notification", Style::default().fg(Color::DarkGray)));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let title = if self.calls.len() > 1 { format!("Batch of {} calls", self.calls.len()) } else { "Call".to_string() };
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .style(Style::default().fg(if self.focus == Pane::Calls { Color::Yellow } else { Color::White }))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED))
+            .highlight_symbol(">>");
+        self.list_state.borrow_mut().select(Some(self.selected));
+        f.render_stateful_widget(list, columns[0], &mut self.list_state.borrow_mut());
+
+        let editors = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(4)].as_ref())
+            .split(columns[1]);
+        let method_style = if self.focus == Pane::Method { Style::default().fg(Color::Yellow) } else { Style::default() };
+        f.render_widget(
+            Paragraph::new(self.method.value())
+                .style(method_style)
+                .block(Block::default().borders(Borders::ALL).title("Method")),
+            editors[0],
+        );
+        if self.focus == Pane::Method {
+            f.set_cursor(editors[0].x + 1 + self.method.visual_cursor() as u16, editors[0].y + 1);
+        }
+        let kind = if self.calls[self.selected].notification { "notification, no reply" } else { "call" };
+        self.params.draw(
+            f,
+            editors[1],
+            Block::default().borders(Borders::ALL).title(format!("Params (JSON array or object, empty for none) - {}", kind)),
+            self.focus == Pane::Params,
+        );
+
+        let problem = match &self.problem {
+            Some(problem) => Span::styled(problem.as_str(), Style::default().fg(Color::Red)),
+            None => Span::styled("No problems found", Style::default().fg(Color::Green)),
+        };
+        let problems_title = if self.leave_anyway { "Problems - esc again leaves without saving" } else { "Problems" };
+        f.render_widget(
+            Paragraph::new(Line::from(problem))
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(problems_title)),
+            editors[2],
+        );
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc => {
+                self.store();
+                // A body that can't be built would be thrown away; say so
+                // once first.
+                if self.body().is_err() && !self.leave_anyway {
+                    self.check();
+                    self.leave_anyway = true;
+                } else {
+                    self.done = true;
+                }
+                return;
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                let panes = [Pane::Calls, Pane::Method, Pane::Params];
+                let i = panes.iter().position(|pane| *pane == self.focus).unwrap_or(0);
+                let step = if key == KeyCode::Tab { 1 } else { panes.len() - 1 };
+                self.focus = panes[(i + step) % panes.len()];
+                return;
+            }
+            KeyCode::F(2) => {
+                self.store();
+                self.calls.insert(self.selected + 1, Call::new());
+                self.load(self.selected + 1);
+                self.focus = Pane::Method;
+            }
+            KeyCode::F(3) if self.calls.len() > 1 => {
+                self.calls.remove(self.selected);
+                self.load(self.selected.min(self.calls.len() - 1));
+            }
+            KeyCode::F(4) => {
+                self.calls[self.selected].notification = !self.calls[self.selected].notification;
+            }
+            _ => match self.focus {
+                Pane::Calls => match key {
+                    KeyCode::Up | KeyCode::Char('k') if self.selected > 0 => {
+                        self.store();
+                        self.load(self.selected - 1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < self.calls.len() => {
+                        self.store();
+                        self.load(self.selected + 1);
+                    }
+                    KeyCode::Enter => self.focus = Pane::Method,
+                    _ => {}
+                },
+                Pane::Method => match key {
+                    KeyCode::Enter => self.focus = Pane::Params,
+                    _ => {
+                        self.method.handle_event(&Event::Key(KeyEvent::new(
+                            key,
+                            crossterm::event::KeyModifiers::NONE,
+                        )));
+                    }
+                },
+                Pane::Params => self.params.keybinds(key),
+            },
+        }
+        self.store();
+        self.check();
+    }
+}
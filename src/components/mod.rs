@@ -3,6 +3,18 @@ pub mod output;
 pub mod selector;
 pub mod requesthea;
 pub mod history;
+pub mod settings;
+pub mod cookies;
+pub mod prompt;
+pub mod diff;
+pub mod websocket;
+pub mod sse;
+pub mod graphql;
+pub mod grpc;
+pub mod jsonrpc;
+pub mod soap;
+pub mod raw;
+pub mod textarea;
 
 
 
@@ -11,5 +23,16 @@ pub use output::OutputComponent;
 pub use selector::SelectorComponent;
 pub use requesthea::RequestComponent;
 pub use history::HistoryComponent;
+pub use settings::SettingsComponent;
+pub use cookies::CookiesComponent;
+pub use prompt::PromptComponent;
+pub use diff::DiffComponent;
+pub use websocket::WebSocketComponent;
+pub use sse::SseComponent;
+pub use graphql::GraphqlComponent;
+pub use grpc::GrpcComponent;
+pub use jsonrpc::JsonRpcComponent;
+pub use soap::SoapComponent;
+pub use raw::RawComponent;
 
 
@@ -1,17 +1,93 @@
 use ratatui::backend::Backend;
 use ratatui::layout::Rect;
 use ratatui::widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState};
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
 use ratatui::Frame;
 use crossterm::event::KeyCode;
+use std::cell::Cell;
 use std::fs::File;
 use std::io::Write;
 use std::process::Command;
 
+use crate::jsonrpc::{self, Outcome};
+use crate::preview::{self, Graphics, Preview};
+use crate::request::Response;
+use crate::soap;
+use crate::stream::Progress;
 use crate::ui::Component;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputTab {
+    Body,
+    Info,
+    Redirects,
+    Verbose,
+    Tls,
+}
+
+impl OutputTab {
+    pub fn all_tabs() -> Vec<OutputTab> {
+        vec![OutputTab::Body, OutputTab::Info, OutputTab::Redirects, OutputTab::Verbose, OutputTab::Tls]
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            OutputTab::Body => "Body",
+            OutputTab::Info => "Info",
+            OutputTab::Redirects => "Redirects",
+            OutputTab::Verbose => "Verbose",
+            OutputTab::Tls => "TLS",
+        }
+    }
+}
+
+// How the body of a response is shown in the Body tab.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BodyView {
+    Text,
+    Hex,
+    Base64,
+    Image,
+    JsonRpc,
+    Soap,
+}
+
+impl BodyView {
+    pub fn all_views() -> Vec<BodyView> {
+        vec![BodyView::Text, BodyView::Hex, BodyView::Base64, BodyView::Image, BodyView::JsonRpc, BodyView::Soap]
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            BodyView::Text => "text",
+            BodyView::Hex => "hex",
+            BodyView::Base64 => "base64",
+            BodyView::Image => "image",
+            BodyView::JsonRpc => "JSON-RPC",
+            BodyView::Soap => "SOAP",
+        }
+    }
+}
+
 pub struct OutputComponent {
     pub message: String,
+    pub response: Option<Response>,
+    pub tls_report: Option<String>,
+    pub receiving: Option<u64>,
+    pub download_status: Option<String>,
+    pub body_view: BodyView,
+    pub preview: Option<Preview>,
+    pub graphics: Graphics,
+    // Ids and methods of the JSON-RPC calls the response answers, if the
+    // request was made from the JSON-RPC tab.
+    pub jsonrpc: Option<Vec<(u64, String)>>,
+    // Where the last frame wanted a terminal-drawn image, and where one is
+    // actually on screen (with the response it belongs to).
+    pub image_area: Cell<Option<Rect>>,
+    shown_image: Option<(Rect, u64)>,
+    generation: u64,
+    pub selected_tab: usize,
     pub scroll_x: u16,
     pub scroll_y: u16,
 }
@@ -20,11 +96,204 @@ impl OutputComponent {
     pub fn new() -> Self {
         Self {
             message: String::new(),
+            response: None,
+            tls_report: None,
+            receiving: None,
+            download_status: None,
+            body_view: BodyView::Text,
+            preview: None,
+            graphics: Graphics::detect(),
+            jsonrpc: None,
+            image_area: Cell::new(None),
+            shown_image: None,
+            generation: 0,
+            selected_tab: 0,
             scroll_x: 0,
             scroll_y: 0,
         }
     }
 
+    pub fn start_stream(&mut self) {
+        self.message = String::new();
+        self.download_status = None;
+        self.response = None;
+        self.clear_body_view();
+        self.receiving = Some(0);
+        self.selected_tab = 0;
+        self.scroll_y = 0;
+    }
+
+    pub fn push_stream(&mut self, progress: Progress) {
+        self.message.push_str(&progress.text);
+        self.receiving = Some(progress.received);
+    }
+
+    // The preview is decoded off the UI thread, together with the request.
+    pub fn set_response(&mut self, response: Response, preview: Option<Preview>) {
+        // Binary bodies only turn into garbage as text.
+        self.preview = preview;
+        self.generation += 1;
+        self.body_view = if self.preview.is_some() {
+            BodyView::Image
+        } else if self.replies(&response).is_some() {
+            BodyView::JsonRpc
+        } else if soap::reply(&response.body).is_some() {
+            BodyView::Soap
+        } else if response.is_binary() {
+            BodyView::Hex
+        } else {
+            BodyView::Text
+        };
+        self.message = self.render_body(&response, self.body_view);
+        self.receiving = None;
+        self.response = Some(response);
+        self.scroll_y = 0;
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.message = error;
+        self.receiving = None;
+        self.response = None;
+        self.clear_body_view();
+        self.scroll_y = 0;
+    }
+
+    // For a request whose answer is shown somewhere else, like an event
+    // stream.
+    pub fn clear(&mut self) {
+        self.set_error(String::new());
+    }
+
+    // Drops what belonged to the previous response so its image doesn't stay
+    // on screen.
+    fn clear_body_view(&mut self) {
+        self.preview = None;
+        self.body_view = BodyView::Text;
+        self.generation += 1;
+    }
+
+    pub fn set_tls_report(&mut self, report: String) {
+        self.tls_report = Some(report);
+        self.selected_tab = OutputTab::all_tabs().iter().position(|tab| *tab == OutputTab::Tls).unwrap_or(0);
+        self.scroll_y = 0;
+    }
+
+    fn render_body(&self, response: &Response, view: BodyView) -> String {
+        match (view, &self.preview) {
+            (BodyView::Image, Some(preview)) => preview.describe(),
+            (BodyView::Hex, _) | (BodyView::Image, None) => response.render_hex(),
+            (BodyView::Base64, _) => response.render_base64(),
+            (BodyView::Text, _) => response.render(),
+            (BodyView::JsonRpc | BodyView::Soap, _) => self
+                .styled_lines(response, view)
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    // The views drawn with colors rather than as plain text.
+    fn styled_lines(&self, response: &Response, view: BodyView) -> Vec<Line<'static>> {
+        match view {
+            BodyView::JsonRpc => self.jsonrpc_lines(response),
+            BodyView::Soap => soap_lines(response),
+            _ => Vec::new(),
+        }
+    }
+
+    fn replies(&self, response: &Response) -> Option<Vec<jsonrpc::Reply>> {
+        jsonrpc::replies(&response.body, self.jsonrpc.as_ref()?)
+    }
+
+    // Each reply under a line naming the call, green for a result and red
+    // for an error.
+    fn jsonrpc_lines(&self, response: &Response) -> Vec<Line<'static>> {
+        let pretty = |value: &serde_json::Value| serde_json::to_string_pretty(value).unwrap_or_default();
+        let mut lines = Vec::new();
+        for reply in self.replies(response).unwrap_or_default() {
+            let call = format!("#{} {}", reply.id, reply.method.as_deref().unwrap_or("?"));
+            let (heading, color, detail) = match &reply.outcome {
+                Outcome::Result(result) => (format!("{} - result", call), Color::Green, pretty(result)),
+                Outcome::Error { code, message, data } => (
+                    format!("{} - error {}: {}", call, code, message),
+                    Color::Red,
+                    data.as_ref().map(|data| format!("data: {}", pretty(data))).unwrap_or_default(),
+                ),
+                Outcome::Invalid(reply) => (format!("{} - not a valid reply", call), Color::Yellow, pretty(reply)),
+                Outcome::Missing => (format!("{} - no reply", call), Color::Yellow, String::new()),
+            };
+            lines.push(Line::from(Span::styled(heading, Style::default().fg(color).add_modifier(Modifier::BOLD))));
+            lines.extend(detail.lines().map(|line| Line::from(format!("  {}", line))));
+            lines.push(Line::from(""));
+        }
+        lines
+    }
+
+    // Puts the image on screen with the terminal's graphics protocol once
+    // the frame around it is drawn, or takes it away. Returns true when the
+    // whole screen has to be redrawn first to get rid of an old image.
+    pub fn flush_graphics(&mut self, out: &mut impl Write) -> std::io::Result<bool> {
+        let wanted = self.image_area.get().map(|area| (area, self.generation));
+        if wanted == self.shown_image {
+            return Ok(false);
+        }
+        if self.shown_image.is_some() {
+            if self.graphics != Graphics::Kitty {
+                // Sixel pixels stay until the cells under them are written,
+                // the new image (if any) goes out after the redraw.
+                self.shown_image = None;
+                return Ok(true);
+            }
+            out.write_all(preview::kitty_clear())?;
+        }
+        if let (Some((area, _)), Some(preview)) = (wanted, &self.preview) {
+            if let Some(image) = preview.encode(self.graphics, area) {
+                crossterm::queue!(out, crossterm::cursor::MoveTo(area.x, area.y))?;
+                out.write_all(&image)?;
+            }
+        }
+        out.flush()?;
+        self.shown_image = wanted;
+        Ok(false)
+    }
+
+    fn cycle_body_view(&mut self) {
+        let Some(response) = &self.response else {
+            return;
+        };
+        let views: Vec<BodyView> = BodyView::all_views()
+            .into_iter()
+            .filter(|view| *view != BodyView::Image || self.preview.is_some())
+            .filter(|view| *view != BodyView::JsonRpc || self.replies(response).is_some())
+            .filter(|view| *view != BodyView::Soap || soap::reply(&response.body).is_some())
+            .collect();
+        let current = views.iter().position(|view| *view == self.body_view).unwrap_or(0);
+        self.body_view = views[(current + 1) % views.len()];
+        self.message = self.render_body(response, self.body_view);
+        self.scroll_y = 0;
+    }
+
+    fn tab(&self) -> OutputTab {
+        OutputTab::all_tabs()[self.selected_tab]
+    }
+
+    // Text shown for the selected tab; everything but the body needs a
+    // response to describe.
+    fn content(&self) -> String {
+        match (self.tab(), &self.response) {
+            (OutputTab::Body, _) => self.message.clone(),
+            (OutputTab::Info, Some(response)) => response.render_info(),
+            (OutputTab::Redirects, Some(response)) => response.render_redirects(),
+            (OutputTab::Verbose, Some(response)) => response.wire.clone(),
+            (OutputTab::Tls, _) => self
+                .tls_report
+                .clone()
+                .unwrap_or_else(|| "Press t on the Input block to inspect the certificate chain of an https URL".to_string()),
+            (_, None) => String::new(),
+        }
+    }
+
     fn save_message_to_file(&self) -> std::io::Result<String> {
         let file_path = "/tmp/output.json";
         let mut file = File::create(file_path)?;
@@ -41,27 +310,100 @@ impl OutputComponent {
     }
 }
 
+// The envelope re-indented, with the fault (if it is one) picked out in red
+// above it.
+fn soap_lines(response: &Response) -> Vec<Line<'static>> {
+    let Some(reply) = soap::reply(&response.body) else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    if let Some(fault) = &reply.fault {
+        lines.push(Line::from(Span::styled(
+            format!("{} fault {}: {}", reply.version.to_string(), fault.code, fault.reason),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+        if let Some(actor) = &fault.actor {
+            lines.push(Line::from(format!("  actor: {}", actor)));
+        }
+        if let Some(detail) = &fault.detail {
+            lines.push(Line::from("  detail:"));
+            lines.extend(detail.lines().map(|line| Line::from(format!("    {}", line))));
+        }
+        lines.push(Line::from(""));
+    }
+    lines.extend(reply.pretty.lines().map(|line| Line::from(line.to_string())));
+    lines
+}
+
 impl Component for OutputComponent {
     fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let mut title_spans = vec![Span::raw(match self.receiving {
+            Some(received) => format!("Message (receiving, {} bytes) - ", received),
+            None => "Message - ".to_string(),
+        })];
+        for (i, tab) in OutputTab::all_tabs().iter().enumerate() {
+            let style = if i == self.selected_tab {
+                Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let label = match (tab, &self.response) {
+                (OutputTab::Body, Some(_)) => format!("Body ({})", self.body_view.to_string()),
+                _ => tab.to_string().to_string(),
+            };
+            title_spans.push(Span::styled(label, style));
+            title_spans.push(Span::raw(" "));
+        }
+        if let Some(status) = &self.download_status {
+            title_spans.push(Span::styled(format!("- {} ", status), Style::default().fg(Color::Yellow)));
+        }
+
+        let content = self.content();
         let block = Block::default()
             .borders(Borders::ALL)
-            .title("Message")
+            .title(Line::from(title_spans))
             .style(Style::default().fg(if is_active {
                 Color::Green
             } else {
                 Color::White
             }));
         
-        let paragraph = Paragraph::new(self.message.clone())
-            .block(block)
-            .style(Style::default().fg(Color::White))
-            .scroll((self.scroll_y, 0));
+        let paragraph = match (self.tab(), self.body_view, &self.preview) {
+            (OutputTab::Body, BodyView::Image, Some(preview)) => {
+                let inner = block.inner(area);
+                let image_area = Rect::new(inner.x, inner.y + 1, inner.width, inner.height.saturating_sub(1));
+                let mut lines = vec![Line::from(preview.describe())];
+                if image_area.area() > 0 {
+                    if self.graphics == Graphics::HalfBlocks {
+                        lines.extend(preview.half_blocks(image_area));
+                    } else {
+                        self.image_area.set(Some(image_area));
+                    }
+                }
+                Paragraph::new(lines).block(block).style(Style::default().fg(Color::White))
+            }
+            (OutputTab::Body, BodyView::JsonRpc | BodyView::Soap, _) if self.response.is_some() => {
+                let lines = self
+                    .response
+                    .as_ref()
+                    .map(|response| self.styled_lines(response, self.body_view))
+                    .unwrap_or_default();
+                Paragraph::new(lines)
+                    .block(block)
+                    .style(Style::default().fg(Color::White))
+                    .scroll((self.scroll_y, 0))
+            }
+            _ => Paragraph::new(content.clone())
+                .block(block)
+                .style(Style::default().fg(Color::White))
+                .scroll((self.scroll_y, 0)),
+        };
         
         let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
             .begin_symbol(Some("↑"))
             .end_symbol(Some("↓"));
         
-        let mut scrollbar_state = ScrollbarState::new(self.message.lines().count())
+        let mut scrollbar_state = ScrollbarState::new(content.lines().count())
             .position(self.scroll_y as usize);
         
         f.render_widget(paragraph, area);
@@ -76,7 +418,7 @@ impl Component for OutputComponent {
     }
 
     fn keybinds(&mut self, key: KeyCode) {
-        let max_scroll_y = self.message.lines().count().saturating_sub(1) as u16;
+        let max_scroll_y = self.content().lines().count().saturating_sub(1) as u16;
         match key {
             KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('K') => {
                 if self.scroll_y > 0 {
@@ -96,6 +438,16 @@ impl Component for OutputComponent {
             KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('L') => {
                 self.scroll_x += 1;
             }
+            KeyCode::Char('[') => {
+                let count = OutputTab::all_tabs().len();
+                self.selected_tab = (self.selected_tab + count - 1) % count;
+                self.scroll_y = 0;
+            }
+            KeyCode::Char(']') => {
+                self.selected_tab = (self.selected_tab + 1) % OutputTab::all_tabs().len();
+                self.scroll_y = 0;
+            }
+            KeyCode::Char('v') if self.tab() == OutputTab::Body => self.cycle_body_view(),
             KeyCode::Char('e') => {
                 if let Err(e) = self.open_in_editor() {
                     eprintln!("Failed to open editor: {}", e);
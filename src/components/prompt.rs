@@ -0,0 +1,89 @@
+use ratatui::backend::Backend;
+use ratatui::layout::Rect;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::ui::Component;
+
+// A one-line question drawn over the rest of the UI. The answer is left in
+// `submitted` for whoever opened the prompt to pick up.
+pub struct PromptComponent {
+    pub title: String,
+    pub input: Input,
+    pub masked: bool,
+    pub show_modal: bool,
+    pub submitted: Option<String>,
+}
+
+impl PromptComponent {
+    pub fn new() -> Self {
+        Self {
+            title: String::new(),
+            input: Input::default(),
+            masked: false,
+            show_modal: false,
+            submitted: None,
+        }
+    }
+
+    pub fn open(&mut self, title: String, value: String, masked: bool) {
+        self.title = title;
+        self.input = Input::from(value);
+        self.masked = masked;
+        self.show_modal = true;
+        self.submitted = None;
+    }
+}
+
+impl Component for PromptComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, _area: Rect, _is_active: bool) {
+        if !self.show_modal {
+            return;
+        }
+
+        let size = f.size();
+        let modal_width = 80.min(size.width);
+        let modal_height = 3;
+        let area = Rect::new(
+            (size.width - modal_width) / 2,
+            (size.height.saturating_sub(modal_height)) / 2,
+            modal_width,
+            modal_height,
+        );
+
+        let value = if self.masked {
+            "*".repeat(self.input.value().chars().count())
+        } else {
+            self.input.value().to_string()
+        };
+        let paragraph = Paragraph::new(value)
+            .block(Block::default().borders(Borders::ALL).title(self.title.clone()))
+            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+        f.set_cursor(area.x + 1 + self.input.visual_cursor() as u16, area.y + 1);
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Enter => {
+                self.submitted = Some(self.input.value().to_string());
+                self.show_modal = false;
+            }
+            KeyCode::Esc => {
+                self.show_modal = false;
+            }
+            _ => {
+                self.input.handle_event(&Event::Key(KeyEvent::new(
+                    key,
+                    crossterm::event::KeyModifiers::NONE,
+                )));
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+use ratatui::Frame;
+use crossterm::event::KeyCode;
+
+use crate::components::textarea::TextArea;
+use crate::raw::{self, Exchange};
+use crate::ui::Component;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Request,
+    Raw,
+    Parsed,
+}
+
+// Full-screen view for raw HTTP/1.1: the request is edited as text and
+// written to the socket as is, the response shows as the bytes that came
+// back next to a lenient parse of them.
+pub struct RawComponent {
+    // Where the request goes, or why the URL can't be used.
+    pub target: String,
+    pub editor: TextArea,
+    pub focus: Pane,
+    pub exchange: Option<Exchange>,
+    pub status: String,
+    pub hex: bool,
+    // Rendered once per exchange rather than on every frame.
+    raw_text: String,
+    parsed_text: String,
+    pub raw_scroll: u16,
+    pub parsed_scroll: u16,
+    pub wants_send: bool,
+    pub wants_template: bool,
+    pub done: bool,
+}
+
+impl RawComponent {
+    pub fn new() -> Self {
+        Self {
+            target: String::new(),
+            editor: TextArea::from(""),
+            focus: Pane::Request,
+            exchange: None,
+            status: "Nothing sent yet (F5: send)".to_string(),
+            hex: false,
+            raw_text: String::new(),
+            parsed_text: String::new(),
+            raw_scroll: 0,
+            parsed_scroll: 0,
+            wants_send: false,
+            wants_template: false,
+            done: false,
+        }
+    }
+
+    pub fn open(&mut self, target: String) {
+        self.target = target;
+        self.done = false;
+    }
+
+    pub fn set_template(&mut self, text: &str) {
+        self.editor = TextArea::from(text);
+        self.focus = Pane::Request;
+    }
+
+    pub fn request(&self) -> Result<Vec<u8>, String> {
+        raw::encode(&self.editor.value())
+    }
+
+    pub fn set_exchange(&mut self, exchange: Result<Exchange, String>) {
+        match exchange {
+            Ok(exchange) => {
+                self.status = format!(
+                    "Sent {} bytes, received {} bytes in {} ms; {}",
+                    exchange.sent.len(),
+                    exchange.received.len(),
+                    exchange.elapsed.as_millis(),
+                    exchange.ended
+                );
+                self.exchange = Some(exchange);
+            }
+            Err(err) => {
+                self.status = format!("Error: {}", err);
+                self.exchange = None;
+            }
+        }
+        self.render();
+        self.raw_scroll = 0;
+        self.parsed_scroll = 0;
+    }
+
+    fn render(&mut self) {
+        let Some(exchange) = &self.exchange else {
+            self.raw_text = String::new();
+            self.parsed_text = String::new();
+            return;
+        };
+        self.raw_text = if self.hex { raw::hex(&exchange.received) } else { raw::escaped(&exchange.received) };
+        let head = exchange.sent.starts_with(b"HEAD ");
+        self.parsed_text = raw::describe(&raw::parse(&exchange.received, head));
+    }
+
+    // Request problems first, then anything that may trip up the server.
+    fn problems(&self) -> Option<String> {
+        match self.request() {
+            Err(err) => Some(err),
+            Ok(request) => Some(raw::warnings(&request).join("; ")).filter(|warnings| !warnings.is_empty()),
+        }
+    }
+}
+
+impl Component for RawComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Raw HTTP/1.1 {} (F5: send, F2: rebuild from request, F4: text/hex, tab: next pane, esc: back)",
+                self.target
+            ))
+            .style(Style::default().fg(if is_active { Color::Green } else { Color::White }));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(1),
+                    Constraint::Percentage(40),
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(inner);
+        f.render_widget(
+            Paragraph::new(Span::styled(self.status.as_str(), Style::default().fg(Color::Yellow))),
+            chunks[0],
+        );
+        self.editor.draw(
+            f,
+            chunks[1],
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Request (line breaks go out as CRLF, \\xHH for any byte)"),
+            self.focus == Pane::Request,
+        );
+        let problem = match self.problems() {
+            Some(problem) => Span::styled(problem, Style::default().fg(Color::Red)),
+            None => Span::styled("No problems found", Style::default().fg(Color::Green)),
+        };
+        f.render_widget(Paragraph::new(problem), chunks[2]);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(chunks[3]);
+        let pane_style = |pane: Pane| Style::default().fg(if self.focus == pane { Color::Yellow } else { Color::White });
+        f.render_widget(
+            Paragraph::new(self.raw_text.as_str())
+                .style(pane_style(Pane::Raw))
+                .scroll((self.raw_scroll, 0))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(if self.hex { "Response bytes (hex)" } else { "Response bytes (text)" }),
+                ),
+            columns[0],
+        );
+        f.render_widget(
+            Paragraph::new(self.parsed_text.as_str())
+                .style(pane_style(Pane::Parsed))
+                .scroll((self.parsed_scroll, 0))
+                .block(Block::default().borders(Borders::ALL).title("Parsed")),
+            columns[1],
+        );
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc => self.done = true,
+            KeyCode::Tab | KeyCode::BackTab => {
+                let panes = [Pane::Request, Pane::Raw, Pane::Parsed];
+                let i = panes.iter().position(|pane| *pane == self.focus).unwrap_or(0);
+                let step = if key == KeyCode::Tab { 1 } else { panes.len() - 1 };
+                self.focus = panes[(i + step) % panes.len()];
+            }
+            KeyCode::F(5) => self.wants_send = true,
+            KeyCode::F(2) => self.wants_template = true,
+            KeyCode::F(4) => {
+                self.hex = !self.hex;
+                self.render();
+                self.raw_scroll = 0;
+            }
+            _ => match self.focus {
+                Pane::Request => self.editor.keybinds(key),
+                Pane::Raw | Pane::Parsed => {
+                    let (text, scroll) = if self.focus == Pane::Raw {
+                        (&self.raw_text, &mut self.raw_scroll)
+                    } else {
+                        (&self.parsed_text, &mut self.parsed_scroll)
+                    };
+                    let max_scroll = text.lines().count().saturating_sub(1) as u16;
+                    match key {
+                        KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+                        KeyCode::Down | KeyCode::Char('j') => *scroll = (*scroll + 1).min(max_scroll),
+                        KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+                        KeyCode::PageDown => *scroll = (*scroll + 10).min(max_scroll),
+                        KeyCode::Home => *scroll = 0,
+                        _ => {}
+                    }
+                }
+            },
+        }
+    }
+}
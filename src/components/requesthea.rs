@@ -30,6 +30,7 @@ pub enum RequestHeaders {
     Raw,
     Binary,
     Graphql,
+    JsonRpc,
 }
 
 impl RequestHeaders {
@@ -41,6 +42,7 @@ impl RequestHeaders {
             RequestHeaders::Raw,
             RequestHeaders::Binary,
             RequestHeaders::Graphql,
+            RequestHeaders::JsonRpc,
         ]
     }
 
@@ -52,6 +54,7 @@ impl RequestHeaders {
             RequestHeaders::Raw => "raw",
             RequestHeaders::Binary => "binary",
             RequestHeaders::Graphql => "GraphQL",
+            RequestHeaders::JsonRpc => "JSON-RPC",
         }
     }
 }
@@ -273,6 +276,73 @@ impl RequestComponent {
         }
     }
 
+    pub fn header_pairs(&self) -> Vec<(String, String)> {
+        self.headers
+            .iter()
+            .filter(|header| !header.key.is_empty())
+            .map(|header| (header.key.clone(), header.value.clone()))
+            .collect()
+    }
+
+    pub fn body(&self) -> Option<String> {
+        let selected_tab = self.body_tabs[self.selected_body_tab];
+        if selected_tab == RequestHeaders::None {
+            return None;
+        }
+        self.body_content
+            .iter()
+            .find(|(tab, _)| *tab == selected_tab)
+            .map(|(_, body_text)| body_text.clone())
+    }
+
+    pub fn selected_body_tab(&self) -> RequestHeaders {
+        self.body_tabs[self.selected_body_tab]
+    }
+
+    // Stores a body built somewhere else, e.g. by the GraphQL editor.
+    pub fn set_body(&mut self, tab: RequestHeaders, body_text: String) {
+        if let Some(existing_entry) = self.body_content.iter_mut().find(|(existing, _)| *existing == tab) {
+            existing_entry.1 = body_text;
+        } else {
+            self.body_content.push((tab, body_text));
+        }
+        if self.selected_body_tab() == tab {
+            self.load_body();
+        }
+    }
+
+    pub fn stored_body(&self, tab: RequestHeaders) -> Option<&str> {
+        self.body_content
+            .iter()
+            .find(|(existing, _)| *existing == tab)
+            .map(|(_, body_text)| body_text.as_str())
+    }
+
+    pub fn select_body_tab(&mut self, tab: RequestHeaders) {
+        if let Some(i) = self.body_tabs.iter().position(|existing| *existing == tab) {
+            self.selected_body_tab = i;
+            self.show_body = true;
+            self.load_body();
+        }
+    }
+
+    // Replaces the value of a header (matched case-insensitively) or adds it.
+    pub fn set_header(&mut self, key: &str, value: String) {
+        match self.headers.iter_mut().find(|header| header.key.eq_ignore_ascii_case(key)) {
+            Some(header) => header.value = value,
+            None => self.headers.push(RequestHeader {
+                key: key.to_string(),
+                value,
+                previous_value: String::new(),
+            }),
+        }
+    }
+
+    pub fn remove_header(&mut self, key: &str) {
+        self.headers.retain(|header| !header.key.eq_ignore_ascii_case(key));
+        self.selected_header = self.selected_header.min(self.headers.len().saturating_sub(1));
+    }
+
     fn save_body(&mut self) {
         let selected_tab = self.body_tabs[self.selected_body_tab].clone();
         let body_text = self.inputs[0].value().to_string();
@@ -14,7 +14,7 @@ use crate::ui::Component;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum HttpMethod {
-    GET, POST, PUT, DELETE, PATCH, HEAD, OPTIONS
+    GET, POST, PUT, DELETE, PATCH, HEAD, OPTIONS, GRPC
 }
 
 impl HttpMethod {
@@ -27,6 +27,7 @@ impl HttpMethod {
             HttpMethod::PATCH,
             HttpMethod::HEAD,
             HttpMethod::OPTIONS,
+            HttpMethod::GRPC,
         ]
     }
 
@@ -39,12 +40,15 @@ impl HttpMethod {
             HttpMethod::PATCH => "PATCH",
             HttpMethod::HEAD => "HEAD",
             HttpMethod::OPTIONS => "OPTIONS",
+            HttpMethod::GRPC => "GRPC",
         }
     }
 }
 
 pub struct SelectorComponent {
     pub method: HttpMethod,
+    // Shown after the method, e.g. the gRPC method a GRPC request calls.
+    pub detail: Option<String>,
     pub show_selection: bool,
     pub list_state: RefCell<ListState>,
 }
@@ -55,10 +59,20 @@ impl SelectorComponent {
         list_state.select(Some(0));
         Self {
             method: HttpMethod::GET,
+            detail: None,
             show_selection: false,
             list_state: RefCell::new(list_state),
         }
     }
+
+    // Picks a method without the menu, keeping the menu on it.
+    pub fn set_method(&mut self, method: HttpMethod) {
+        self.method = method;
+        self.detail = None;
+        self.list_state
+            .borrow_mut()
+            .select(HttpMethod::all_methods().iter().position(|existing| *existing == method));
+    }
 }
 
 impl Component for SelectorComponent {
@@ -71,7 +85,11 @@ impl Component for SelectorComponent {
             } else {
                 Color::White
             }));
-        let paragraph = Paragraph::new(format!("{:?}", self.method))
+        let label = match (&self.detail, self.method) {
+            (Some(detail), HttpMethod::GRPC) => format!("{} {}", self.method.to_string(), detail),
+            _ => self.method.to_string().to_string(),
+        };
+        let paragraph = Paragraph::new(label)
             .block(block)
             .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
         f.render_widget(paragraph, area);
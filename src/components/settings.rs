@@ -0,0 +1,274 @@
+use std::cell::RefCell;
+
+use ratatui::backend::Backend;
+use ratatui::layout::Rect;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::settings::RequestSettings;
+use crate::ui::Component;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SettingsScope {
+    Request,
+    Environment,
+    Collection,
+}
+
+impl SettingsScope {
+    pub fn all_scopes() -> Vec<SettingsScope> {
+        vec![SettingsScope::Request, SettingsScope::Environment, SettingsScope::Collection]
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            SettingsScope::Request => "Request",
+            SettingsScope::Environment => "Environment",
+            SettingsScope::Collection => "Collection defaults",
+        }
+    }
+}
+
+pub struct SettingsComponent {
+    pub environment: String,
+    pub request: RequestSettings,
+    pub environment_settings: RequestSettings,
+    pub defaults: RequestSettings,
+    pub scope: SettingsScope,
+    pub selected: usize,
+    pub list_state: RefCell<ListState>,
+    pub input: Input,
+    pub is_editing: bool,
+    pub error: Option<String>,
+}
+
+impl SettingsComponent {
+    pub fn new(environment: String, environment_settings: RequestSettings, defaults: RequestSettings) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+        Self {
+            environment,
+            request: RequestSettings::default(),
+            environment_settings,
+            defaults,
+            scope: SettingsScope::Request,
+            selected: 0,
+            list_state: RefCell::new(list_state),
+            input: Input::default(),
+            is_editing: false,
+            error: None,
+        }
+    }
+
+    // What a request sends with: its own settings, then the active
+    // environment's, then the collection defaults.
+    pub fn effective(&self) -> RequestSettings {
+        self.effective_in(&self.environment_settings)
+    }
+
+    // What the request would use in another environment.
+    pub fn effective_in(&self, environment_settings: &RequestSettings) -> RequestSettings {
+        self.request.merged(&environment_settings.merged(&self.defaults))
+    }
+
+    fn current(&self) -> &RequestSettings {
+        match self.scope {
+            SettingsScope::Request => &self.request,
+            SettingsScope::Environment => &self.environment_settings,
+            SettingsScope::Collection => &self.defaults,
+        }
+    }
+
+    fn current_mut(&mut self) -> &mut RequestSettings {
+        match self.scope {
+            SettingsScope::Request => &mut self.request,
+            SettingsScope::Environment => &mut self.environment_settings,
+            SettingsScope::Collection => &mut self.defaults,
+        }
+    }
+
+    // The value a field falls back to at the current scope, and where it
+    // comes from.
+    fn inherited(&self, field: &str) -> Option<(&'static str, String)> {
+        let from_environment = ("environment", self.environment_settings.get(field));
+        let from_collection = ("collection", self.defaults.get(field));
+        let candidates = match self.scope {
+            SettingsScope::Request => vec![from_environment, from_collection],
+            SettingsScope::Environment => vec![from_collection],
+            SettingsScope::Collection => vec![],
+        };
+        candidates
+            .into_iter()
+            .find(|(_, value)| !value.is_empty() && value != "none")
+    }
+
+    // The active environment sits on top of the list so it can be switched
+    // from the same place its settings are edited.
+    fn fields(&self) -> Vec<String> {
+        let mut fields = vec!["environment".to_string()];
+        fields.extend(self.current().fields());
+        fields
+    }
+
+    fn value(&self, field: &str) -> String {
+        if field == "environment" {
+            self.environment.clone()
+        } else {
+            self.current().get(field)
+        }
+    }
+
+    fn apply(&mut self, field: &str, value: String) -> Result<(), String> {
+        if field == "environment" {
+            let value = value.trim();
+            if value.is_empty() || value.contains(['/', '\\', '.']) {
+                return Err("invalid environment name".to_string());
+            }
+            self.environment = value.to_string();
+            return Ok(());
+        }
+        self.current_mut().set(field, value)
+    }
+
+    fn selected_field(&self) -> Option<String> {
+        self.fields().get(self.selected).cloned()
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        self.list_state.borrow_mut().select(Some(index));
+    }
+
+    fn display_value(&self, field: &str) -> String {
+        let value = self.value(field);
+        let value = if is_secret(field) && !value.is_empty() {
+            "*".repeat(8)
+        } else {
+            value
+        };
+        if field != "environment" && !is_secret(field) && (value.is_empty() || value == "none") {
+            if let Some((source, inherited)) = self.inherited(field) {
+                return format!("{} ({}: {})", value, source, inherited);
+            }
+        }
+        value
+    }
+
+    fn draw_modal(&self, f: &mut Frame, field: &str) {
+        let size = f.size();
+        let modal_width = 80.min(size.width);
+        let modal_height = 5;
+        let area = Rect::new(
+            (size.width - modal_width) / 2,
+            (size.height.saturating_sub(modal_height)) / 2,
+            modal_width,
+            modal_height,
+        );
+
+        let title = match &self.error {
+            Some(error) => format!("{} - {}", field, error),
+            None => field.to_string(),
+        };
+        let paragraph = Paragraph::new(self.input.value())
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .style(Style::default().fg(if self.error.is_some() { Color::Red } else { Color::Yellow }).add_modifier(Modifier::BOLD));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+        f.set_cursor(area.x + 1 + self.input.visual_cursor() as u16, area.y + 1);
+    }
+}
+
+fn is_secret(field: &str) -> bool {
+    field.ends_with("secret") || field.ends_with("secret_key") || field.ends_with("passphrase") || field.ends_with("password")
+}
+
+impl Component for SettingsComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let title = format!("Settings - {} (c: switch scope)", self.scope.to_string());
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(if is_active {
+                Color::Green
+            } else {
+                Color::White
+            }));
+
+        let items: Vec<ListItem> = self
+            .fields()
+            .iter()
+            .map(|field| ListItem::new(format!("{}: {}", field, self.display_value(field))))
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">>");
+
+        f.render_stateful_widget(list, area, &mut self.list_state.borrow_mut());
+
+        if self.is_editing {
+            if let Some(field) = self.selected_field() {
+                self.draw_modal(f, &field);
+            }
+        }
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        if self.is_editing {
+            match key {
+                KeyCode::Enter => {
+                    if let Some(field) = self.selected_field() {
+                        let value = self.input.value().to_string();
+                        match self.apply(&field, value) {
+                            Ok(()) => {
+                                self.is_editing = false;
+                                self.error = None;
+                            }
+                            Err(error) => self.error = Some(error),
+                        }
+                    }
+                }
+                KeyCode::Esc => {
+                    self.is_editing = false;
+                    self.error = None;
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(KeyEvent::new(
+                        key,
+                        crossterm::event::KeyModifiers::NONE,
+                    )));
+                }
+            }
+            return;
+        }
+
+        let count = self.fields().len();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.select(if self.selected == 0 { count - 1 } else { self.selected - 1 });
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.select(if self.selected + 1 >= count { 0 } else { self.selected + 1 });
+            }
+            KeyCode::Enter => {
+                if let Some(field) = self.selected_field() {
+                    self.input = Input::from(self.value(&field));
+                    self.is_editing = true;
+                }
+            }
+            KeyCode::Char('c') => {
+                let scopes = SettingsScope::all_scopes();
+                let i = scopes.iter().position(|scope| *scope == self.scope).unwrap_or(0);
+                self.scope = scopes[(i + 1) % scopes.len()];
+                self.select(0);
+            }
+            _ => {}
+        }
+    }
+}
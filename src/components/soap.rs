@@ -0,0 +1,193 @@
+use std::cell::RefCell;
+
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::soap::Operation;
+use crate::ui::Component;
+
+// Full-screen view listing the operations of a WSDL; picking one puts its
+// envelope in the raw body tab.
+pub struct SoapComponent {
+    // A path or an http(s) URL.
+    pub source: String,
+    pub operations: Vec<Operation>,
+    pub selected: usize,
+    pub status: String,
+    pub input: Input,
+    pub is_editing: bool,
+    pub list_state: RefCell<ListState>,
+    pub wants_wsdl: bool,
+    pub chosen: Option<Operation>,
+    pub done: bool,
+}
+
+impl SoapComponent {
+    pub fn new() -> Self {
+        Self {
+            source: String::new(),
+            operations: Vec::new(),
+            selected: 0,
+            status: "No WSDL loaded (F3: WSDL file or URL)".to_string(),
+            input: Input::default(),
+            is_editing: false,
+            list_state: RefCell::new(ListState::default()),
+            wants_wsdl: false,
+            chosen: None,
+            done: false,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.chosen = None;
+        self.done = false;
+        if self.operations.is_empty() && !self.source.is_empty() {
+            self.wants_wsdl = true;
+        }
+    }
+
+    pub fn set_operations(&mut self, operations: Result<Vec<Operation>, String>) {
+        match operations {
+            Ok(operations) => {
+                self.status = format!("From {} ({} operations)", self.source, operations.len());
+                self.operations = operations;
+                self.selected = 0;
+            }
+            Err(err) => self.status = format!("Could not load the WSDL: {}", err),
+        }
+    }
+
+    fn draw_modal(&self, f: &mut Frame) {
+        let size = f.size();
+        let modal_width = 80.min(size.width);
+        let modal_height = 3;
+        let area = Rect::new(
+            (size.width - modal_width) / 2,
+            (size.height.saturating_sub(modal_height)) / 2,
+            modal_width,
+            modal_height,
+        );
+
+        let paragraph = Paragraph::new(self.input.value())
+            .block(Block::default().borders(Borders::ALL).title("WSDL file or URL"))
+            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+        f.set_cursor(area.x + 1 + self.input.visual_cursor() as u16, area.y + 1);
+    }
+}
+
+impl Component for SoapComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("SOAP (enter: use operation, F3: WSDL file or URL, F5: reload, esc: back)")
+            .style(Style::default().fg(if is_active { Color::Green } else { Color::White }));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+            .split(inner);
+        f.render_widget(
+            Paragraph::new(Span::styled(self.status.as_str(), Style::default().fg(Color::Yellow))),
+            chunks[0],
+        );
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+            .split(chunks[1]);
+        let items: Vec<ListItem> = self
+            .operations
+            .iter()
+            .map(|operation| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} ", operation.label())),
+                    Span::styled(operation.version.to_string(), Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Operations"))
+            .style(Style::default().fg(Color::White))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED))
+            .highlight_symbol(">>");
+        self.list_state
+            .borrow_mut()
+            .select(if self.operations.is_empty() { None } else { Some(self.selected) });
+        f.render_stateful_widget(list, columns[0], &mut self.list_state.borrow_mut());
+
+        let mut lines = Vec::new();
+        if let Some(operation) = self.operations.get(self.selected) {
+            let detail = |label: &str, value: &str| {
+                Line::from(vec![
+                    Span::styled(format!("{}: ", label), Style::default().fg(Color::Cyan)),
+                    Span::raw(value.to_string()),
+                ])
+            };
+            lines.push(detail("Service", &operation.service));
+            lines.push(detail("Endpoint", operation.endpoint.as_deref().unwrap_or("(none given)")));
+            lines.push(detail("SOAPAction", &operation.action));
+            lines.push(Line::from(""));
+            lines.extend(operation.envelope.lines().map(|line| Line::from(line.to_string())));
+        }
+        f.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Envelope")),
+            columns[1],
+        );
+
+        if self.is_editing {
+            self.draw_modal(f);
+        }
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        if self.is_editing {
+            match key {
+                KeyCode::Enter => {
+                    self.source = self.input.value().trim().to_string();
+                    self.is_editing = false;
+                    self.wants_wsdl = !self.source.is_empty();
+                }
+                KeyCode::Esc => {
+                    self.is_editing = false;
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(KeyEvent::new(
+                        key,
+                        crossterm::event::KeyModifiers::NONE,
+                    )));
+                }
+            }
+            return;
+        }
+
+        match key {
+            KeyCode::Esc => self.done = true,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.operations.len().saturating_sub(1))
+            }
+            KeyCode::Enter if !self.operations.is_empty() => {
+                self.chosen = Some(self.operations[self.selected].clone());
+                self.done = true;
+            }
+            KeyCode::F(3) => {
+                self.input = Input::from(self.source.clone());
+                self.is_editing = true;
+            }
+            KeyCode::F(5) if !self.source.is_empty() => self.wants_wsdl = true,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::cell::RefCell;
+
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::sse::{self, Command};
+use crate::ui::Component;
+
+// Takes the place of the output panel while an event stream is open: the
+// events received so far, newest last, and the data of the selected one.
+pub struct SseComponent {
+    pub url: String,
+    pub visible: bool,
+    pub open: bool,
+    pub status: String,
+    pub frames: Vec<sse::Frame>,
+    // Event types to show; empty shows everything.
+    pub filter: Vec<String>,
+    pub input: Input,
+    pub is_editing: bool,
+    pub selected: usize,
+    // Keeps the newest event selected until the user picks another.
+    pub follow: bool,
+    pub list_state: RefCell<ListState>,
+    // Commands waiting to be handed to the subscription.
+    pub commands: Vec<Command>,
+}
+
+impl SseComponent {
+    pub fn new() -> Self {
+        Self {
+            url: String::new(),
+            visible: false,
+            open: false,
+            status: String::new(),
+            frames: Vec::new(),
+            filter: Vec::new(),
+            input: Input::default(),
+            is_editing: false,
+            selected: 0,
+            follow: true,
+            list_state: RefCell::new(ListState::default()),
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn start(&mut self, url: String) {
+        self.url = url;
+        self.visible = true;
+        self.open = false;
+        self.status = String::new();
+        self.frames.clear();
+        self.commands.clear();
+        self.selected = 0;
+        self.follow = true;
+    }
+
+    pub fn push(&mut self, frame: sse::Frame) {
+        self.frames.push(frame);
+        if self.follow {
+            self.selected = self.shown().len().saturating_sub(1);
+        }
+    }
+
+    fn shown(&self) -> Vec<&sse::Frame> {
+        self.frames
+            .iter()
+            .filter(|frame| self.filter.is_empty() || self.filter.contains(&frame.event))
+            .collect()
+    }
+
+    // The data pretty-printed when it's JSON, as most event payloads are.
+    fn detail(frame: &sse::Frame) -> String {
+        let data = match serde_json::from_str::<serde_json::Value>(&frame.data) {
+            Ok(json) => serde_json::to_string_pretty(&json).unwrap_or_else(|_| frame.data.clone()),
+            Err(_) => frame.data.clone(),
+        };
+        let mut detail = format!("event: {}\n", frame.event);
+        if let Some(id) = &frame.id {
+            detail.push_str(&format!("id: {}\n", id));
+        }
+        if let Some(retry) = frame.retry {
+            detail.push_str(&format!("retry: {}\n", retry));
+        }
+        detail.push('\n');
+        detail.push_str(&data);
+        detail
+    }
+
+    fn draw_modal(&self, f: &mut Frame) {
+        let size = f.size();
+        let modal_width = 80.min(size.width);
+        let modal_height = 3;
+        let area = Rect::new(
+            (size.width - modal_width) / 2,
+            (size.height.saturating_sub(modal_height)) / 2,
+            modal_width,
+            modal_height,
+        );
+
+        let paragraph = Paragraph::new(self.input.value())
+            .block(Block::default().borders(Borders::ALL).title("Show event types (comma separated, empty for all)"))
+            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+        f.set_cursor(area.x + 1 + self.input.visual_cursor() as u16, area.y + 1);
+    }
+}
+
+impl Component for SseComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let shown = self.shown();
+        let filtered = if self.filter.is_empty() {
+            String::new()
+        } else {
+            format!(", showing {} of {} ({})", shown.len(), self.frames.len(), self.filter.join(", "))
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Events {} - {}{} (f: filter, r: reconnect, x: stop)",
+                self.url,
+                if self.open { "open" } else { "closed" },
+                filtered
+            ))
+            .style(Style::default().fg(if is_active {
+                Color::Green
+            } else {
+                Color::White
+            }));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+            .split(inner);
+        f.render_widget(
+            Paragraph::new(Span::styled(self.status.as_str(), Style::default().fg(Color::Yellow))),
+            chunks[0],
+        );
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(chunks[1]);
+        let items: Vec<ListItem> = shown
+            .iter()
+            .map(|frame| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{} ", frame.time), Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("{} ", frame.event), Style::default().fg(Color::Cyan)),
+                    Span::raw(frame.data.lines().next().unwrap_or_default().to_string()),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::RIGHT))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">>");
+        self.list_state
+            .borrow_mut()
+            .select(if shown.is_empty() { None } else { Some(self.selected.min(shown.len() - 1)) });
+        f.render_stateful_widget(list, columns[0], &mut self.list_state.borrow_mut());
+
+        if let Some(frame) = shown.get(self.selected) {
+            f.render_widget(Paragraph::new(Self::detail(frame)).wrap(Wrap { trim: false }), columns[1]);
+        }
+
+        if self.is_editing {
+            self.draw_modal(f);
+        }
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        if self.is_editing {
+            match key {
+                KeyCode::Enter => {
+                    self.filter = self
+                        .input
+                        .value()
+                        .split(',')
+                        .map(|event| event.trim().to_string())
+                        .filter(|event| !event.is_empty())
+                        .collect();
+                    self.is_editing = false;
+                    self.follow = true;
+                    self.selected = self.shown().len().saturating_sub(1);
+                }
+                KeyCode::Esc => {
+                    self.is_editing = false;
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(KeyEvent::new(
+                        key,
+                        crossterm::event::KeyModifiers::NONE,
+                    )));
+                }
+            }
+            return;
+        }
+
+        let count = self.shown().len();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                self.follow = false;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(count.saturating_sub(1));
+                self.follow = self.selected + 1 >= count;
+            }
+            KeyCode::Char('f') => {
+                self.input = Input::from(self.filter.join(", "));
+                self.is_editing = true;
+            }
+            KeyCode::Char('r') => self.commands.push(Command::Reconnect),
+            KeyCode::Char('x') => self.commands.push(Command::Stop),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::Frame;
+use crossterm::event::KeyCode;
+
+// A small multi-line editor, for bodies too long for a single-line input.
+pub struct TextArea {
+    lines: Vec<String>,
+    row: usize,
+    // In characters, not bytes.
+    column: usize,
+}
+
+impl TextArea {
+    pub fn from(text: &str) -> Self {
+        let lines: Vec<String> = text.split('\n').map(|line| line.to_string()).collect();
+        Self {
+            row: lines.len() - 1,
+            column: lines.last().map(|line| line.chars().count()).unwrap_or(0),
+            lines,
+        }
+    }
+
+    pub fn value(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    // Byte offset of the cursor in `value()`.
+    pub fn offset(&self) -> usize {
+        self.lines[..self.row].iter().map(|line| line.len() + 1).sum::<usize>() + self.byte_column()
+    }
+
+    fn byte_column(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices().nth(self.column).map(|(i, _)| i).unwrap_or(line.len())
+    }
+
+    fn insert(&mut self, c: char) {
+        let at = self.byte_column();
+        self.lines[self.row].insert(at, c);
+        self.column += 1;
+    }
+
+    // A new line keeps the indentation of the one it was split from.
+    fn newline(&mut self) {
+        let at = self.byte_column();
+        let rest = self.lines[self.row].split_off(at);
+        let indent: String = self.lines[self.row].chars().take_while(|c| *c == ' ').collect();
+        self.column = indent.chars().count();
+        self.lines.insert(self.row + 1, indent + &rest);
+        self.row += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.column > 0 {
+            self.column -= 1;
+            let at = self.byte_column();
+            self.lines[self.row].remove(at);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.column = self.lines[self.row].chars().count();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn delete(&mut self) {
+        let at = self.byte_column();
+        if at < self.lines[self.row].len() {
+            self.lines[self.row].remove(at);
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    // Swaps what's between byte offset `start` and the cursor for `text`,
+    // like the word being typed for a completion.
+    pub fn replace_to_cursor(&mut self, start: usize, text: &str) {
+        let value = self.value();
+        let typed = value[start..self.offset()].chars().count();
+        for _ in 0..typed {
+            self.backspace();
+        }
+        for c in text.chars() {
+            self.insert(c);
+        }
+    }
+
+    pub fn keybinds(&mut self, key: KeyCode) {
+        let length = |area: &TextArea| area.lines[area.row].chars().count();
+        match key {
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Enter => self.newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left if self.column > 0 => self.column -= 1,
+            KeyCode::Left if self.row > 0 => {
+                self.row -= 1;
+                self.column = length(self);
+            }
+            KeyCode::Right if self.column < length(self) => self.column += 1,
+            KeyCode::Right if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.column = 0;
+            }
+            KeyCode::Up if self.row > 0 => {
+                self.row -= 1;
+                self.column = self.column.min(length(self));
+            }
+            KeyCode::Down if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.column = self.column.min(length(self));
+            }
+            KeyCode::Home => self.column = 0,
+            KeyCode::End => self.column = length(self),
+            _ => {}
+        }
+    }
+
+    // Scrolled so the cursor stays in view; returns where the cursor is.
+    pub fn draw(&self, f: &mut Frame, area: Rect, block: Block, focused: bool) -> (u16, u16) {
+        let inner = block.inner(area);
+        let top = self.row.saturating_sub(inner.height.saturating_sub(1) as usize);
+        let left = self.column.saturating_sub(inner.width.saturating_sub(1) as usize);
+        let style = if focused { Style::default().fg(Color::Yellow) } else { Style::default() };
+        let lines: Vec<Line> = self.lines.iter().map(|line| Line::from(line.as_str())).collect();
+        f.render_widget(
+            Paragraph::new(lines).block(block).style(style).scroll((top as u16, left as u16)),
+            area,
+        );
+        let cursor = (inner.x + (self.column - left) as u16, inner.y + (self.row - top) as u16);
+        if focused {
+            f.set_cursor(cursor.0, cursor.1);
+        }
+        cursor
+    }
+}
@@ -0,0 +1,229 @@
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Frame;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::backend::crossterm::EventHandler;
+use tui_input::Input;
+
+use crate::ui::Component;
+use crate::websocket::{self, Entry, Outgoing, Template};
+
+// Takes the place of the output panel while a WebSocket URL is open: a log
+// of every frame, a composer and saved message templates.
+pub struct WebSocketComponent {
+    // "WebSocket", or "GraphQL subscription" when the log is a subscription's.
+    pub kind: &'static str,
+    pub url: String,
+    pub visible: bool,
+    pub open: bool,
+    pub log: Vec<Entry>,
+    pub input: Input,
+    pub is_composing: bool,
+    pub binary: bool,
+    pub templates: Vec<Template>,
+    pub selected_template: usize,
+    // Frames waiting to be handed to the connection.
+    pub outgoing: Vec<Outgoing>,
+    // Lines scrolled back from the newest entry.
+    pub scroll_back: u16,
+}
+
+impl WebSocketComponent {
+    pub fn new() -> Self {
+        Self {
+            kind: "WebSocket",
+            url: String::new(),
+            visible: false,
+            open: false,
+            log: Vec::new(),
+            input: Input::default(),
+            is_composing: false,
+            binary: false,
+            templates: Vec::new(),
+            selected_template: 0,
+            outgoing: Vec::new(),
+            scroll_back: 0,
+        }
+    }
+
+    pub fn start(&mut self, kind: &'static str, url: String) {
+        self.kind = kind;
+        self.url = url;
+        self.visible = true;
+        self.open = false;
+        self.log.clear();
+        self.outgoing.clear();
+        self.scroll_back = 0;
+    }
+
+    pub fn push(&mut self, entry: Entry) {
+        self.log.push(entry);
+    }
+
+    fn help(&self) -> &'static str {
+        match self.kind {
+            "gRPC" => "i: compose, x: end stream or cancel, T: save template, [/]: pick, l: load, d: delete",
+            _ => "i: compose, m: text/binary, p: ping, x: close, T: save template, [/]: pick, l: load, d: delete",
+        }
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        self.log
+            .iter()
+            .flat_map(|entry| {
+                let (arrow, color) = match entry.direction {
+                    websocket::Direction::Sent => ("->", Color::Cyan),
+                    websocket::Direction::Received => ("<-", Color::Green),
+                    websocket::Direction::Info => ("--", Color::Yellow),
+                };
+                let mut text = entry.text.lines();
+                let first = Line::from(vec![
+                    Span::styled(format!("{} ", entry.time), Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("{} {:<6} ", arrow, entry.kind), Style::default().fg(color)),
+                    Span::raw(text.next().unwrap_or_default().to_string()),
+                ]);
+                // Continuation lines of multi-line messages line up under the
+                // first one.
+                let indent = " ".repeat(entry.time.len() + 11);
+                std::iter::once(first)
+                    .chain(text.map(move |line| Line::from(format!("{}{}", indent, line))))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn submit(&mut self) {
+        let template = Template {
+            binary: self.binary,
+            payload: self.input.value().to_string(),
+        };
+        match template.to_outgoing() {
+            Ok(message) => {
+                self.outgoing.push(message);
+                self.input.reset();
+                self.scroll_back = 0;
+            }
+            Err(message) => self.push(Entry::new(websocket::Direction::Info, "error", message)),
+        }
+    }
+
+    fn draw_templates(&self, f: &mut Frame, area: Rect) {
+        let mut spans = vec![Span::raw("Templates: ")];
+        if self.templates.is_empty() {
+            spans.push(Span::styled("none, T saves the composer", Style::default().fg(Color::DarkGray)));
+        }
+        for (i, template) in self.templates.iter().enumerate() {
+            let label: String = template.payload.chars().take(24).collect();
+            let label = format!("[{}{}] ", if template.binary { "bin " } else { "" }, label);
+            let style = if i == self.selected_template {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(label, style));
+        }
+        f.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+}
+
+impl Component for WebSocketComponent {
+    fn draw<B: Backend>(&self, f: &mut Frame, area: Rect, is_active: bool) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "{} {} - {} ({})",
+                self.kind,
+                self.url,
+                if self.open { "open" } else { "closed" },
+                self.help()
+            ))
+            .style(Style::default().fg(if is_active {
+                Color::Green
+            } else {
+                Color::White
+            }));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1), Constraint::Length(3)].as_ref())
+            .split(inner);
+
+        let lines = self.lines();
+        let top = lines
+            .len()
+            .saturating_sub(chunks[0].height as usize + self.scroll_back as usize);
+        f.render_widget(Paragraph::new(lines).scroll((top as u16, 0)), chunks[0]);
+
+        self.draw_templates(f, chunks[1]);
+
+        let composer = Paragraph::new(self.input.value())
+            .block(Block::default().borders(Borders::ALL).title(if self.binary {
+                "Binary message (hex)"
+            } else {
+                "Text message"
+            }))
+            .style(if self.is_composing {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            });
+        f.render_widget(composer, chunks[2]);
+        if self.is_composing {
+            f.set_cursor(chunks[2].x + 1 + self.input.visual_cursor() as u16, chunks[2].y + 1);
+        }
+    }
+
+    fn keybinds(&mut self, key: KeyCode) {
+        if self.is_composing {
+            match key {
+                KeyCode::Enter => self.submit(),
+                KeyCode::Esc => {
+                    self.is_composing = false;
+                }
+                _ => {
+                    self.input.handle_event(&Event::Key(KeyEvent::new(
+                        key,
+                        crossterm::event::KeyModifiers::NONE,
+                    )));
+                }
+            }
+            return;
+        }
+
+        let count = self.templates.len();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_back = self.scroll_back.saturating_add(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_back = self.scroll_back.saturating_sub(1),
+            KeyCode::Char('i') | KeyCode::Enter => self.is_composing = true,
+            KeyCode::Char('m') => self.binary = !self.binary,
+            KeyCode::Char('p') if self.open => self.outgoing.push(Outgoing::Ping),
+            KeyCode::Char('x') if self.open => self.outgoing.push(Outgoing::Close),
+            KeyCode::Char('T') if !self.input.value().is_empty() => {
+                self.templates.push(Template {
+                    binary: self.binary,
+                    payload: self.input.value().to_string(),
+                });
+                self.selected_template = self.templates.len() - 1;
+            }
+            KeyCode::Char('[') if count > 0 => self.selected_template = (self.selected_template + count - 1) % count,
+            KeyCode::Char(']') if count > 0 => self.selected_template = (self.selected_template + 1) % count,
+            KeyCode::Char('l') => {
+                if let Some(template) = self.templates.get(self.selected_template) {
+                    self.binary = template.binary;
+                    self.input = Input::from(template.payload.clone());
+                    self.is_composing = true;
+                }
+            }
+            KeyCode::Char('d') if self.selected_template < count => {
+                self.templates.remove(self.selected_template);
+                self.selected_template = self.selected_template.min(self.templates.len().saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+}
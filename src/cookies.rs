@@ -0,0 +1,179 @@
+use dirs::config_dir;
+use reqwest::Url;
+use cookie_store::Cookie;
+use reqwest_cookie_store::{CookieStore, CookieStoreMutex};
+use sha2::{Digest, Sha256};
+use std::{fs, io::BufReader, path::PathBuf, sync::Arc};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CookieEntry {
+    pub domain: String,
+    pub path: String,
+    pub name: String,
+    pub value: String,
+    pub expires: String,
+}
+
+// One jar per environment, stored next to the session file in
+// `postsmith/cookies/<environment>.json`.
+pub struct CookieJar {
+    pub environment: String,
+    pub store: Arc<CookieStoreMutex>,
+}
+
+impl CookieJar {
+    pub fn load(environment: &str) -> Self {
+        let store = Self::read(environment).unwrap_or_default();
+        Self {
+            environment: environment.to_string(),
+            store: Arc::new(CookieStoreMutex::new(store)),
+        }
+    }
+
+    fn read(environment: &str) -> Result<CookieStore, Box<dyn std::error::Error + Send + Sync>> {
+        let file_path = Self::dir().join(file_name(environment));
+        if !file_path.exists() {
+            return Ok(CookieStore::default());
+        }
+        let file = fs::File::open(file_path)?;
+        cookie_store::serde::json::load_all(BufReader::new(file))
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let config_path = Self::dir();
+        if !config_path.exists() {
+            fs::create_dir_all(&config_path)?;
+        }
+        let mut file = fs::File::create(config_path.join(file_name(&self.environment)))?;
+        let store = self.store.lock().unwrap();
+        cookie_store::serde::json::save_incl_expired_and_nonpersistent(&store, &mut file)
+    }
+
+    fn dir() -> PathBuf {
+        config_dir().unwrap().join("postsmith/cookies")
+    }
+
+    pub fn entries(&self) -> Vec<CookieEntry> {
+        let store = self.store.lock().unwrap();
+        let mut entries: Vec<CookieEntry> = store
+            .iter_any()
+            .map(|cookie| CookieEntry {
+                domain: cookie.domain.as_cow().map(|d| d.to_string()).unwrap_or_default(),
+                path: cookie.path.to_string(),
+                name: cookie.name().to_string(),
+                value: cookie.value().to_string(),
+                expires: match cookie.expires_datetime() {
+                    Some(at) => at.to_string(),
+                    None if cookie.is_expired() => "expired".to_string(),
+                    None => "session".to_string(),
+                },
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+        entries
+    }
+
+    // The path, domain and expiry stored for the cookie are kept as they are,
+    // rather than worked out again from a URL, so the edit replaces it.
+    pub fn set_value(&self, entry: &CookieEntry, value: &str) {
+        let mut store = self.store.lock().unwrap();
+        let Some(cookie) = store.get_any(&entry.domain, &entry.path, &entry.name) else {
+            return;
+        };
+        let mut raw = (**cookie).clone();
+        raw.set_value(value.to_string());
+        let Ok(url) = Url::parse(&format!("https://{}{}", entry.domain, entry.path)) else {
+            return;
+        };
+        let Ok(mut edited) = Cookie::try_from_raw_cookie(&raw, &url) else {
+            return;
+        };
+        edited.path = cookie.path.clone();
+        edited.domain = cookie.domain.clone();
+        edited.expires = cookie.expires.clone();
+        let _ = store.insert(edited.into_owned(), &url);
+    }
+
+    pub fn remove(&self, entry: &CookieEntry) {
+        self.store.lock().unwrap().remove(&entry.domain, &entry.path, &entry.name);
+    }
+
+    pub fn clear_domain(&self, domain: &str) {
+        for entry in self.entries().iter().filter(|entry| entry.domain == domain) {
+            self.remove(entry);
+        }
+    }
+
+    pub fn clear(&self) {
+        self.store.lock().unwrap().clear();
+    }
+}
+
+// Environment names are typed in freely, so anything that isn't safe in a
+// file name is replaced, with a hash of the real name added to keep "a/b"
+// and "a_b" apart.
+fn file_name(environment: &str) -> String {
+    let safe: String = environment
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            '.' if i > 0 => c,
+            _ => '_',
+        })
+        .collect();
+    if safe == environment {
+        return format!("{}.json", environment);
+    }
+    let hash = hex::encode(Sha256::digest(environment.as_bytes()));
+    format!("{}-{}.json", safe, &hash[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_stay_in_the_cookie_directory() {
+        assert_eq!(file_name("staging"), "staging.json");
+        assert_eq!(file_name("prod.eu"), "prod.eu.json");
+        for name in ["../x", "..", "/etc/passwd", ".hidden", "a\\b"] {
+            let file = file_name(name);
+            assert!(!file.contains('/') && !file.contains('\\') && !file.starts_with('.'), "{}", file);
+        }
+        assert_ne!(file_name("a/b"), file_name("a_b"));
+    }
+
+    #[test]
+    fn editing_a_cookie_keeps_one_cookie() {
+        let jar = CookieJar {
+            environment: "test".to_string(),
+            store: Arc::new(CookieStoreMutex::new(CookieStore::default())),
+        };
+        {
+            let mut store = jar.store.lock().unwrap();
+            // No Path attribute: the path comes from the page, "/app".
+            store.parse("sid=1; HttpOnly", &Url::parse("https://example.test/app/page").unwrap()).unwrap();
+            let url = Url::parse("https://www.example.test/").unwrap();
+            store.parse("theme=dark; Domain=example.test; Secure; Max-Age=3600", &url).unwrap();
+        }
+        let before = jar.entries();
+
+        for entry in &before {
+            jar.set_value(entry, "edited");
+        }
+        let after = jar.entries();
+        assert_eq!(after.len(), 2, "{:?}", after);
+        for (before, after) in before.iter().zip(&after) {
+            assert_eq!(after.value, "edited");
+            assert_eq!((&after.domain, &after.path, &after.name), (&before.domain, &before.path, &before.name));
+            assert_eq!(after.expires, before.expires);
+        }
+        let store = jar.store.lock().unwrap();
+        let theme = store.get_any("example.test", "/", "theme").unwrap();
+        assert!(theme.secure().unwrap_or(false));
+        assert!(theme.matches(&Url::parse("https://api.example.test/").unwrap()));
+        let sid = store.get_any("example.test", "/app", "sid").unwrap();
+        assert!(!sid.matches(&Url::parse("https://api.example.test/app").unwrap()));
+    }
+}
@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::request::Response;
+
+// Longest body kept in a history entry for comparing later.
+const SNAPSHOT_LIMIT: usize = 256 * 1024;
+// Above this many line pairs the exact diff gets too slow, so the middle of
+// the two documents is shown as one changed block.
+const DIFF_LIMIT: usize = 4_000_000;
+// Headers whose values are credentials, masked before a snapshot is kept.
+const SENSITIVE_HEADERS: [&str; 7] = [
+    "set-cookie",
+    "cookie",
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "x-auth-token",
+    "x-amz-security-token",
+];
+
+// The parts of a response worth comparing, small enough to keep in history.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Snapshot {
+    pub fn of(response: &Response) -> Snapshot {
+        let body = if response.is_binary() {
+            format!("[{} bytes of binary data]", response.size)
+        } else {
+            let text = String::from_utf8_lossy(&response.body);
+            let end = (0..=text.len().min(SNAPSHOT_LIMIT))
+                .rev()
+                .find(|i| text.is_char_boundary(*i))
+                .unwrap_or(0);
+            text[..end].to_string()
+        };
+        Snapshot {
+            status: response.status,
+            headers: redact(&response.headers),
+            body,
+        }
+    }
+
+    // The text that gets diffed: status, headers sorted by name, then the
+    // body, pretty-printed with sorted keys when it's JSON. `ignore` holds
+    // JSON paths like `meta.timestamp` or `items[*].id`, and `header:date`
+    // to leave out a header.
+    pub fn normalized(&self, ignore: &[String]) -> Vec<String> {
+        let mut lines = vec![format!("status: {}", self.status)];
+
+        let ignored_headers: Vec<String> = ignore
+            .iter()
+            .filter_map(|path| path.strip_prefix("header:"))
+            .map(|name| name.trim().to_lowercase())
+            .collect();
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(key, value)| (key.to_lowercase(), value.clone()))
+            .filter(|(key, _)| !ignored_headers.contains(key))
+            .collect();
+        headers.sort();
+        lines.extend(headers.into_iter().map(|(key, value)| format!("{}: {}", key, value)));
+        lines.push(String::new());
+
+        match serde_json::from_str::<Value>(&self.body) {
+            Ok(mut json) => {
+                for path in ignore.iter().filter(|path| !path.starts_with("header:")) {
+                    remove_path(&mut json, &parse_path(path));
+                }
+                // serde_json keeps object keys sorted, so this also puts
+                // them in a stable order.
+                let pretty = serde_json::to_string_pretty(&json).unwrap_or_default();
+                lines.extend(pretty.lines().map(|line| line.to_string()));
+            }
+            Err(_) => lines.extend(self.body.lines().map(|line| line.to_string())),
+        }
+        lines
+    }
+}
+
+// The header stays so a diff still shows whether it was sent.
+fn redact(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(key, value)| {
+            if SENSITIVE_HEADERS.contains(&key.to_lowercase().as_str()) {
+                (key.clone(), "[redacted]".to_string())
+            } else {
+                (key.clone(), value.clone())
+            }
+        })
+        .collect()
+}
+
+fn parse_path(path: &str) -> Vec<String> {
+    path.trim()
+        .trim_start_matches('$')
+        .replace('[', ".")
+        .replace(']', "")
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+// `*` matches every key or array element on its level.
+fn remove_path(value: &mut Value, path: &[String]) {
+    let Some((segment, rest)) = path.split_first() else {
+        return;
+    };
+    match value {
+        Value::Object(map) => {
+            if rest.is_empty() {
+                if segment == "*" {
+                    map.clear();
+                } else {
+                    map.remove(segment);
+                }
+            } else {
+                for (key, child) in map.iter_mut() {
+                    if segment == "*" || key == segment {
+                        remove_path(child, rest);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            let index = segment.parse::<usize>().ok();
+            if rest.is_empty() {
+                match index {
+                    Some(index) if index < items.len() => {
+                        items.remove(index);
+                    }
+                    _ if segment == "*" => items.clear(),
+                    _ => {}
+                }
+            } else {
+                for (i, child) in items.iter_mut().enumerate() {
+                    if segment == "*" || index == Some(i) {
+                        remove_path(child, rest);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+// Line diff based on the longest common subsequence.
+pub fn diff(left: &[String], right: &[String]) -> Vec<Change> {
+    let prefix = left.iter().zip(right).take_while(|(a, b)| a == b).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old, new) = (&left[prefix..left.len() - suffix], &right[prefix..right.len() - suffix]);
+
+    let mut changes: Vec<Change> = left[..prefix].iter().cloned().map(Change::Same).collect();
+    if old.len().saturating_mul(new.len()) > DIFF_LIMIT {
+        changes.extend(old.iter().cloned().map(Change::Removed));
+        changes.extend(new.iter().cloned().map(Change::Added));
+    } else {
+        // lengths[i][j]: common lines between old[i..] and new[j..].
+        let width = new.len() + 1;
+        let mut lengths = vec![0u32; (old.len() + 1) * width];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lengths[i * width + j] = if old[i] == new[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() && j < new.len() {
+            if old[i] == new[j] {
+                changes.push(Change::Same(old[i].clone()));
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+                changes.push(Change::Removed(old[i].clone()));
+                i += 1;
+            } else {
+                changes.push(Change::Added(new[j].clone()));
+                j += 1;
+            }
+        }
+        changes.extend(old[i..].iter().cloned().map(Change::Removed));
+        changes.extend(new[j..].iter().cloned().map(Change::Added));
+    }
+    changes.extend(left[left.len() - suffix..].iter().cloned().map(Change::Same));
+    changes
+}
+
+// One row of a side by side diff: the left line and the right line.
+pub type Row = (Option<Change>, Option<Change>);
+
+// Pairs up removed and added lines of each changed block so they can be
+// shown next to each other.
+pub fn side_by_side(changes: &[Change]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let flush = |rows: &mut Vec<Row>, removed: &mut Vec<Change>, added: &mut Vec<Change>| {
+        let count = removed.len().max(added.len());
+        let mut removed = removed.drain(..);
+        let mut added = added.drain(..);
+        for _ in 0..count {
+            rows.push((removed.next(), added.next()));
+        }
+    };
+    for change in changes {
+        match change {
+            Change::Same(_) => {
+                flush(&mut rows, &mut removed, &mut added);
+                rows.push((Some(change.clone()), Some(change.clone())));
+            }
+            Change::Removed(_) => removed.push(change.clone()),
+            Change::Added(_) => added.push(change.clone()),
+        }
+    }
+    flush(&mut rows, &mut removed, &mut added);
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_are_masked() {
+        let headers = vec![
+            ("Set-Cookie".to_string(), "session=secret".to_string()),
+            ("content-type".to_string(), "application/json".to_string()),
+            ("X-Api-Key".to_string(), "secret".to_string()),
+        ];
+        assert_eq!(
+            redact(&headers),
+            vec![
+                ("Set-Cookie".to_string(), "[redacted]".to_string()),
+                ("content-type".to_string(), "application/json".to_string()),
+                ("X-Api-Key".to_string(), "[redacted]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignored_paths_leave_the_normalized_text() {
+        let snapshot = Snapshot {
+            status: 200,
+            headers: vec![("Date".to_string(), "today".to_string()), ("B".to_string(), "2".to_string())],
+            body: r#"{"z": 1, "meta": {"timestamp": 5, "id": 7}, "items": [{"id": 1, "x": 2}]}"#.to_string(),
+        };
+        let ignore = vec!["meta.timestamp".to_string(), "items[*].id".to_string(), "header:date".to_string()];
+        let text = snapshot.normalized(&ignore).join("\n");
+        assert!(text.starts_with("status: 200\nb: 2\n\n{"));
+        assert!(!text.contains("timestamp"));
+        assert!(!text.contains("today"));
+        assert!(text.contains("\"id\": 7"));
+        assert!(text.find("\"items\"").unwrap() < text.find("\"z\"").unwrap());
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use reqwest::Url;
+use reqwest_cookie_store::CookieStoreMutex;
+use tempfile::TempPath;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::request::{self, Request};
+use crate::settings::RequestSettings;
+
+// Sent as the file fills up; `total` is only known when the server says how
+// long the body is.
+#[derive(Clone, Copy, Debug)]
+pub struct DownloadProgress {
+    pub received: u64,
+    pub total: Option<u64>,
+}
+
+impl DownloadProgress {
+    pub fn render(&self) -> String {
+        const WIDTH: usize = 20;
+        match self.total {
+            Some(total) if total > 0 => {
+                let filled = ((self.received as f64 / total as f64) * WIDTH as f64).round() as usize;
+                let filled = filled.min(WIDTH);
+                format!(
+                    "[{}{}] {}% {}/{} bytes",
+                    "#".repeat(filled),
+                    " ".repeat(WIDTH - filled),
+                    self.received * 100 / total,
+                    self.received,
+                    total
+                )
+            }
+            _ => format!("{} bytes", self.received),
+        }
+    }
+}
+
+// A name for the saved file: what Content-Disposition suggests, else the
+// last segment of the URL path, with an extension from Content-Type when the
+// name has none.
+pub fn default_filename(url: &str, headers: &[(String, String)]) -> String {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    };
+
+    let from_disposition = header("content-disposition").and_then(disposition_filename);
+    let from_url = || {
+        Url::parse(url)
+            .ok()
+            .and_then(|url| url.path_segments().and_then(|mut segments| segments.next_back().map(|s| s.to_string())))
+            .filter(|segment| !segment.is_empty())
+    };
+    // Only the file name, never a path the server picked.
+    let name = from_disposition
+        .or_else(from_url)
+        .and_then(|name| Path::new(&name).file_name().map(|name| name.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "response".to_string());
+
+    if Path::new(&name).extension().is_some() {
+        return name;
+    }
+    match header("content-type").and_then(extension_for) {
+        Some(extension) => format!("{}.{}", name, extension),
+        None => name,
+    }
+}
+
+fn disposition_filename(value: &str) -> Option<String> {
+    let params = value.split(';').map(|param| param.trim()).filter_map(|param| param.split_once('='));
+    let mut plain = None;
+    for (key, value) in params {
+        match key.trim().to_lowercase().as_str() {
+            // RFC 5987: filename*=UTF-8''name%20with%20spaces
+            "filename*" => {
+                if let Some((_, encoded)) = value.split_once("''") {
+                    let decoded = percent_decode(encoded);
+                    if !decoded.is_empty() {
+                        return Some(decoded);
+                    }
+                }
+            }
+            "filename" => plain = Some(value.trim_matches('"').to_string()),
+            _ => {}
+        }
+    }
+    plain.filter(|name| !name.is_empty())
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn extension_for(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    let extension = match mime.as_str() {
+        "application/json" => "json",
+        "application/x-ndjson" => "ndjson",
+        "application/xml" | "text/xml" => "xml",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "application/gzip" => "gz",
+        "application/javascript" | "text/javascript" => "js",
+        "application/octet-stream" => "bin",
+        "text/html" => "html",
+        "text/plain" => "txt",
+        "text/css" => "css",
+        "text/csv" => "csv",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        _ if mime.ends_with("+json") => "json",
+        _ if mime.ends_with("+xml") => "xml",
+        _ => return None,
+    };
+    Some(extension)
+}
+
+// Writes out a response that's already in: the spool file when the body
+// outgrew memory, the body itself otherwise.
+pub async fn write(body: &[u8], spooled: Option<Arc<TempPath>>, path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let saved = match spooled {
+        Some(spool) => tokio::fs::copy(&*spool, path).await?,
+        None => {
+            tokio::fs::write(path, body).await?;
+            body.len() as u64
+        }
+    };
+    Ok(format!("Saved {} bytes to {}", saved, path.display()))
+}
+
+// Whether the file at `path` holds the start of the received body, which is
+// what makes it safe to resume; any other file is written over.
+pub async fn holds_start_of(path: &Path, body: &[u8], spooled: Option<&TempPath>) -> std::io::Result<bool> {
+    let Ok(mut file) = File::open(path).await else {
+        return Ok(false);
+    };
+    let existing = file.metadata().await?.len();
+    let mut spool = match spooled {
+        Some(spool) => Some(File::open(spool).await?),
+        None => None,
+    };
+    let received = match &spool {
+        Some(spool) => spool.metadata().await?.len(),
+        None => body.len() as u64,
+    };
+    if existing == 0 || existing > received {
+        return Ok(false);
+    }
+    let mut compared = 0;
+    let mut saved = vec![0; 64 * 1024];
+    let mut expected = vec![0; 64 * 1024];
+    while compared < existing {
+        let n = (existing - compared).min(saved.len() as u64) as usize;
+        file.read_exact(&mut saved[..n]).await?;
+        let expected = match &mut spool {
+            Some(spool) => {
+                spool.read_exact(&mut expected[..n]).await?;
+                &expected[..n]
+            }
+            None => &body[compared as usize..compared as usize + n],
+        };
+        if saved[..n] != *expected {
+            return Ok(false);
+        }
+        compared += n as u64;
+    }
+    Ok(true)
+}
+
+// What If-Range is sent with, so a body that changed since it was received
+// comes back whole instead of being appended to the old one. Weak ETags
+// can't be used there.
+pub fn validator(headers: &[(String, String)]) -> Option<String> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().to_string())
+    };
+    header("etag")
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header("last-modified"))
+}
+
+// Resumes a GET into `path`: only the part missing from the file is asked for
+// with a Range request; a server that doesn't do ranges, or whose body no
+// longer matches `validator`, sends everything and the file starts over.
+pub async fn save(
+    mut request: Request,
+    validator: Option<String>,
+    settings: &RequestSettings,
+    cookies: Arc<CookieStoreMutex>,
+    passphrases: &HashMap<String, String>,
+    path: &Path,
+    progress: &UnboundedSender<DownloadProgress>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let existing = tokio::fs::metadata(path).await.map(|meta| meta.len()).unwrap_or(0);
+    // Ranges count bytes of the body as stored, so ask for it unencoded.
+    request.headers.retain(|(key, _)| {
+        !["range", "if-range", "accept-encoding"].iter().any(|name| key.eq_ignore_ascii_case(name))
+    });
+    request.headers.push(("Accept-Encoding".to_string(), "identity".to_string()));
+    if existing > 0 {
+        request.headers.push(("Range".to_string(), format!("bytes={}-", existing)));
+        if let Some(validator) = validator {
+            request.headers.push(("If-Range".to_string(), validator));
+        }
+    }
+    if let Some(signing) = &settings.signing {
+        signing.sign(&mut request, chrono::Utc::now());
+    }
+
+    let client = request::build_client(settings, cookies, &request::host_of(&request.url), passphrases)?;
+    let method = reqwest::Method::from_bytes(request.method.as_bytes()).unwrap_or(reqwest::Method::GET);
+    let mut builder = client.request(method, &request.url);
+    for (key, value) in &request.headers {
+        builder = builder.header(key, value);
+    }
+    if let Some(body) = request.body.clone() {
+        builder = builder.body(body);
+    }
+    let mut response = builder.send().await?;
+
+    let status = response.status().as_u16();
+    let resumes_at = format!("bytes {}-", existing);
+    let offset = match status {
+        206 if response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|range| range.starts_with(&resumes_at)) =>
+        {
+            existing
+        }
+        206 => return Err("the server sent a different range than asked for".into()),
+        416 if existing > 0 => return Ok(format!("{} is already complete ({} bytes)", path.display(), existing)),
+        200..=299 => 0,
+        _ => return Err(format!("the server answered {} instead of the body", status).into()),
+    };
+
+    let mut file = if offset > 0 {
+        OpenOptions::new().append(true).open(path).await?
+    } else {
+        File::create(path).await?
+    };
+    let total = response.content_length().map(|length| length + offset);
+    let mut received = offset;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        received += chunk.len() as u64;
+        let _ = progress.send(DownloadProgress { received, total });
+    }
+    file.flush().await?;
+
+    Ok(if offset > 0 {
+        format!("Resumed at byte {}, saved {} bytes to {}", offset, received, path.display())
+    } else {
+        format!("Saved {} bytes to {}", received, path.display())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("postsmith-download-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn only_the_start_of_the_body_is_resumed() {
+        let body = b"0123456789";
+        let partial = file("partial", b"01234");
+        assert!(holds_start_of(&partial, body, None).await.unwrap());
+        assert!(!holds_start_of(&file("other", b"abcde"), body, None).await.unwrap());
+        assert!(!holds_start_of(&file("empty", b""), body, None).await.unwrap());
+        assert!(!holds_start_of(&file("longer", b"0123456789!"), body, None).await.unwrap());
+        assert!(!holds_start_of(Path::new("/nonexistent/postsmith"), body, None).await.unwrap());
+
+        // A spooled body is read from its file, past the in-memory buffers.
+        let big: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let spool = TempPath::from_path(file("spool", &big));
+        assert!(holds_start_of(&file("big", &big[..150_000]), &[], Some(&spool)).await.unwrap());
+        let mut changed = big[..150_000].to_vec();
+        changed[100_000] ^= 1;
+        assert!(!holds_start_of(&file("changed", &changed), &[], Some(&spool)).await.unwrap());
+    }
+
+    #[test]
+    fn strong_validators_are_used() {
+        let headers = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+        };
+        let modified = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert_eq!(validator(&headers(&[("ETag", "\"v1\""), ("Last-Modified", modified)])), Some("\"v1\"".to_string()));
+        assert_eq!(validator(&headers(&[("etag", "W/\"v1\""), ("last-modified", modified)])), Some(modified.to_string()));
+        assert_eq!(validator(&headers(&[("Content-Type", "text/plain")])), None);
+    }
+
+    #[tokio::test]
+    async fn resumes_with_range_and_if_range() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buffer = [0; 4096];
+            while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                let n = stream.read(&mut buffer).await.unwrap();
+                head.extend_from_slice(&buffer[..n]);
+            }
+            let reply = "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 5-9/10\r\nContent-Length: 5\r\nConnection: close\r\n\r\n56789";
+            stream.write_all(reply.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&head).to_lowercase()
+        });
+
+        let path = file("resumed", b"01234");
+        let request = Request {
+            method: "GET".to_string(),
+            url: format!("http://127.0.0.1:{}/file", port),
+            headers: Vec::new(),
+            body: None,
+        };
+        let settings = RequestSettings {
+            proxy: Some("none".to_string()),
+            ..Default::default()
+        };
+        let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+        let cookies = Arc::new(CookieStoreMutex::default());
+        let summary = save(request, Some("\"v1\"".to_string()), &settings, cookies, &HashMap::new(), &path, &sender)
+            .await
+            .unwrap();
+        assert!(summary.starts_with("Resumed at byte 5"), "{}", summary);
+        assert_eq!(std::fs::read(&path).unwrap(), b"0123456789");
+        let head = server.await.unwrap();
+        assert!(head.contains("range: bytes=5-\r\n"), "{}", head);
+        assert!(head.contains("if-range: \"v1\"\r\n"), "{}", head);
+    }
+}
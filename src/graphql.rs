@@ -0,0 +1,1102 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use dirs::config_dir;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+pub const INTROSPECTION_QUERY: &str = "query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types {
+      kind name description
+      fields(includeDeprecated: true) {
+        name description
+        args { name description type { ...TypeRef } defaultValue }
+        type { ...TypeRef }
+      }
+      inputFields { name description type { ...TypeRef } defaultValue }
+      enumValues(includeDeprecated: true) { name description }
+      possibleTypes { name }
+    }
+  }
+}
+fragment TypeRef on __Type {
+  kind name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } } } }
+}";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TypeRef {
+    pub kind: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default, rename = "ofType")]
+    pub of_type: Option<Box<TypeRef>>,
+}
+
+impl TypeRef {
+    // The type with lists and non-null stripped off.
+    pub fn named(&self) -> &str {
+        match (&self.name, &self.of_type) {
+            (Some(name), _) => name,
+            (None, Some(inner)) => inner.named(),
+            (None, None) => "",
+        }
+    }
+
+    pub fn display(&self) -> String {
+        match (self.kind.as_str(), &self.of_type) {
+            ("NON_NULL", Some(inner)) => format!("{}!", inner.display()),
+            ("LIST", Some(inner)) => format!("[{}]", inner.display()),
+            _ => self.named().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InputValue {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub type_ref: TypeRef,
+    #[serde(default, rename = "defaultValue")]
+    pub default_value: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub args: Vec<InputValue>,
+    #[serde(rename = "type")]
+    pub type_ref: TypeRef,
+}
+
+impl Field {
+    pub fn signature(&self) -> String {
+        if self.args.is_empty() {
+            return format!("{}: {}", self.name, self.type_ref.display());
+        }
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| format!("{}: {}", arg.name, arg.type_ref.display()))
+            .collect();
+        format!("{}({}): {}", self.name, args.join(", "), self.type_ref.display())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NamedRef {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EnumValue {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TypeDef {
+    pub kind: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub fields: Vec<Field>,
+    #[serde(default, rename = "inputFields", deserialize_with = "null_as_empty")]
+    pub input_fields: Vec<InputValue>,
+    #[serde(default, rename = "enumValues", deserialize_with = "null_as_empty")]
+    pub enum_values: Vec<EnumValue>,
+    #[serde(default, rename = "possibleTypes", deserialize_with = "null_as_empty")]
+    pub possible_types: Vec<NamedRef>,
+}
+
+impl TypeDef {
+    // Objects, interfaces and unions need a selection set.
+    pub fn is_composite(&self) -> bool {
+        matches!(self.kind.as_str(), "OBJECT" | "INTERFACE" | "UNION")
+    }
+}
+
+// Introspection answers `null` rather than `[]` for lists that don't apply
+// to a kind.
+fn null_as_empty<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(Option::<Vec<T>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schema {
+    pub query_type: Option<String>,
+    pub mutation_type: Option<String>,
+    pub subscription_type: Option<String>,
+    pub types: BTreeMap<String, TypeDef>,
+}
+
+impl Schema {
+    // Reads the answer to `INTROSPECTION_QUERY`.
+    pub fn from_introspection(body: &[u8]) -> Result<Schema, String> {
+        let response: Value = serde_json::from_slice(body).map_err(|e| format!("the response isn't JSON: {}", e))?;
+        if let Some(errors) = response.get("errors").and_then(|errors| errors.as_array()) {
+            let messages: Vec<&str> = errors.iter().filter_map(|error| error["message"].as_str()).collect();
+            if !messages.is_empty() {
+                return Err(messages.join("; "));
+            }
+        }
+        let schema = &response["data"]["__schema"];
+        if schema.is_null() {
+            return Err("the response has no data.__schema, introspection may be turned off".to_string());
+        }
+        let root = |key: &str| schema[key]["name"].as_str().map(|name| name.to_string());
+        let types: Vec<TypeDef> = serde_json::from_value(schema["types"].clone()).map_err(|e| e.to_string())?;
+        Ok(Schema {
+            query_type: root("queryType"),
+            mutation_type: root("mutationType"),
+            subscription_type: root("subscriptionType"),
+            types: types.into_iter().map(|type_def| (type_def.name.clone(), type_def)).collect(),
+        })
+    }
+
+    pub fn root(&self, operation: &str) -> Option<&str> {
+        match operation {
+            "mutation" => self.mutation_type.as_deref(),
+            "subscription" => self.subscription_type.as_deref(),
+            _ => self.query_type.as_deref(),
+        }
+    }
+
+    // `__typename` is on every composite type and the two schema fields on
+    // the query root, without being listed in introspection.
+    pub fn field(&self, type_name: &str, field: &str) -> Option<Field> {
+        let named = |kind: &str, name: &str| TypeRef {
+            kind: "NON_NULL".to_string(),
+            name: None,
+            of_type: Some(Box::new(TypeRef {
+                kind: kind.to_string(),
+                name: Some(name.to_string()),
+                of_type: None,
+            })),
+        };
+        let meta = |name: &str, type_ref: TypeRef, args: Vec<InputValue>| Field {
+            name: name.to_string(),
+            description: None,
+            args,
+            type_ref,
+        };
+        let is_query = Some(type_name) == self.query_type.as_deref();
+        match field {
+            "__typename" => return Some(meta(field, named("SCALAR", "String"), Vec::new())),
+            "__schema" if is_query => return Some(meta(field, named("OBJECT", "__Schema"), Vec::new())),
+            "__type" if is_query => {
+                let name = InputValue {
+                    name: "name".to_string(),
+                    description: None,
+                    type_ref: named("SCALAR", "String"),
+                    default_value: None,
+                };
+                let type_ref = TypeRef {
+                    kind: "OBJECT".to_string(),
+                    name: Some("__Type".to_string()),
+                    of_type: None,
+                };
+                return Some(meta(field, type_ref, vec![name]));
+            }
+            _ => {}
+        }
+        self.types
+            .get(type_name)?
+            .fields
+            .iter()
+            .find(|candidate| candidate.name == field)
+            .cloned()
+    }
+}
+
+// Schemas are cached per endpoint in `postsmith/schemas/<hash of url>.json`
+// so the editor can complete right away next time.
+fn cache_path(url: &str) -> std::path::PathBuf {
+    let hash = hex::encode(Sha256::digest(url.trim().as_bytes()));
+    config_dir().unwrap().join("postsmith/schemas").join(format!("{}.json", &hash[..16]))
+}
+
+pub fn load_cached(url: &str) -> Option<Schema> {
+    let data = fs::read_to_string(cache_path(url)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+pub fn save_cached(url: &str, schema: &Schema) -> Result<(), Box<dyn std::error::Error>> {
+    let path = cache_path(url);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string(schema)?)?;
+    Ok(())
+}
+
+// The JSON body for a GraphQL request over HTTP.
+pub fn request_body(query: &str, variables: &str, operation_name: Option<&str>) -> Result<String, String> {
+    let mut body = json!({ "query": query });
+    if !variables.trim().is_empty() {
+        let variables: Value = serde_json::from_str(variables).map_err(|e| format!("variables: {}", e))?;
+        body["variables"] = variables;
+    }
+    if let Some(name) = operation_name {
+        body["operationName"] = json!(name);
+    }
+    Ok(body.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Kind {
+    Name(String),
+    Punct(char),
+    Spread,
+    Variable(String),
+    Value,
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: Kind,
+    start: usize,
+}
+
+// Commas count as whitespace in GraphQL, and `#` starts a comment.
+fn tokenize(doc: &str) -> Vec<Token> {
+    let bytes = doc.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let name_char = |b: u8| b == b'_' || b.is_ascii_alphanumeric();
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b' ' | b'\t' | b'\r' | b'\n' | b',' => i += 1,
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'.' if doc[i..].starts_with("...") => {
+                i += 3;
+                tokens.push(Token { kind: Kind::Spread, start });
+            }
+            b'"' if doc[i..].starts_with("\"\"\"") => {
+                i = doc[i + 3..].find("\"\"\"").map(|end| i + 6 + end).unwrap_or(bytes.len());
+                tokens.push(Token { kind: Kind::Value, start });
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' && bytes[i] != b'\n' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                tokens.push(Token { kind: Kind::Value, start });
+            }
+            b'-' | b'0'..=b'9' => {
+                i += 1;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.' || bytes[i] == b'+' || bytes[i] == b'-') {
+                    i += 1;
+                }
+                tokens.push(Token { kind: Kind::Value, start });
+            }
+            b'$' => {
+                i += 1;
+                while i < bytes.len() && name_char(bytes[i]) {
+                    i += 1;
+                }
+                tokens.push(Token {
+                    kind: Kind::Variable(doc[start + 1..i].to_string()),
+                    start,
+                });
+            }
+            b if b == b'_' || b.is_ascii_alphabetic() => {
+                while i < bytes.len() && name_char(bytes[i]) {
+                    i += 1;
+                }
+                tokens.push(Token {
+                    kind: Kind::Name(doc[start..i].to_string()),
+                    start,
+                });
+            }
+            _ => {
+                let c = doc[i..].chars().next().unwrap_or(' ');
+                i += c.len_utf8();
+                tokens.push(Token { kind: Kind::Punct(c), start });
+            }
+        }
+    }
+    tokens
+}
+
+fn line_of(doc: &str, offset: usize) -> usize {
+    doc[..offset.min(doc.len())].matches('\n').count() + 1
+}
+
+// One executable operation in the document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Operation {
+    pub kind: String,
+    pub name: Option<String>,
+    pub line: usize,
+}
+
+impl Operation {
+    pub fn label(&self) -> String {
+        format!("{} {}", self.kind, self.name.as_deref().unwrap_or("(anonymous)"))
+    }
+}
+
+// Queries, mutations and subscriptions at the top level; fragments are left
+// out.
+pub fn operations(doc: &str) -> Vec<Operation> {
+    let tokens = tokenize(doc);
+    let mut operations = Vec::new();
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate() {
+        match &token.kind {
+            Kind::Punct('{') | Kind::Punct('(') => {
+                if depth == 0 && token.kind == Kind::Punct('{') && (i == 0 || matches!(tokens[i - 1].kind, Kind::Punct('}'))) {
+                    operations.push(Operation {
+                        kind: "query".to_string(),
+                        name: None,
+                        line: line_of(doc, token.start),
+                    });
+                }
+                depth += 1;
+            }
+            Kind::Punct('}') | Kind::Punct(')') => depth = depth.saturating_sub(1),
+            Kind::Name(keyword) if depth == 0 && ["query", "mutation", "subscription"].contains(&keyword.as_str()) => {
+                let name = match tokens.get(i + 1).map(|next| &next.kind) {
+                    Some(Kind::Name(name)) => Some(name.clone()),
+                    _ => None,
+                };
+                operations.push(Operation {
+                    kind: keyword.clone(),
+                    name,
+                    line: line_of(doc, token.start),
+                });
+            }
+            _ => {}
+        }
+    }
+    operations
+}
+
+// Whether a request body built by `request_body` runs a subscription,
+// which has to go over a WebSocket instead.
+pub fn is_subscription(body: &str) -> bool {
+    let Ok(body) = serde_json::from_str::<Value>(body) else {
+        return false;
+    };
+    let operations = operations(body["query"].as_str().unwrap_or_default());
+    let selected = match body["operationName"].as_str() {
+        Some(name) => operations.iter().find(|operation| operation.name.as_deref() == Some(name)),
+        None => operations.first(),
+    };
+    selected.is_some_and(|operation| operation.kind == "subscription")
+}
+
+// What is expected where the document ends, for completion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Field(String),
+    Argument(String, String),
+    Type,
+    FragmentType,
+    Nothing,
+}
+
+// A completion candidate: what gets inserted and a hint shown next to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion {
+    pub text: String,
+    pub detail: String,
+}
+
+// Walks a document the way a GraphQL parser would, checking names against
+// the schema as it goes. It stops at the first syntax error, or at the end
+// of the text, and remembers what it was expecting there.
+struct Walker<'a> {
+    doc: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    schema: &'a Schema,
+    fragments: HashMap<String, String>,
+    problems: Vec<String>,
+    expected: Expected,
+    ended: bool,
+}
+
+// The walk gave up: at the end of the text, or on a syntax error.
+struct Stop;
+
+impl<'a> Walker<'a> {
+    fn new(doc: &'a str, schema: &'a Schema) -> Walker<'a> {
+        let tokens = tokenize(doc);
+        // Fragments can be used before they're defined.
+        let fragments = tokens
+            .windows(4)
+            .filter_map(|window| match (&window[0].kind, &window[1].kind, &window[2].kind, &window[3].kind) {
+                (Kind::Name(keyword), Kind::Name(name), Kind::Name(on), Kind::Name(type_name)) if keyword == "fragment" && on == "on" => {
+                    Some((name.clone(), type_name.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        Walker {
+            doc,
+            tokens,
+            position: 0,
+            schema,
+            fragments,
+            problems: Vec::new(),
+            expected: Expected::Nothing,
+            ended: false,
+        }
+    }
+
+    fn peek(&self) -> Option<&Kind> {
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+
+    fn line(&self) -> usize {
+        let offset = self
+            .tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|token| token.start)
+            .unwrap_or(0);
+        line_of(self.doc, offset)
+    }
+
+    fn problem(&mut self, message: String) {
+        let message = format!("line {}: {}", self.line(), message);
+        self.problems.push(message);
+    }
+
+    // Runs out of tokens expecting `expected`, or returns the next one.
+    fn next(&mut self, expected: Expected) -> Result<Kind, Stop> {
+        match self.tokens.get(self.position) {
+            Some(token) => {
+                self.position += 1;
+                Ok(token.kind.clone())
+            }
+            None => {
+                self.expected = expected;
+                self.ended = true;
+                Err(Stop)
+            }
+        }
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), Stop> {
+        match self.next(Expected::Nothing)? {
+            Kind::Punct(c) if c == punct => Ok(()),
+            other => {
+                self.position -= 1;
+                self.problem(format!("expected `{}`, found {}", punct, describe(&other)));
+                Err(Stop)
+            }
+        }
+    }
+
+    fn name(&mut self, expected: Expected) -> Result<String, Stop> {
+        match self.next(expected)? {
+            Kind::Name(name) => Ok(name),
+            other => {
+                self.position -= 1;
+                self.problem(format!("expected a name, found {}", describe(&other)));
+                Err(Stop)
+            }
+        }
+    }
+
+    fn document(&mut self) -> Result<(), Stop> {
+        while self.position < self.tokens.len() {
+            match self.peek() {
+                Some(Kind::Punct('{')) => {
+                    let root = self.root("query");
+                    self.selection_set(root)?;
+                }
+                Some(Kind::Name(keyword)) if keyword == "fragment" => {
+                    self.position += 1;
+                    self.name(Expected::Nothing)?;
+                    match self.name(Expected::Nothing)?.as_str() {
+                        "on" => {}
+                        other => {
+                            self.problem(format!("expected `on`, found `{}`", other));
+                            return Err(Stop);
+                        }
+                    }
+                    let type_name = self.type_condition()?;
+                    self.directives()?;
+                    self.selection_set(type_name)?;
+                }
+                Some(Kind::Name(keyword)) if ["query", "mutation", "subscription"].contains(&keyword.as_str()) => {
+                    let keyword = keyword.clone();
+                    self.position += 1;
+                    let root = self.root(&keyword);
+                    if let Some(Kind::Name(_)) = self.peek() {
+                        self.position += 1;
+                    }
+                    if let Some(Kind::Punct('(')) = self.peek() {
+                        self.variable_definitions()?;
+                    }
+                    self.directives()?;
+                    self.selection_set(root)?;
+                }
+                Some(other) => {
+                    let other = describe(other);
+                    self.problem(format!("expected an operation or fragment, found {}", other));
+                    return Err(Stop);
+                }
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn root(&mut self, operation: &str) -> Option<String> {
+        if self.schema.types.is_empty() {
+            return None;
+        }
+        let root = self.schema.root(operation).map(|name| name.to_string());
+        if root.is_none() {
+            self.problem(format!("the schema has no {} type", operation));
+        }
+        root
+    }
+
+    fn type_condition(&mut self) -> Result<Option<String>, Stop> {
+        let type_name = self.name(Expected::FragmentType)?;
+        if self.schema.types.is_empty() {
+            return Ok(None);
+        }
+        match self.schema.types.get(&type_name) {
+            Some(type_def) if type_def.is_composite() => Ok(Some(type_name)),
+            Some(_) => {
+                self.problem(format!("fragments can't be on {}, it isn't an object, interface or union", type_name));
+                Ok(None)
+            }
+            None => {
+                self.problem(format!("unknown type {}", type_name));
+                Ok(None)
+            }
+        }
+    }
+
+    // `type_name` is None when the type isn't known, which turns off the
+    // checks below it rather than reporting every field.
+    fn selection_set(&mut self, type_name: Option<String>) -> Result<(), Stop> {
+        self.expect('{')?;
+        let expected = || match &type_name {
+            Some(type_name) => Expected::Field(type_name.clone()),
+            None => Expected::Nothing,
+        };
+        loop {
+            match self.next(expected())? {
+                Kind::Punct('}') => return Ok(()),
+                Kind::Spread => match self.next(Expected::Nothing)? {
+                    Kind::Name(on) if on == "on" => {
+                        let inner = self.type_condition()?;
+                        self.directives()?;
+                        self.selection_set(inner)?;
+                    }
+                    Kind::Name(fragment) => {
+                        if !self.fragments.contains_key(&fragment) {
+                            self.problem(format!("unknown fragment {}", fragment));
+                        }
+                        self.directives()?;
+                    }
+                    Kind::Punct('@') | Kind::Punct('{') => {
+                        self.position -= 1;
+                        self.directives()?;
+                        self.selection_set(type_name.clone())?;
+                    }
+                    other => {
+                        self.position -= 1;
+                        self.problem(format!("expected a fragment name or `on`, found {}", describe(&other)));
+                        return Err(Stop);
+                    }
+                },
+                Kind::Name(name) => {
+                    let mut field_name = name;
+                    if let Some(Kind::Punct(':')) = self.peek() {
+                        self.position += 1;
+                        field_name = self.name(expected())?;
+                    }
+                    self.field(&type_name, &field_name)?;
+                }
+                other => {
+                    self.position -= 1;
+                    self.problem(format!("expected a field, found {}", describe(&other)));
+                    return Err(Stop);
+                }
+            }
+        }
+    }
+
+    fn field(&mut self, type_name: &Option<String>, field_name: &str) -> Result<(), Stop> {
+        let field = match type_name {
+            Some(type_name) => {
+                let field = self.schema.field(type_name, field_name);
+                if field.is_none() {
+                    self.problem(format!("{} has no field {}", type_name, field_name));
+                }
+                field
+            }
+            None => None,
+        };
+        if let Some(Kind::Punct('(')) = self.peek() {
+            self.arguments(type_name.as_deref().zip(field.as_ref()))?;
+        }
+        self.directives()?;
+
+        let inner = field
+            .as_ref()
+            .and_then(|field| self.schema.types.get(field.type_ref.named()));
+        let has_selection = matches!(self.peek(), Some(Kind::Punct('{')));
+        match inner {
+            Some(inner) if inner.is_composite() && !has_selection => {
+                let name = inner.name.clone();
+                self.problem(format!("{} returns {}, pick some of its fields", field_name, name));
+            }
+            Some(inner) if !inner.is_composite() && has_selection => {
+                let name = inner.name.clone();
+                self.problem(format!("{} returns {}, which has no fields to select", field_name, name));
+            }
+            _ => {}
+        }
+        if has_selection {
+            let inner = inner.filter(|inner| inner.is_composite()).map(|inner| inner.name.clone());
+            self.selection_set(inner)?;
+        }
+        Ok(())
+    }
+
+    fn arguments(&mut self, field: Option<(&str, &Field)>) -> Result<(), Stop> {
+        self.expect('(')?;
+        let expected = || match field {
+            Some((type_name, field)) => Expected::Argument(type_name.to_string(), field.name.clone()),
+            None => Expected::Nothing,
+        };
+        loop {
+            match self.next(expected())? {
+                Kind::Punct(')') => return Ok(()),
+                Kind::Name(name) => {
+                    if let Some((_, field)) = field {
+                        if !field.args.iter().any(|arg| arg.name == name) {
+                            self.problem(format!("{} has no argument {}", field.name, name));
+                        }
+                    }
+                    self.expect(':')?;
+                    self.value()?;
+                }
+                other => {
+                    self.position -= 1;
+                    self.problem(format!("expected an argument, found {}", describe(&other)));
+                    return Err(Stop);
+                }
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<(), Stop> {
+        match self.next(Expected::Nothing)? {
+            Kind::Value | Kind::Variable(_) | Kind::Name(_) => Ok(()),
+            Kind::Punct('[') => loop {
+                if let Some(Kind::Punct(']')) = self.peek() {
+                    self.position += 1;
+                    return Ok(());
+                }
+                self.value()?;
+            },
+            Kind::Punct('{') => loop {
+                match self.next(Expected::Nothing)? {
+                    Kind::Punct('}') => return Ok(()),
+                    Kind::Name(_) => {
+                        self.expect(':')?;
+                        self.value()?;
+                    }
+                    other => {
+                        self.position -= 1;
+                        self.problem(format!("expected a field of the input object, found {}", describe(&other)));
+                        return Err(Stop);
+                    }
+                }
+            },
+            other => {
+                self.position -= 1;
+                self.problem(format!("expected a value, found {}", describe(&other)));
+                Err(Stop)
+            }
+        }
+    }
+
+    fn variable_definitions(&mut self) -> Result<(), Stop> {
+        self.expect('(')?;
+        loop {
+            match self.next(Expected::Nothing)? {
+                Kind::Punct(')') => return Ok(()),
+                Kind::Variable(_) => {
+                    self.expect(':')?;
+                    self.variable_type()?;
+                    if let Some(Kind::Punct('=')) = self.peek() {
+                        self.position += 1;
+                        self.value()?;
+                    }
+                    self.directives()?;
+                }
+                other => {
+                    self.position -= 1;
+                    self.problem(format!("expected a variable, found {}", describe(&other)));
+                    return Err(Stop);
+                }
+            }
+        }
+    }
+
+    fn variable_type(&mut self) -> Result<(), Stop> {
+        match self.next(Expected::Type)? {
+            Kind::Punct('[') => {
+                self.variable_type()?;
+                self.expect(']')?;
+            }
+            Kind::Name(name) => {
+                if !self.schema.types.is_empty() && !self.schema.types.contains_key(&name) {
+                    self.problem(format!("unknown type {}", name));
+                }
+            }
+            other => {
+                self.position -= 1;
+                self.problem(format!("expected a type, found {}", describe(&other)));
+                return Err(Stop);
+            }
+        }
+        if let Some(Kind::Punct('!')) = self.peek() {
+            self.position += 1;
+        }
+        Ok(())
+    }
+
+    fn directives(&mut self) -> Result<(), Stop> {
+        while let Some(Kind::Punct('@')) = self.peek() {
+            self.position += 1;
+            self.name(Expected::Nothing)?;
+            if let Some(Kind::Punct('(')) = self.peek() {
+                self.arguments(None)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn describe(kind: &Kind) -> String {
+    match kind {
+        Kind::Name(name) => format!("`{}`", name),
+        Kind::Punct(c) => format!("`{}`", c),
+        Kind::Spread => "`...`".to_string(),
+        Kind::Variable(name) => format!("`${}`", name),
+        Kind::Value => "a value".to_string(),
+    }
+}
+
+// Problems with the document: syntax errors, and with a schema, unknown
+// types, fields and arguments.
+pub fn validate(doc: &str, schema: &Schema) -> Vec<String> {
+    let mut walker = Walker::new(doc, schema);
+    if walker.document().is_err() && walker.ended {
+        walker.problems.push("the document ends too early, a `}` or `)` is missing".to_string());
+    }
+    walker.problems
+}
+
+// Where the word being typed at `cursor` starts.
+pub fn word_start(doc: &str, cursor: usize) -> usize {
+    doc[..cursor]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| *c == '_' || c.is_ascii_alphanumeric())
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(cursor)
+}
+
+// Fields, arguments or type names that fit at `cursor`, starting with the
+// word typed so far.
+pub fn complete(doc: &str, cursor: usize, schema: &Schema) -> Vec<Suggestion> {
+    let start = word_start(doc, cursor);
+    let prefix = &doc[start..cursor];
+    let mut walker = Walker::new(&doc[..start], schema);
+    if walker.document().is_ok() {
+        return Vec::new();
+    }
+    let mut suggestions: Vec<Suggestion> = match &walker.expected {
+        Expected::Field(type_name) => match schema.types.get(type_name) {
+            Some(type_def) => type_def
+                .fields
+                .iter()
+                .map(|field| Suggestion {
+                    text: field.name.clone(),
+                    detail: field.type_ref.display(),
+                })
+                .chain(std::iter::once(Suggestion {
+                    text: "__typename".to_string(),
+                    detail: "String!".to_string(),
+                }))
+                .collect(),
+            None => Vec::new(),
+        },
+        Expected::Argument(type_name, field) => schema
+            .field(type_name, field)
+            .map(|field| {
+                field
+                    .args
+                    .iter()
+                    .map(|arg| Suggestion {
+                        text: arg.name.clone(),
+                        detail: arg.type_ref.display(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Expected::Type | Expected::FragmentType => schema
+            .types
+            .values()
+            .filter(|type_def| !type_def.name.starts_with("__"))
+            .filter(|type_def| walker.expected != Expected::FragmentType || type_def.is_composite())
+            .map(|type_def| Suggestion {
+                text: type_def.name.clone(),
+                detail: type_def.kind.to_lowercase(),
+            })
+            .collect(),
+        Expected::Nothing => Vec::new(),
+    };
+    suggestions.retain(|suggestion| suggestion.text.starts_with(prefix) && suggestion.text != prefix);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(kind: &str, name: &str) -> Value {
+        json!({ "kind": kind, "name": name, "ofType": null })
+    }
+
+    fn non_null(inner: Value) -> Value {
+        json!({ "kind": "NON_NULL", "name": null, "ofType": inner })
+    }
+
+    // Query { user(id: ID!): User, users: [User] }, User { id, name, friends }
+    // and a Subscription root with `updated`.
+    fn schema() -> Schema {
+        let field = |name: &str, type_ref: Value, args: Value| json!({ "name": name, "args": args, "type": type_ref });
+        let object = |name: &str, fields: Vec<Value>| json!({ "kind": "OBJECT", "name": name, "fields": fields });
+        let body = json!({
+            "data": { "__schema": {
+                "queryType": { "name": "Query" },
+                "mutationType": null,
+                "subscriptionType": { "name": "Subscription" },
+                "types": [
+                    object("Query", vec![
+                        field("user", named("OBJECT", "User"), json!([{ "name": "id", "type": non_null(named("SCALAR", "ID")) }])),
+                        field("users", json!({ "kind": "LIST", "name": null, "ofType": named("OBJECT", "User") }), json!([])),
+                    ]),
+                    object("Subscription", vec![field("updated", named("OBJECT", "User"), json!([]))]),
+                    object("User", vec![
+                        field("id", non_null(named("SCALAR", "ID")), json!([])),
+                        field("name", named("SCALAR", "String"), json!([])),
+                        field("friends", named("OBJECT", "User"), json!([])),
+                    ]),
+                    { "kind": "SCALAR", "name": "ID", "fields": null },
+                    { "kind": "SCALAR", "name": "String", "fields": null },
+                    { "kind": "OBJECT", "name": "__Type", "fields": [] },
+                ],
+            } },
+        });
+        Schema::from_introspection(body.to_string().as_bytes()).unwrap()
+    }
+
+    fn kinds(doc: &str) -> Vec<Kind> {
+        tokenize(doc).into_iter().map(|token| token.kind).collect()
+    }
+
+    fn texts(suggestions: Vec<Suggestion>) -> Vec<String> {
+        suggestions.into_iter().map(|suggestion| suggestion.text).collect()
+    }
+
+    #[test]
+    fn tokenizes_names_values_and_punctuation() {
+        let name = |name: &str| Kind::Name(name.to_string());
+        assert_eq!(
+            kinds("query($id: ID!) { user(id: $id, n: -1.5e3) { ...F } }"),
+            vec![
+                name("query"),
+                Kind::Punct('('),
+                Kind::Variable("id".to_string()),
+                Kind::Punct(':'),
+                name("ID"),
+                Kind::Punct('!'),
+                Kind::Punct(')'),
+                Kind::Punct('{'),
+                name("user"),
+                Kind::Punct('('),
+                name("id"),
+                Kind::Punct(':'),
+                Kind::Variable("id".to_string()),
+                name("n"),
+                Kind::Punct(':'),
+                Kind::Value,
+                Kind::Punct(')'),
+                Kind::Punct('{'),
+                Kind::Spread,
+                name("F"),
+                Kind::Punct('}'),
+                Kind::Punct('}'),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_strings_comments_and_multi_byte_text() {
+        assert_eq!(
+            kinds("# a comment { (\n\"a \\\" { b\" \"\"\"block\n\"still\" \"\"\" é"),
+            vec![Kind::Value, Kind::Value, Kind::Punct('é')]
+        );
+        // An unterminated string stops at the end of the line.
+        assert_eq!(kinds("\"open\nname"), vec![Kind::Value, Kind::Name("name".to_string())]);
+        let tokens = tokenize("{\n  ñame");
+        assert_eq!(tokens[1].start, 4);
+        assert_eq!(tokens[1].kind, Kind::Punct('ñ'));
+        assert_eq!(tokens[2].kind, Kind::Name("ame".to_string()));
+    }
+
+    #[test]
+    fn lists_the_operations_but_not_fragments() {
+        let doc = "query A { user(id: 1) { id } }\n\nfragment F on User { id }\nmutation { x }\n{ users { id } }\nsubscription S { updated { id } }";
+        let labels: Vec<(String, usize)> = operations(doc).iter().map(|operation| (operation.label(), operation.line)).collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("query A".to_string(), 1),
+                ("mutation (anonymous)".to_string(), 4),
+                ("query (anonymous)".to_string(), 5),
+                ("subscription S".to_string(), 6),
+            ]
+        );
+        assert_eq!(operations("{ user { id } }")[0].label(), "query (anonymous)");
+        assert!(operations("# nothing here").is_empty());
+    }
+
+    #[test]
+    fn tells_subscriptions_apart() {
+        let doc = "query Q { users { id } } subscription S { updated { id } }";
+        assert!(!is_subscription(&request_body(doc, "", None).unwrap()));
+        assert!(!is_subscription(&request_body(doc, "", Some("Q")).unwrap()));
+        assert!(is_subscription(&request_body(doc, "", Some("S")).unwrap()));
+        assert!(is_subscription(&request_body("subscription { updated { id } }", "{}", None).unwrap()));
+        assert!(!is_subscription("not json"));
+    }
+
+    #[test]
+    fn valid_documents_have_no_problems() {
+        let schema = schema();
+        let doc = "query Q($id: ID!) { user(id: $id) { id ...Names friends { __typename } } }\nfragment Names on User { name }";
+        assert_eq!(validate(doc, &schema), Vec::<String>::new());
+        assert_eq!(validate("{ __type(name: \"User\") { __typename } }", &schema), Vec::<String>::new());
+        // Without a schema only the syntax is checked.
+        assert_eq!(validate("{ anything(at: [1, {a: \"all\"}]) { goes } }", &Schema::default()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_names_the_schema_does_not_have() {
+        let schema = schema();
+        assert_eq!(validate("{ user(id: 1) { nickname } }", &schema), vec!["line 1: User has no field nickname"]);
+        assert_eq!(validate("{ user(name: 1) { id } }", &schema), vec!["line 1: user has no argument name"]);
+        assert_eq!(validate("query($id: Uuid) { users { id } }", &schema), vec!["line 1: unknown type Uuid"]);
+        assert_eq!(validate("{ users { ...Missing } }", &schema), vec!["line 1: unknown fragment Missing"]);
+        assert_eq!(validate("mutation { users { id } }", &schema), vec!["line 1: the schema has no mutation type"]);
+        assert_eq!(
+            validate("fragment F on ID { id }", &schema),
+            vec!["line 1: fragments can't be on ID, it isn't an object, interface or union"]
+        );
+    }
+
+    #[test]
+    fn reports_selections_that_do_not_fit_the_type() {
+        let schema = schema();
+        assert_eq!(
+            validate("{\n  users\n}", &schema),
+            vec!["line 3: users returns User, pick some of its fields"]
+        );
+        assert_eq!(
+            validate("{ users { id { x } } }", &schema),
+            vec!["line 1: id returns ID, which has no fields to select"]
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        let schema = schema();
+        assert_eq!(validate("{ users { id }", &schema), vec!["the document ends too early, a `}` or `)` is missing"]);
+        assert_eq!(
+            validate("{ users { id } }\n)", &schema),
+            vec!["line 2: expected an operation or fragment, found `)`"]
+        );
+        assert_eq!(validate("{ user(id 1) { id } }", &schema), vec!["line 1: expected `:`, found a value"]);
+        assert_eq!(validate("{ users { $x } }", &schema), vec!["line 1: expected a field, found `$x`"]);
+        assert_eq!(validate("fragment F in User { id }", &schema), vec!["line 1: expected `on`, found `in`"]);
+    }
+
+    #[test]
+    fn finds_the_start_of_the_word_being_typed() {
+        assert_eq!(word_start("{ users { na", 12), 10);
+        assert_eq!(word_start("{ users { ", 10), 10);
+        assert_eq!(word_start("", 0), 0);
+        // Multi-byte characters end the word without splitting them.
+        let doc = "{ user(id: \"é\") { ñid";
+        assert_eq!(word_start(doc, doc.len()), doc.len() - 2);
+        assert_eq!(word_start("é", 2), 2);
+    }
+
+    #[test]
+    fn completes_fields_arguments_and_types() {
+        let schema = schema();
+        assert_eq!(texts(complete("{ users { na", 12, &schema)), vec!["name"]);
+        assert_eq!(texts(complete("{ users { ", 10, &schema)), vec!["id", "name", "friends", "__typename"]);
+        assert_eq!(texts(complete("{ user(", 7, &schema)), vec!["id"]);
+        assert_eq!(texts(complete("query($id: I", 12, &schema)), vec!["ID"]);
+        assert_eq!(texts(complete("fragment F on ", 14, &schema)), vec!["Query", "Subscription", "User"]);
+        assert_eq!(texts(complete("subscription { u", 16, &schema)), vec!["updated"]);
+        // Nothing to offer after a finished document or a syntax error.
+        assert!(complete("{ users { id } } ", 17, &schema).is_empty());
+        assert!(complete("{ users { $x n", 14, &schema).is_empty());
+    }
+
+    #[test]
+    fn completes_after_multi_byte_text() {
+        let schema = schema();
+        let doc = "# ünïcode\n{ user(id: \"Zoë 🦀\") { fr";
+        let suggestions = complete(doc, doc.len(), &schema);
+        assert_eq!(suggestions, vec![Suggestion { text: "friends".to_string(), detail: "User".to_string() }]);
+        // The cursor can sit in the middle of the text, after a character
+        // that isn't part of a name.
+        let doc = "{ users { 名前 } }";
+        let cursor = doc.find(" }").unwrap();
+        assert!(complete(doc, cursor, &schema).is_empty());
+    }
+}
@@ -0,0 +1,803 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::Engine;
+use futures_util::stream;
+use hyper_util::rt::TokioIo;
+use prost::Message as _;
+use prost_reflect::{DescriptorPool, DynamicMessage, Kind, MessageDescriptor, MethodDescriptor, SerializeOptions};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tonic::client::Grpc;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::codegen::http::Uri;
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+use tonic_prost::ProstCodec;
+use tonic_reflection::pb::v1::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::v1::{ServerReflectionRequest, ServerReflectionResponse};
+
+use crate::proxy;
+use crate::request::describe_error;
+use crate::settings::RequestSettings;
+use crate::tls::{self, ClientIdentity};
+use crate::websocket::{Connection, Direction, Entry, Event, Outgoing};
+
+// Both versions of the reflection service use the same messages, so older
+// servers that only have v1alpha are asked the same way.
+const REFLECTION: &str = "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo";
+const REFLECTION_ALPHA: &str = "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo";
+// Nested messages in a new request are filled in this deep.
+const TEMPLATE_DEPTH: usize = 4;
+
+// Every method of every service in `pool`, apart from reflection itself.
+pub fn methods(pool: &DescriptorPool) -> Vec<MethodDescriptor> {
+    pool.services()
+        .filter(|service| !service.full_name().starts_with("grpc.reflection."))
+        .flat_map(|service| service.methods().collect::<Vec<_>>())
+        .collect()
+}
+
+// `package.Service/Method`, the way the method is named on the wire.
+pub fn method_name(method: &MethodDescriptor) -> String {
+    format!("{}/{}", method.parent_service().full_name(), method.name())
+}
+
+pub fn streaming(method: &MethodDescriptor) -> &'static str {
+    match (method.is_client_streaming(), method.is_server_streaming()) {
+        (false, false) => "unary",
+        (true, false) => "client stream",
+        (false, true) => "server stream",
+        (true, true) => "bidi stream",
+    }
+}
+
+// Parses `.proto` files. A directory loads every `.proto` file under it, and
+// is also where their imports are looked up, as is each file's own folder.
+pub fn load_protos(paths: &[String]) -> Result<DescriptorPool, Box<dyn std::error::Error>> {
+    let mut includes: Vec<PathBuf> = Vec::new();
+    let mut inputs = Vec::new();
+    for path in paths {
+        let path = PathBuf::from(path);
+        if path.is_dir() {
+            proto_files(&path, &mut inputs)?;
+            includes.push(path);
+        } else {
+            includes.push(path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf());
+            inputs.push(path);
+        }
+    }
+    if inputs.is_empty() {
+        return Err("no .proto files given".into());
+    }
+    includes.dedup();
+    // Imports are kept too, well-known types included.
+    let parsed = protobuf_parse::Parser::new()
+        .pure()
+        .includes(&includes)
+        .inputs(&inputs)
+        .parse_and_typecheck()?;
+    let mut set = protobuf::descriptor::FileDescriptorSet::new();
+    set.file = parsed.file_descriptors;
+    let bytes = protobuf::Message::write_to_bytes(&set)?;
+    Ok(DescriptorPool::decode(bytes.as_slice())?)
+}
+
+fn proto_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            proto_files(&path, files)?;
+        } else if path.extension().is_some_and(|extension| extension == "proto") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Asks the server for its services and the files that define them, along
+// with everything those files import.
+pub async fn reflect(
+    url: &str,
+    headers: &[(String, String)],
+    settings: &RequestSettings,
+    identity: Option<&ClientIdentity>,
+) -> Result<DescriptorPool, Box<dyn std::error::Error>> {
+    let mut grpc = Grpc::new(connect(url, settings, identity).await?);
+    let mut path = PathAndQuery::from_static(REFLECTION);
+    let listed = match ask(&mut grpc, &path, headers, MessageRequest::ListServices(String::new())).await {
+        Err(status) if status.code() == Code::Unimplemented => {
+            path = PathAndQuery::from_static(REFLECTION_ALPHA);
+            ask(&mut grpc, &path, headers, MessageRequest::ListServices(String::new())).await?
+        }
+        listed => listed?,
+    };
+    let MessageResponse::ListServicesResponse(listed) = listed else {
+        return Err("the server didn't list its services".into());
+    };
+
+    let mut files: HashMap<String, FileDescriptorProto> = HashMap::new();
+    let mut asked = HashSet::new();
+    let mut wanted: Vec<MessageRequest> = listed
+        .service
+        .into_iter()
+        .filter(|service| !service.name.starts_with("grpc.reflection."))
+        .map(|service| MessageRequest::FileContainingSymbol(service.name))
+        .collect();
+    while let Some(request) = wanted.pop() {
+        let MessageResponse::FileDescriptorResponse(response) = ask(&mut grpc, &path, headers, request).await? else {
+            continue;
+        };
+        for bytes in response.file_descriptor_proto {
+            let file = FileDescriptorProto::decode(bytes.as_slice())?;
+            for dependency in &file.dependency {
+                if !files.contains_key(dependency) && asked.insert(dependency.clone()) {
+                    wanted.push(MessageRequest::FileByFilename(dependency.clone()));
+                }
+            }
+            asked.insert(file.name().to_string());
+            files.insert(file.name().to_string(), file);
+        }
+    }
+    Ok(DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: files.into_values().collect(),
+    })?)
+}
+
+// One question to the reflection service, on a stream of its own.
+async fn ask(
+    grpc: &mut Grpc<Channel>,
+    path: &PathAndQuery,
+    headers: &[(String, String)],
+    request: MessageRequest,
+) -> Result<MessageResponse, Status> {
+    grpc.ready().await.map_err(|err| Status::unavailable(err.to_string()))?;
+    let mut request = tonic::Request::new(stream::iter([ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(request),
+    }]));
+    add_metadata(request.metadata_mut(), headers).map_err(|err| Status::invalid_argument(err.to_string()))?;
+    let codec = ProstCodec::<ServerReflectionRequest, ServerReflectionResponse>::default();
+    let mut responses = grpc.streaming(request, path.clone(), codec).await?.into_inner();
+    match responses.message().await?.and_then(|response| response.message_response) {
+        Some(MessageResponse::ErrorResponse(error)) => Err(Status::new(Code::from(error.error_code), error.error_message)),
+        Some(response) => Ok(response),
+        None => Err(Status::unknown("the server sent no reflection response")),
+    }
+}
+
+// A JSON object with every field of `message` at its default, as a
+// starting point for the request.
+pub fn template(message: &MessageDescriptor) -> String {
+    serde_json::to_string_pretty(&skeleton(message, &mut Vec::new())).unwrap_or_default()
+}
+
+// `outer` holds the messages being filled in around this one; recursive
+// types stop at the first repeat.
+fn skeleton(message: &MessageDescriptor, outer: &mut Vec<MessageDescriptor>) -> Value {
+    outer.push(message.clone());
+    let mut object = serde_json::Map::new();
+    for field in message.fields() {
+        // Only one field of a oneof may be set.
+        if let Some(oneof) = field.containing_oneof() {
+            if !oneof.is_synthetic() && oneof.fields().next().is_some_and(|first| first != field) {
+                continue;
+            }
+        }
+        let value = if field.is_map() {
+            json!({})
+        } else if field.is_list() {
+            json!([])
+        } else {
+            match field.kind() {
+                // Well-known types have JSON forms of their own (timestamps
+                // are strings, and so on), so they're left for the user.
+                Kind::Message(inner)
+                    if outer.len() < TEMPLATE_DEPTH
+                        && !outer.contains(&inner)
+                        && !inner.full_name().starts_with("google.protobuf.") =>
+                {
+                    skeleton(&inner, outer)
+                }
+                Kind::Message(_) => Value::Null,
+                Kind::Enum(values) => json!(values.default_value().name()),
+                Kind::Bool => json!(false),
+                Kind::String | Kind::Bytes => json!(""),
+                // 64-bit integers are strings in JSON.
+                Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 | Kind::Uint64 | Kind::Fixed64 => json!("0"),
+                Kind::Double | Kind::Float => json!(0.0),
+                _ => json!(0),
+            }
+        };
+        object.insert(field.json_name().to_string(), value);
+    }
+    outer.pop();
+    Value::Object(object)
+}
+
+pub fn parse_message(message: &MessageDescriptor, text: &str) -> Result<DynamicMessage, String> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let parsed = DynamicMessage::deserialize(message.clone(), &mut deserializer).map_err(|err| err.to_string())?;
+    deserializer.end().map_err(|err| err.to_string())?;
+    Ok(parsed)
+}
+
+fn to_json(message: &DynamicMessage) -> String {
+    let options = SerializeOptions::new().skip_default_fields(false);
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .map(|value| serde_json::to_string_pretty(&value).unwrap_or_default())
+        .unwrap_or_else(|err| format!("could not show the message: {}", err))
+}
+
+// Request headers go out as metadata; `-bin` keys take base64 values.
+fn add_metadata(metadata: &mut MetadataMap, headers: &[(String, String)]) -> Result<(), Box<dyn std::error::Error>> {
+    for (key, value) in headers {
+        let key = key.to_lowercase();
+        if key.ends_with("-bin") {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(value.trim())?;
+            metadata.insert_bin(MetadataKey::from_bytes(key.as_bytes())?, MetadataValue::from_bytes(&bytes));
+        } else {
+            metadata.insert(MetadataKey::from_bytes(key.as_bytes())?, MetadataValue::from_str(value)?);
+        }
+    }
+    Ok(())
+}
+
+fn describe_metadata(metadata: &MetadataMap) -> String {
+    metadata
+        .clone()
+        .into_headers()
+        .iter()
+        .map(|(key, value)| format!("{}: {}", key, String::from_utf8_lossy(value.as_bytes())))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn describe_status(status: &Status) -> String {
+    let mut text = format!("{} {:?}", status.code() as i32, status.code());
+    if !status.message().is_empty() {
+        text.push_str(&format!(": {}", status.message()));
+    }
+    if !status.details().is_empty() {
+        text.push_str(&format!(
+            "\ndetails: {}",
+            base64::engine::general_purpose::STANDARD.encode(status.details())
+        ));
+    }
+    text
+}
+
+trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
+
+// http:// is plaintext HTTP/2, https:// goes through the same TLS settings
+// as other requests. The connection goes the way an HTTP request's would:
+// through the proxy, or to the address the resolve setting pins.
+async fn connect(
+    url: &str,
+    settings: &RequestSettings,
+    identity: Option<&ClientIdentity>,
+) -> Result<Channel, Box<dyn std::error::Error>> {
+    let mut endpoint = Endpoint::from_shared(url.trim().to_string())?;
+    if let Some(ms) = settings.connect_timeout_ms {
+        endpoint = endpoint.connect_timeout(Duration::from_millis(ms));
+    }
+    let tls = tokio_native_tls::TlsConnector::from(tls::native_tls_builder(settings, identity)?.request_alpns(&["h2"]).build()?);
+    let settings = settings.clone();
+    let connector = tower::service_fn(move |uri: Uri| {
+        let (tls, settings) = (tls.clone(), settings.clone());
+        async move {
+            let host = uri.host().unwrap_or_default().trim_matches(['[', ']']).to_string();
+            let https = uri.scheme_str() == Some("https");
+            let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+            let tcp = proxy::connect(&settings, &uri.to_string(), &host, port)
+                .await
+                .map_err(|err| io::Error::other(describe_error(err.as_ref())))?;
+            tcp.set_nodelay(true)?;
+            let io: Box<dyn Io> = if https {
+                Box::new(tls.connect(&host, tcp).await.map_err(io::Error::other)?)
+            } else {
+                Box::new(tcp)
+            };
+            Ok::<_, io::Error>(TokioIo::new(io))
+        }
+    });
+    Ok(endpoint.connect_with_connector(connector).await?)
+}
+
+// Messages are only known at runtime, so they're encoded and decoded with
+// the method's descriptors.
+#[derive(Clone)]
+struct DynamicCodec(MessageDescriptor);
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicCodec;
+    type Decoder = DynamicCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.clone()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.clone()
+    }
+}
+
+impl Encoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: DynamicMessage, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        item.encode(dst).map_err(|err| Status::internal(err.to_string()))
+    }
+}
+
+impl Decoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<DynamicMessage>, Status> {
+        DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|err| Status::internal(err.to_string()))
+    }
+}
+
+// Calls `method` with `body` as the first request message and logs the
+// response headers, every message as it arrives, the status and the
+// trailers. Client and bidi streams stay open for more messages until
+// they're ended with `Outgoing::Close`.
+pub fn call(
+    runtime: &tokio::runtime::Runtime,
+    url: String,
+    method: MethodDescriptor,
+    headers: Vec<(String, String)>,
+    settings: RequestSettings,
+    identity: Option<ClientIdentity>,
+    body: String,
+) -> Connection {
+    let (commands, mut receiver) = mpsc::unbounded_channel();
+    let (events, event_receiver) = mpsc::unbounded_channel();
+    runtime.spawn(async move {
+        if let Err(err) = run(&url, &method, &headers, &settings, identity.as_ref(), &body, &mut receiver, &events).await {
+            let _ = events.send(Event::Log(Entry::new(Direction::Info, "error", describe_error(err.as_ref()))));
+        }
+        let _ = events.send(Event::Closed);
+    });
+    Connection {
+        commands,
+        events: event_receiver,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run(
+    url: &str,
+    method: &MethodDescriptor,
+    headers: &[(String, String)],
+    settings: &RequestSettings,
+    identity: Option<&ClientIdentity>,
+    body: &str,
+    commands: &mut UnboundedReceiver<Outgoing>,
+    events: &UnboundedSender<Event>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let log = |entry: Entry| {
+        let _ = events.send(Event::Log(entry));
+    };
+    let first = parse_message(&method.input(), body)?;
+    let path = PathAndQuery::from_str(&format!("/{}", method_name(method)))?;
+    let route = proxy::route(settings, url.trim()).describe();
+    log(Entry::new(
+        Direction::Info,
+        "connect",
+        format!("{}{} ({}), {}", url.trim().trim_end_matches('/'), path, streaming(method), route),
+    ));
+    let mut grpc = Grpc::new(connect(url, settings, identity).await?);
+    grpc.ready().await?;
+    let _ = events.send(Event::Open);
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    log(Entry::new(Direction::Sent, "message", to_json(&first)));
+    sender.send(first)?;
+    // Unary and server-streaming calls take exactly one message.
+    let mut sender = Some(sender).filter(|_| method.is_client_streaming());
+    let outgoing = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|message| (message, receiver))
+    });
+    let mut request = tonic::Request::new(outgoing);
+    add_metadata(request.metadata_mut(), headers)?;
+    // A deadline would cut streams off, so only unary calls get one.
+    if let (Some(ms), false) = (settings.timeout_ms, method.is_client_streaming() || method.is_server_streaming()) {
+        request.set_timeout(Duration::from_millis(ms));
+    }
+
+    // The server may hold its headers back until the client stream ends, so
+    // commands are handled while waiting for them.
+    let response = grpc.streaming(request, path, DynamicCodec(method.output()));
+    tokio::pin!(response);
+    let response = loop {
+        tokio::select! {
+            response = &mut response => break response,
+            command = commands.recv() => {
+                if !forward(command, &mut sender, method, &log) {
+                    return Ok(());
+                }
+            }
+        }
+    };
+    let mut messages = match response {
+        Ok(response) => {
+            log(Entry::new(Direction::Received, "headers", describe_metadata(response.metadata())));
+            response.into_inner()
+        }
+        Err(status) => {
+            log_status(&status, &log);
+            return Ok(());
+        }
+    };
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                if !forward(command, &mut sender, method, &log) {
+                    return Ok(());
+                }
+            }
+            message = messages.message() => match message {
+                Ok(Some(message)) => log(Entry::new(Direction::Received, "message", to_json(&message))),
+                Ok(None) => {
+                    log(Entry::new(Direction::Received, "status", "0 Ok".to_string()));
+                    if let Some(trailers) = messages.trailers().await? {
+                        log(Entry::new(Direction::Received, "trailers", describe_metadata(&trailers)));
+                    }
+                    break;
+                }
+                Err(status) => {
+                    log_status(&status, &log);
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn log_status(status: &Status, log: &impl Fn(Entry)) {
+    log(Entry::new(Direction::Received, "status", describe_status(status)));
+    if !status.metadata().is_empty() {
+        log(Entry::new(Direction::Received, "trailers", describe_metadata(status.metadata())));
+    }
+}
+
+// Hands a command from the composer to the request stream. Returns false
+// once the call should be dropped.
+fn forward(
+    command: Option<Outgoing>,
+    sender: &mut Option<UnboundedSender<DynamicMessage>>,
+    method: &MethodDescriptor,
+    log: &impl Fn(Entry),
+) -> bool {
+    match command {
+        Some(Outgoing::Text(text)) => {
+            let Some(stream) = sender else {
+                log(Entry::new(Direction::Info, "error", "the request stream is closed".to_string()));
+                return true;
+            };
+            match parse_message(&method.input(), &text) {
+                Ok(message) => {
+                    log(Entry::new(Direction::Sent, "message", to_json(&message)));
+                    let _ = stream.send(message);
+                }
+                Err(err) => log(Entry::new(Direction::Info, "error", err)),
+            }
+            true
+        }
+        Some(Outgoing::Binary(_)) | Some(Outgoing::Ping) => {
+            log(Entry::new(Direction::Info, "error", "only JSON messages can be sent on a gRPC stream".to_string()));
+            true
+        }
+        // Ends the request stream if it's still open, otherwise cancels.
+        Some(Outgoing::Close) => {
+            if sender.take().is_some() {
+                log(Entry::new(Direction::Sent, "end", "request stream ended".to_string()));
+                true
+            } else {
+                log(Entry::new(Direction::Info, "cancelled", "call cancelled".to_string()));
+                false
+            }
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::{ready, Future, Ready};
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tonic::body::Body;
+    use tonic::codegen::http;
+    use tonic::server::{NamedService, UnaryService};
+    use tonic::transport::server::TcpIncoming;
+
+    use crate::resolve;
+
+    const PROTO: &str = r#"
+        syntax = "proto3";
+        package test;
+        import "google/protobuf/timestamp.proto";
+
+        enum Mood {
+            MOOD_UNKNOWN = 0;
+            MOOD_GOOD = 1;
+        }
+
+        message Inner {
+            bool flag = 1;
+            double ratio = 2;
+        }
+
+        message Node {
+            string name = 1;
+            Node next = 2;
+        }
+
+        message Ping {
+            string text = 1;
+            int64 count = 2;
+            Inner inner = 3;
+            repeated string tags = 4;
+            map<string, int32> scores = 5;
+            Mood mood = 6;
+            Node node = 7;
+            google.protobuf.Timestamp at = 8;
+            oneof choice {
+                string first = 9;
+                string second = 10;
+            }
+            bytes data = 11;
+        }
+
+        service Echo {
+            rpc Say(Ping) returns (Ping);
+        }
+    "#;
+
+    fn pool(name: &str) -> DescriptorPool {
+        let dir = std::env::temp_dir().join(format!("postsmith-grpc-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("echo.proto"), PROTO).unwrap();
+        load_protos(&[dir.to_string_lossy().to_string()]).unwrap()
+    }
+
+    fn say(pool: &DescriptorPool) -> MethodDescriptor {
+        methods(pool).into_iter().find(|method| method.name() == "Say").unwrap()
+    }
+
+    // test.Echo, answering Say with the request and its text upper-cased.
+    #[derive(Clone)]
+    struct Echo(MessageDescriptor);
+
+    impl NamedService for Echo {
+        const NAME: &'static str = "test.Echo";
+    }
+
+    impl tower::Service<http::Request<Body>> for Echo {
+        type Response = http::Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+            let mut grpc = tonic::server::Grpc::new(DynamicCodec(self.0.clone()));
+            Box::pin(async move { Ok(grpc.unary(Shout, request).await) })
+        }
+    }
+
+    struct Shout;
+
+    impl UnaryService<DynamicMessage> for Shout {
+        type Response = DynamicMessage;
+        type Future = Ready<Result<tonic::Response<DynamicMessage>, Status>>;
+
+        fn call(&mut self, request: tonic::Request<DynamicMessage>) -> Self::Future {
+            let mut message = request.into_inner();
+            let text = message.get_field_by_name("text").unwrap().as_str().unwrap_or_default().to_uppercase();
+            message.set_field_by_name("text", prost_reflect::Value::String(text));
+            ready(Ok(tonic::Response::new(message)))
+        }
+    }
+
+    async fn serve(pool: &DescriptorPool) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let echo = Echo(say(pool).input());
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(echo)
+                .serve_with_incoming(TcpIncoming::from(listener)),
+        );
+        port
+    }
+
+    // An http proxy that tunnels every CONNECT to 127.0.0.1 on the port
+    // asked for, remembering the targets.
+    async fn connect_proxy() -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let log = log.clone();
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut byte = [0; 1];
+                    while !head.ends_with(b"\r\n\r\n") {
+                        stream.read_exact(&mut byte).await.unwrap();
+                        head.push(byte[0]);
+                    }
+                    let target = String::from_utf8_lossy(&head).split(' ').nth(1).unwrap().to_string();
+                    let port = target.rsplit(':').next().unwrap().parse::<u16>().unwrap();
+                    log.lock().unwrap().push(target);
+                    let mut upstream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+                    stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+                });
+            }
+        });
+        (port, seen)
+    }
+
+    // Calls Say with `{"text": "hello"}` on a runtime of its own, with the
+    // URL and settings `setup` gives once the servers it needs are up.
+    // Returns what was logged.
+    fn call_say(
+        pool: &DescriptorPool,
+        setup: impl FnOnce(&tokio::runtime::Runtime) -> (String, RequestSettings),
+    ) -> Vec<(&'static str, String)> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (url, settings) = setup(&runtime);
+        let body = r#"{"text": "hello"}"#.to_string();
+        let mut connection = call(&runtime, url, say(pool), Vec::new(), settings, None, body);
+        let mut log = Vec::new();
+        while let Some(event) = connection.events.blocking_recv() {
+            match event {
+                Event::Log(entry) => log.push((entry.kind, entry.text)),
+                Event::Closed => break,
+                Event::Open => {}
+            }
+        }
+        log
+    }
+
+    // The messages that came back; the first one logged is the one sent.
+    fn received(log: &[(&'static str, String)]) -> Vec<Value> {
+        log.iter()
+            .filter(|(kind, _)| *kind == "message")
+            .skip(1)
+            .map(|(_, text)| serde_json::from_str(text).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn templates_fill_in_every_field() {
+        let pool = pool("template");
+        let template: Value = serde_json::from_str(&template(&say(&pool).input())).unwrap();
+        assert_eq!(
+            template,
+            json!({
+                "text": "",
+                "count": "0",
+                "inner": { "flag": false, "ratio": 0.0 },
+                "tags": [],
+                "scores": {},
+                "mood": "MOOD_UNKNOWN",
+                // Recursion stops at the first repeat.
+                "node": { "name": "", "next": null },
+                "at": null,
+                "first": "",
+                "data": "",
+            })
+        );
+    }
+
+    #[test]
+    fn messages_survive_json_and_the_wire() {
+        let pool = pool("roundtrip");
+        let ping = say(&pool).input();
+        let text = r#"{"text": "hi", "count": "12", "inner": {"flag": true}, "tags": ["a", "b"], "scores": {"x": 3},
+            "mood": "MOOD_GOOD", "at": "2024-01-02T03:04:05Z", "second": "picked", "data": "AAE="}"#;
+        let message = parse_message(&ping, text).unwrap();
+        let bytes = message.encode_to_vec();
+        let decoded = DynamicMessage::decode(ping.clone(), bytes.as_slice()).unwrap();
+        // Messages are compared as JSON: their Debug output walks the
+        // descriptors, which never ends for recursive types.
+        assert_eq!(to_json(&decoded), to_json(&message));
+        let shown = to_json(&decoded);
+        assert_eq!(to_json(&parse_message(&ping, &shown).unwrap()), shown);
+
+        let shown: Value = serde_json::from_str(&shown).unwrap();
+        assert_eq!(shown["count"], "12");
+        assert_eq!(shown["at"], "2024-01-02T03:04:05Z");
+        assert_eq!(shown["second"], "picked");
+        // Defaults are shown too.
+        assert_eq!(shown["inner"]["ratio"], 0.0);
+    }
+
+    #[test]
+    fn bad_messages_are_reported() {
+        let pool = pool("bad");
+        let ping = say(&pool).input();
+        assert!(parse_message(&ping, r#"{"nope": 1}"#).unwrap_err().contains("nope"));
+        assert!(parse_message(&ping, r#"{"count": "many"}"#).is_err());
+        assert!(parse_message(&ping, r#"{"text": "a"} {"text": "b"}"#).is_err());
+    }
+
+    #[test]
+    fn binary_metadata_is_base64() {
+        let mut metadata = MetadataMap::new();
+        add_metadata(&mut metadata, &[("X-Trace-Bin".to_string(), "AAEC".to_string()), ("X-Name".to_string(), "value".to_string())]).unwrap();
+        assert_eq!(metadata.get_bin("x-trace-bin").unwrap().to_bytes().unwrap().as_ref(), &[0, 1, 2]);
+        assert_eq!(metadata.get("x-name").unwrap(), "value");
+        assert!(add_metadata(&mut metadata, &[("x-bad-bin".to_string(), "not base64!".to_string())]).is_err());
+    }
+
+    #[test]
+    fn calls_go_to_the_address_the_resolve_setting_pins() {
+        let pool = pool("resolve");
+        let log = call_say(&pool, |runtime| {
+            let settings = RequestSettings {
+                proxy: Some("none".to_string()),
+                resolve: resolve::parse_overrides("grpc.test=127.0.0.1").unwrap(),
+                ..Default::default()
+            };
+            (format!("http://grpc.test:{}", runtime.block_on(serve(&pool))), settings)
+        });
+        let received = received(&log);
+        assert_eq!(received.len(), 1, "{:?}", log);
+        assert_eq!(received[0]["text"], "HELLO");
+        assert!(log.iter().any(|(kind, text)| *kind == "status" && text == "0 Ok"), "{:?}", log);
+    }
+
+    #[test]
+    fn calls_go_through_the_proxy() {
+        let pool = pool("proxy");
+        let mut seen = None;
+        let log = call_say(&pool, |runtime| {
+            let port = runtime.block_on(serve(&pool));
+            let (proxy_port, proxied) = runtime.block_on(connect_proxy());
+            seen = Some(proxied);
+            let settings = RequestSettings {
+                proxy: Some(format!("http://127.0.0.1:{}", proxy_port)),
+                ..Default::default()
+            };
+            (format!("http://grpc.test:{}", port), settings)
+        });
+        assert_eq!(received(&log)[0]["text"], "HELLO", "{:?}", log);
+        assert!(log[0].1.contains("via http://127.0.0.1:"), "{}", log[0].1);
+        let seen = seen.unwrap().lock().unwrap().clone();
+        assert_eq!(seen.len(), 1);
+        assert!(seen[0].starts_with("grpc.test:"), "{:?}", seen);
+    }
+}
@@ -0,0 +1,112 @@
+// `hexdump -C` style: offset, sixteen bytes in two groups of eight, and the
+// printable ones as ASCII. Stops after `limit` bytes.
+pub fn hex_dump(bytes: &[u8], limit: usize) -> String {
+    let mut lines = String::new();
+    for (row, chunk) in bytes[..bytes.len().min(limit)].chunks(16).enumerate() {
+        let mut hex = String::new();
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(byte) => hex.push_str(&format!("{:02x} ", byte)),
+                None => hex.push_str("   "),
+            }
+            if i == 7 {
+                hex.push(' ');
+            }
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' })
+            .collect();
+        lines.push_str(&format!("{:08x}  {} |{}|\n", row * 16, hex, ascii));
+    }
+    if bytes.len() > limit {
+        lines.push_str(&format!("... {} more bytes\n", bytes.len() - limit));
+    }
+    lines.push_str(&format!("{:08x}\n", bytes.len()));
+    lines
+}
+
+// Whether a body would come out as garbage when shown as text. The
+// Content-Type is trusted when it clearly names a text format; everything
+// else is judged by looking at the bytes.
+pub fn is_binary(bytes: &[u8], content_type: Option<&str>) -> bool {
+    let content_type = content_type.unwrap_or_default().to_lowercase();
+    let textual = content_type.starts_with("text/")
+        || ["json", "xml", "javascript", "x-www-form-urlencoded", "yaml", "graphql"]
+            .iter()
+            .any(|kind| content_type.contains(kind));
+    if textual {
+        return false;
+    }
+    if content_type.starts_with("image/")
+        || content_type.starts_with("audio/")
+        || content_type.starts_with("video/")
+        || ["octet-stream", "protobuf", "grpc", "zip", "gzip", "pdf", "msgpack", "cbor"]
+            .iter()
+            .any(|kind| content_type.contains(kind))
+    {
+        return true;
+    }
+
+    // A multi-byte character cut off at the end of the sample is fine, only
+    // a byte that can't be UTF-8 at all counts.
+    let sample = &bytes[..bytes.len().min(8192)];
+    let invalid_utf8 = match std::str::from_utf8(sample) {
+        Ok(_) => false,
+        Err(e) => e.error_len().is_some(),
+    };
+    invalid_utf8 || sample.contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_hexdump_c() {
+        // printf 'Hello, world!\n\x00\x01\xff' | hexdump -C
+        let dump = hex_dump(b"Hello, world!\n\x00\x01\xff", 1024);
+        assert_eq!(
+            dump,
+            "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 01  |Hello, world!...|\n\
+             00000010  ff                                                |.|\n\
+             00000011\n"
+        );
+    }
+
+    #[test]
+    fn empty_body_is_just_the_length() {
+        assert_eq!(hex_dump(b"", 1024), "00000000\n");
+    }
+
+    #[test]
+    fn stops_at_the_limit() {
+        let bytes = [b'a'; 40];
+        let dump = hex_dump(&bytes, 16);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("|aaaaaaaaaaaaaaaa|"));
+        assert_eq!(lines[1], "... 24 more bytes");
+        assert_eq!(lines[2], "00000028");
+    }
+
+    #[test]
+    fn content_type_decides_when_it_is_clear() {
+        assert!(!is_binary(b"\x00\x01", Some("application/json; charset=utf-8")));
+        assert!(!is_binary(b"\xff", Some("text/plain")));
+        assert!(is_binary(b"plain words", Some("image/png")));
+        assert!(is_binary(b"plain words", Some("application/octet-stream")));
+        assert!(is_binary(b"plain words", Some("application/grpc+proto")));
+    }
+
+    #[test]
+    fn bytes_decide_otherwise() {
+        assert!(!is_binary("naïve text".as_bytes(), None));
+        assert!(is_binary(b"text with a \x00 in it", None));
+        assert!(is_binary(b"\xff\xfe not utf-8", Some("application/x-unknown")));
+        // 'é' cut in half at the end of the sample.
+        let mut cut = vec![b'a'; 8191];
+        cut.extend_from_slice("é".as_bytes());
+        assert!(!is_binary(&cut, None));
+    }
+}
@@ -0,0 +1,216 @@
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::pkey::Id;
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::x509::{X509NameRef, X509Ref, X509VerifyResult};
+use reqwest::Url;
+
+use crate::proxy::{self, Route};
+use crate::resolve;
+use crate::settings::RequestSettings;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Connects to the host of an https URL, through the proxy if one is in use,
+// and describes what the server presented, whether or not it would pass
+// verification. Blocks, so it's run with `spawn_blocking`.
+pub fn inspect(url: &str, settings: &RequestSettings) -> Result<String, Box<dyn std::error::Error>> {
+    let url = Url::parse(url)?;
+    if url.scheme() != "https" {
+        return Err("the certificate inspector needs an https:// URL".into());
+    }
+    let host = url.host_str().ok_or("URL has no host")?.to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let mut builder = SslConnector::builder(SslMethod::tls_client())?;
+    if let Some(ca_bundle) = &settings.ca_bundle {
+        builder.set_ca_file(ca_bundle)?;
+    }
+    // Let the handshake finish whatever the verdict, it is read back from
+    // `verify_result` once the chain has been captured.
+    builder.set_verify_callback(SslVerifyMode::PEER, |_, _| true);
+    let connector = builder.build();
+
+    let route = proxy::route(settings, url.as_str());
+    let (stream, address) = match &route {
+        Route::Proxy(proxy, _) => {
+            let host = host.trim_matches(['[', ']']);
+            (proxy::tunnel(proxy, settings, host, port, TIMEOUT)?, route.describe())
+        }
+        Route::Direct(_) => {
+            let pinned = settings
+                .resolve
+                .as_deref()
+                .and_then(|overrides| resolve::lookup(overrides, &host))
+                .map(|entry| SocketAddr::new(entry.address, port));
+            let address = match pinned {
+                Some(address) => address,
+                None => (host.as_str(), port)
+                    .to_socket_addrs()?
+                    .next()
+                    .ok_or_else(|| format!("could not resolve {}", host))?,
+            };
+            let stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            (stream, address.to_string())
+        }
+    };
+    let stream = connector
+        .connect(&host, stream)
+        .map_err(|e| format!("TLS handshake with {}:{} failed: {}", host, port, e))?;
+    let ssl = stream.ssl();
+
+    let mut report = format!("TLS inspection of {}:{} ({})\n\n", host, port, address);
+    report.push_str(&format!("Protocol: {}\n", ssl.version_str()));
+    report.push_str(&format!(
+        "Cipher:   {}\n",
+        ssl.current_cipher().map(|cipher| cipher.name()).unwrap_or("unknown")
+    ));
+
+    let verdict = ssl.verify_result();
+    if verdict == X509VerifyResult::OK {
+        report.push_str("Verification: OK\n");
+    } else {
+        report.push_str(&format!("Verification: FAILED ({})\n", verdict.error_string()));
+        report.push_str(&format!("  {}\n", explain(verdict, &host)));
+    }
+
+    report.push_str("\nCertificate chain, as presented by the server:\n");
+    match ssl.peer_cert_chain() {
+        Some(chain) if !chain.is_empty() => {
+            for (i, cert) in chain.iter().enumerate() {
+                report.push_str(&describe(i, cert)?);
+            }
+        }
+        _ => report.push_str("  (the server sent no certificates)\n"),
+    }
+
+    Ok(report)
+}
+
+fn explain(verdict: X509VerifyResult, host: &str) -> String {
+    // Raw X509_V_ERR_* codes; the openssl crate only names a couple of them.
+    match verdict.as_raw() {
+        9 => "The certificate is not valid yet. Check its start date and the local clock.".to_string(),
+        10 => "The certificate has expired and needs to be renewed on the server.".to_string(),
+        18 => "The server certificate is self-signed. Add it to a ca_bundle or turn off verify_tls.".to_string(),
+        19 => "The chain ends in a self-signed root that isn't trusted. Add the root to a ca_bundle.".to_string(),
+        2 | 20 => "The issuer of a certificate in the chain is unknown. The server may be missing an intermediate, or the root belongs to a private CA that needs a ca_bundle.".to_string(),
+        21 => "Only the leaf certificate was sent and it can't be traced to a trusted root. The server should send its intermediates.".to_string(),
+        23 => "The certificate has been revoked by its issuer.".to_string(),
+        26 => "The certificate isn't allowed to be used for TLS server authentication.".to_string(),
+        7 | 8 => "A signature in the chain doesn't match; the chain is broken or tampered with.".to_string(),
+        62 => format!("None of the certificate's names match {}. Compare the SANs below with the host in the URL.", host),
+        _ => "See the chain below for details.".to_string(),
+    }
+}
+
+fn name(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn fingerprint(cert: &X509Ref, digest: MessageDigest) -> Result<String, openssl::error::ErrorStack> {
+    Ok(cert
+        .digest(digest)?
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":"))
+}
+
+fn key_type(cert: &X509Ref) -> Result<String, openssl::error::ErrorStack> {
+    let key = cert.public_key()?;
+    let kind = match key.id() {
+        Id::RSA => "RSA".to_string(),
+        Id::EC => {
+            let curve = key
+                .ec_key()
+                .ok()
+                .and_then(|ec| ec.group().curve_name())
+                .and_then(|nid| nid.short_name().ok())
+                .unwrap_or("unknown curve");
+            format!("EC ({})", curve)
+        }
+        Id::ED25519 => "Ed25519".to_string(),
+        Id::ED448 => "Ed448".to_string(),
+        Id::DSA => "DSA".to_string(),
+        _ => "unknown".to_string(),
+    };
+    Ok(format!("{} {} bits", kind, key.bits()))
+}
+
+fn describe(index: usize, cert: &X509Ref) -> Result<String, Box<dyn std::error::Error>> {
+    let now = Asn1Time::days_from_now(0)?;
+    let validity = if cert.not_after() < now {
+        " EXPIRED"
+    } else if cert.not_before() > now {
+        " NOT YET VALID"
+    } else {
+        ""
+    };
+
+    let sans = cert
+        .subject_alt_names()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|san| {
+                    san.dnsname()
+                        .map(|dns| format!("DNS:{}", dns))
+                        .or_else(|| san.ipaddress().map(|ip| format!("IP:{}", format_ip(ip))))
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_else(|| "none".to_string());
+
+    Ok(format!(
+        "\n[{}] Subject: {}\n    Issuer:  {}\n    SANs:    {}\n    Valid:   {} - {}{}\n    Key:     {}\n    Signed:  {}\n    SHA-256: {}\n    SHA-1:   {}\n",
+        index,
+        name(cert.subject_name()),
+        name(cert.issuer_name()),
+        sans,
+        cert.not_before(),
+        cert.not_after(),
+        validity,
+        key_type(cert)?,
+        cert.signature_algorithm().object().nid().long_name().unwrap_or("unknown"),
+        fingerprint(cert, MessageDigest::sha256())?,
+        fingerprint(cert, MessageDigest::sha1())?,
+    ))
+}
+
+fn format_ip(ip: &[u8]) -> String {
+    match ip.len() {
+        4 => ip.iter().map(|b| b.to_string()).collect::<Vec<_>>().join("."),
+        16 => ip
+            .chunks(2)
+            .map(|pair| format!("{:x}", u16::from_be_bytes([pair[0], pair[1]])))
+            .collect::<Vec<_>>()
+            .join(":"),
+        _ => hex::encode(ip),
+    }
+}
+
+// Whether a failed request is worth running the inspector for.
+pub fn is_tls_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["certificate", "tls", "ssl", "handshake"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
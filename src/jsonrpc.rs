@@ -0,0 +1,248 @@
+use serde_json::{json, Map, Value};
+
+pub const VERSION: &str = "2.0";
+
+// Which method each id was sent for.
+pub type Ids = Vec<(u64, String)>;
+
+// One call in the JSON-RPC body tab. Notifications go out without an id
+// and get no reply.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call {
+    pub method: String,
+    // JSON text; empty leaves `params` out.
+    pub params: String,
+    pub notification: bool,
+}
+
+impl Call {
+    pub fn new() -> Self {
+        Self {
+            method: String::new(),
+            params: String::new(),
+            notification: false,
+        }
+    }
+}
+
+// A reply matched up with the call it answers, by id.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reply {
+    pub id: Value,
+    pub method: Option<String>,
+    pub outcome: Outcome,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Result(Value),
+    Error {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+    // Neither a result nor an error, or both.
+    Invalid(Value),
+    // A call that had an id but got nothing back.
+    Missing,
+}
+
+// Reads the calls back out of a body built by `request_body`; a single
+// object is one call, an array is a batch.
+pub fn parse_calls(body: &str) -> Result<Vec<Call>, String> {
+    let value: Value = serde_json::from_str(body).map_err(|err| format!("the body isn't JSON: {}", err))?;
+    let objects = match value {
+        Value::Array(objects) => objects,
+        object => vec![object],
+    };
+    objects
+        .iter()
+        .map(|object| {
+            let method = object["method"]
+                .as_str()
+                .ok_or_else(|| "every call needs a method".to_string())?;
+            Ok(Call {
+                method: method.to_string(),
+                params: match object.get("params") {
+                    Some(params) => serde_json::to_string_pretty(params).unwrap_or_default(),
+                    None => String::new(),
+                },
+                notification: object.get("id").is_none(),
+            })
+        })
+        .collect()
+}
+
+// The body for `calls`, with `jsonrpc` set and ids counted up from
+// `next_id`. Also returns which method each id belongs to.
+pub fn request_body(calls: &[Call], next_id: &mut u64) -> Result<(String, Ids), String> {
+    if calls.is_empty() {
+        return Err("there are no calls".to_string());
+    }
+    let mut ids = Vec::new();
+    let mut objects = Vec::new();
+    for call in calls {
+        let method = call.method.trim();
+        if method.is_empty() {
+            return Err("every call needs a method".to_string());
+        }
+        let mut object = Map::new();
+        object.insert("jsonrpc".to_string(), json!(VERSION));
+        object.insert("method".to_string(), json!(method));
+        if !call.params.trim().is_empty() {
+            let params: Value = serde_json::from_str(&call.params)
+                .map_err(|err| format!("the params of {} aren't JSON: {}", method, err))?;
+            if !params.is_array() && !params.is_object() {
+                return Err(format!("the params of {} must be an array or an object", method));
+            }
+            object.insert("params".to_string(), params);
+        }
+        if !call.notification {
+            object.insert("id".to_string(), json!(*next_id));
+            ids.push((*next_id, method.to_string()));
+            *next_id += 1;
+        }
+        objects.push(Value::Object(object));
+    }
+    let body = if objects.len() == 1 {
+        objects.remove(0)
+    } else {
+        Value::Array(objects)
+    };
+    Ok((serde_json::to_string_pretty(&body).unwrap_or_default(), ids))
+}
+
+// The replies in a response body in the order the calls were made, or None
+// when the body isn't JSON-RPC at all. `ids` is what `request_body`
+// returned for the request.
+pub fn replies(body: &[u8], ids: &[(u64, String)]) -> Option<Vec<Reply>> {
+    let value: Value = serde_json::from_slice(body).ok()?;
+    let objects = match value {
+        Value::Array(objects) => objects,
+        object @ Value::Object(_) => vec![object],
+        _ => return None,
+    };
+    if !objects.iter().all(|object| object.get("jsonrpc").is_some_and(|version| version == VERSION)) {
+        return None;
+    }
+
+    let method_of = |id: &Value| id.as_u64().and_then(|id| ids.iter().find(|(sent, _)| *sent == id)).map(|(_, method)| method.clone());
+    let mut replies: Vec<Reply> = objects
+        .into_iter()
+        .map(|object| {
+            let id = object.get("id").cloned().unwrap_or(Value::Null);
+            let outcome = match (object.get("result"), object.get("error")) {
+                (Some(result), None) => Outcome::Result(result.clone()),
+                (None, Some(error)) if error.is_object() => Outcome::Error {
+                    code: error["code"].as_i64().unwrap_or_default(),
+                    message: error["message"].as_str().unwrap_or_default().to_string(),
+                    data: error.get("data").cloned(),
+                },
+                _ => Outcome::Invalid(object.clone()),
+            };
+            Reply {
+                method: method_of(&id),
+                id,
+                outcome,
+            }
+        })
+        .collect();
+    // Servers may answer a batch in any order.
+    replies.sort_by_key(|reply| reply.id.as_u64().and_then(|id| ids.iter().position(|(sent, _)| *sent == id)).unwrap_or(usize::MAX));
+    for (id, method) in ids {
+        if !replies.iter().any(|reply| reply.id.as_u64() == Some(*id)) {
+            replies.push(Reply {
+                id: json!(id),
+                method: Some(method.clone()),
+                outcome: Outcome::Missing,
+            });
+        }
+    }
+    Some(replies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(method: &str, params: &str, notification: bool) -> Call {
+        Call {
+            method: method.to_string(),
+            params: params.to_string(),
+            notification,
+        }
+    }
+
+    #[test]
+    fn numbers_calls_but_not_notifications() {
+        let calls = [call("add", "[1, 2]", false), call("log", "", true), call("sub", "{\"a\": 1}", false)];
+        let mut next_id = 7;
+        let (body, ids) = request_body(&calls, &mut next_id).unwrap();
+        assert_eq!(ids, vec![(7, "add".to_string()), (8, "sub".to_string())]);
+        assert_eq!(next_id, 9);
+        let sent: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(sent[0], json!({"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 7}));
+        assert_eq!(sent[1], json!({"jsonrpc": "2.0", "method": "log"}));
+        assert_eq!(parse_calls(&body).unwrap(), vec![
+            call("add", "[\n  1,\n  2\n]", false),
+            call("log", "", true),
+            call("sub", "{\n  \"a\": 1\n}", false),
+        ]);
+
+        assert!(request_body(&[call("add", "3", false)], &mut next_id).is_err());
+        assert!(request_body(&[call(" ", "", false)], &mut next_id).is_err());
+    }
+
+    #[test]
+    fn matches_batch_replies_to_ids() {
+        let ids = vec![(1, "add".to_string()), (2, "sub".to_string()), (3, "mul".to_string()), (4, "div".to_string())];
+        // Out of order, one error, one malformed and one unanswered.
+        let body = br#"[
+            {"jsonrpc": "2.0", "id": 3, "result": 6, "error": null},
+            {"jsonrpc": "2.0", "id": 2, "error": {"code": -32601, "message": "Method not found", "data": "sub"}},
+            {"jsonrpc": "2.0", "id": 1, "result": 3}
+        ]"#;
+        let replies = replies(body, &ids).unwrap();
+        assert_eq!(replies.len(), 4);
+        assert_eq!(replies[0], Reply {
+            id: json!(1),
+            method: Some("add".to_string()),
+            outcome: Outcome::Result(json!(3)),
+        });
+        assert_eq!(replies[1], Reply {
+            id: json!(2),
+            method: Some("sub".to_string()),
+            outcome: Outcome::Error {
+                code: -32601,
+                message: "Method not found".to_string(),
+                data: Some(json!("sub")),
+            },
+        });
+        assert_eq!(replies[2].method.as_deref(), Some("mul"));
+        assert!(matches!(replies[2].outcome, Outcome::Invalid(_)));
+        assert_eq!(replies[3], Reply {
+            id: json!(4),
+            method: Some("div".to_string()),
+            outcome: Outcome::Missing,
+        });
+    }
+
+    #[test]
+    fn keeps_replies_to_unknown_ids() {
+        let ids = vec![(1, "add".to_string())];
+        let replies = replies(br#"{"jsonrpc": "2.0", "id": null, "error": {"code": -32700, "message": "Parse error"}}"#, &ids).unwrap();
+        assert_eq!(replies[0].id, Value::Null);
+        assert_eq!(replies[0].method, None);
+        assert!(matches!(replies[0].outcome, Outcome::Error { code: -32700, .. }));
+        assert_eq!(replies[1].outcome, Outcome::Missing);
+    }
+
+    #[test]
+    fn ignores_bodies_that_are_not_jsonrpc() {
+        let ids = vec![(1, "add".to_string())];
+        assert_eq!(replies(b"not json", &ids), None);
+        assert_eq!(replies(b"[1, 2]", &ids), None);
+        assert_eq!(replies(br#"{"id": 1, "result": 3}"#, &ids), None);
+        assert_eq!(replies(br#"{"jsonrpc": "1.0", "id": 1, "result": 3}"#, &ids), None);
+    }
+}
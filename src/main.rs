@@ -1,9 +1,33 @@
 mod components;
+mod cookies;
+mod diff;
+mod download;
+mod graphql;
+mod grpc;
+mod hexdump;
+mod inspector;
+mod jsonrpc;
+mod preview;
+mod proxy;
+mod raw;
 mod request;
+mod resolve;
 mod ui;
 mod session;
+mod settings;
+mod signing;
+mod soap;
+mod sse;
+mod stream;
+mod subscription;
+mod timing;
+mod tls;
+mod unix;
+mod websocket;
+mod wire;
 
 use std::io;
+use std::time::Duration;
 
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event},
@@ -44,8 +68,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app_state: &mut AppState) -> io::Result<()> {
     loop {    
+        app_state.poll_request();
+        app_state.poll_download();
+        app_state.poll_comparison();
+        app_state.poll_websocket();
+        app_state.poll_event_stream();
+        app_state.poll_introspection();
+        app_state.poll_reflection();
+        app_state.poll_wsdl();
+        app_state.poll_raw();
+        app_state.poll_inspection();
         draw_ui(terminal, app_state)?;
-        
+
+        // While requests are running in the background, wake up regularly to
+        // show how they're doing.
+        if app_state.is_busy() && !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
             if app_state.handle_key_event(key.code) {
                 break;
@@ -0,0 +1,241 @@
+use std::io::{Cursor, Write};
+
+use base64::Engine;
+use image::{DynamicImage, GenericImageView, ImageFormat, ImageReader, Limits, RgbaImage};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+// Big enough for any terminal panel; decoded images are shrunk to this once
+// so redrawing doesn't resample the original every frame.
+const THUMBNAIL_SIZE: u32 = 640;
+// Anything bigger isn't decoded at all, a few bytes of header could
+// otherwise ask for gigabytes of pixels.
+const MAX_DIMENSION: u32 = 16384;
+// Terminals don't tell us their cell size without a round trip, so sixel
+// output assumes the common 10x20 pixel cell.
+const CELL_WIDTH: u32 = 10;
+const CELL_HEIGHT: u32 = 20;
+
+// How images can be drawn in the current terminal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Graphics {
+    HalfBlocks,
+    Kitty,
+    Sixel,
+}
+
+impl Graphics {
+    // Guessed from the environment; querying the terminal would mean reading
+    // its answer from the same stdin the UI reads keys from.
+    pub fn detect() -> Graphics {
+        let var = |name: &str| std::env::var(name).unwrap_or_default().to_lowercase();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+        if term.contains("kitty") || std::env::var("KITTY_WINDOW_ID").is_ok() || ["wezterm", "ghostty"].contains(&program.as_str()) {
+            Graphics::Kitty
+        } else if term.contains("sixel") || ["foot", "mlterm"].iter().any(|name| term.starts_with(name)) || program == "mlterm" {
+            Graphics::Sixel
+        } else {
+            Graphics::HalfBlocks
+        }
+    }
+}
+
+pub struct Preview {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    thumbnail: DynamicImage,
+}
+
+impl Preview {
+    pub fn decode(bytes: &[u8]) -> Option<Preview> {
+        let format = image::guess_format(bytes).ok()?;
+        if !matches!(format, ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP) {
+            return None;
+        }
+        let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(MAX_DIMENSION);
+        limits.max_image_height = Some(MAX_DIMENSION);
+        reader.limits(limits);
+        let image = reader.decode().ok()?;
+        let (width, height) = image.dimensions();
+        Some(Preview {
+            format,
+            width,
+            height,
+            thumbnail: image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE),
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} image, {}x{} px",
+            self.format.extensions_str().first().unwrap_or(&"").to_uppercase(),
+            self.width,
+            self.height
+        )
+    }
+
+    // The largest box of cells with the image's aspect ratio that fits in
+    // `area`, counting a cell as twice as tall as it is wide.
+    pub fn fit(&self, area: Rect) -> Rect {
+        let (width, height) = self.thumbnail.dimensions();
+        let (width, height) = (width.max(1) as f64, height.max(1) as f64);
+        let scale = (area.width as f64 / width).min(area.height as f64 * 2.0 / height);
+        Rect::new(
+            area.x,
+            area.y,
+            ((width * scale).round() as u16).clamp(1, area.width.max(1)),
+            ((height * scale / 2.0).round() as u16).clamp(1, area.height.max(1)),
+        )
+    }
+
+    // One line per cell row; every cell is an upper half block whose
+    // foreground is the top pixel and background the bottom one.
+    pub fn half_blocks(&self, area: Rect) -> Vec<Line<'static>> {
+        let cells = self.fit(area);
+        let pixels = flatten(&self.thumbnail.resize_exact(
+            cells.width as u32,
+            cells.height as u32 * 2,
+            image::imageops::FilterType::Triangle,
+        ));
+        (0..cells.height as u32)
+            .map(|row| {
+                Line::from(
+                    (0..cells.width as u32)
+                        .map(|column| {
+                            let top = pixels.get_pixel(column, row * 2);
+                            let bottom = pixels.get_pixel(column, row * 2 + 1);
+                            Span::styled(
+                                "▀",
+                                Style::default()
+                                    .fg(Color::Rgb(top[0], top[1], top[2]))
+                                    .bg(Color::Rgb(bottom[0], bottom[1], bottom[2])),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect()
+    }
+
+    // Escape sequence that draws the image over `area` at the cursor.
+    pub fn encode(&self, graphics: Graphics, area: Rect) -> Option<Vec<u8>> {
+        let cells = self.fit(area);
+        match graphics {
+            Graphics::HalfBlocks => None,
+            Graphics::Kitty => {
+                let mut png = Vec::new();
+                self.thumbnail.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+                Some(kitty(&png, cells))
+            }
+            Graphics::Sixel => {
+                let pixels = flatten(&self.thumbnail.resize_exact(
+                    cells.width as u32 * CELL_WIDTH,
+                    cells.height as u32 * CELL_HEIGHT,
+                    image::imageops::FilterType::Triangle,
+                ));
+                Some(sixel(&pixels))
+            }
+        }
+    }
+}
+
+// Transparent pixels are blended onto black, the usual terminal background.
+fn flatten(image: &DynamicImage) -> RgbaImage {
+    let mut pixels = image.to_rgba8();
+    for pixel in pixels.pixels_mut() {
+        let alpha = pixel[3] as u16;
+        for channel in 0..3 {
+            pixel[channel] = (pixel[channel] as u16 * alpha / 255) as u8;
+        }
+        pixel[3] = 255;
+    }
+    pixels
+}
+
+// Kitty graphics protocol: a PNG sent in base64 chunks of at most 4096
+// bytes, scaled by the terminal to `cells`.
+fn kitty(png: &[u8], cells: Rect) -> Vec<u8> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+    let mut out = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            let _ = write!(out, "\x1b_Ga=T,f=100,q=2,c={},r={},m={};", cells.width, cells.height, more);
+        } else {
+            let _ = write!(out, "\x1b_Gm={};", more);
+        }
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\x1b\\");
+    }
+    out
+}
+
+pub fn kitty_clear() -> &'static [u8] {
+    b"\x1b_Ga=d,q=2\x1b\\"
+}
+
+// Sixel with a fixed 6x6x6 color cube, which is plenty for a preview.
+fn sixel(pixels: &RgbaImage) -> Vec<u8> {
+    let (width, height) = pixels.dimensions();
+    let level = |value: u8| (value as u32 * 5 + 127) / 255;
+    let index = |x: u32, y: u32| {
+        let pixel = pixels.get_pixel(x, y);
+        (level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2])) as usize
+    };
+
+    let mut out = Vec::new();
+    let _ = write!(out, "\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for color in 0..216u32 {
+        let percent = |step: u32| step * 100 / 5;
+        let _ = write!(out, "#{};2;{};{};{}", color, percent(color / 36), percent(color / 6 % 6), percent(color % 6));
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = (height - band).min(6);
+        let mut used = [false; 216];
+        for y in band..band + rows {
+            for x in 0..width {
+                used[index(x, y)] = true;
+            }
+        }
+        for (color, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+            let _ = write!(out, "#{}", color);
+            let mut run: Option<(u8, u32)> = None;
+            for x in 0..width {
+                let bits = (0..rows)
+                    .filter(|row| index(x, band + row) == color)
+                    .fold(0u8, |bits, row| bits | (1 << row));
+                let symbol = 63 + bits;
+                run = match run {
+                    Some((previous, count)) if previous == symbol => Some((previous, count + 1)),
+                    Some((previous, count)) => {
+                        push_run(&mut out, previous, count);
+                        Some((symbol, 1))
+                    }
+                    None => Some((symbol, 1)),
+                };
+            }
+            if let Some((symbol, count)) = run {
+                push_run(&mut out, symbol, count);
+            }
+            out.push(b'$');
+        }
+        out.push(b'-');
+    }
+    out.extend_from_slice(b"\x1b\\");
+    out
+}
+
+fn push_run(out: &mut Vec<u8>, symbol: u8, count: u32) {
+    if count > 3 {
+        let _ = write!(out, "!{}", count);
+        out.push(symbol);
+    } else {
+        out.extend(std::iter::repeat_n(symbol, count as usize));
+    }
+}
@@ -0,0 +1,639 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::hexdump;
+use crate::resolve;
+use crate::settings::RequestSettings;
+use crate::tls::{self, ClientIdentity};
+
+// Without a timeout in the settings, a server that never answers is given
+// up on after this long.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+// Once a whole response is in, reading goes on only while more keeps
+// coming, e.g. responses to pipelined requests.
+const QUIET: Duration = Duration::from_millis(500);
+// Responses after this many are left unparsed.
+const MAX_MESSAGES: usize = 16;
+// Reading stops once this much has come back.
+const MAX_RECEIVED: usize = 16 * 1024 * 1024;
+const HEX_DUMP_LIMIT: usize = 256 * 1024;
+
+// Where the request goes, taken from the URL. The path and query are only
+// used for the request line of the template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub path: String,
+}
+
+impl Target {
+    pub fn parse(url: &str) -> Result<Target, String> {
+        let url = reqwest::Url::parse(url.trim()).map_err(|err| format!("{} is not a URL: {}", url.trim(), err))?;
+        let tls = match url.scheme() {
+            "http" | "ws" => false,
+            "https" | "wss" => true,
+            scheme => return Err(format!("raw requests go over TCP or TLS, not {}", scheme)),
+        };
+        let host = url.host_str().ok_or("the URL has no host")?.trim_matches(['[', ']']).to_string();
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        Ok(Target {
+            port: url.port_or_known_default().unwrap_or(if tls { 443 } else { 80 }),
+            host,
+            tls,
+            path,
+        })
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}://{}:{}", if self.tls { "tls" } else { "tcp" }, self.bracketed(), self.port)
+    }
+
+    fn bracketed(&self) -> String {
+        if self.host.contains(':') { format!("[{}]", self.host) } else { self.host.clone() }
+    }
+
+    // The Host header value; the port only when it isn't the default.
+    fn authority(&self) -> String {
+        let host = self.bracketed();
+        match (self.tls, self.port) {
+            (false, 80) | (true, 443) => host,
+            _ => format!("{}:{}", host, self.port),
+        }
+    }
+}
+
+// The editor text as bytes: every line break goes out as CRLF, and `\xHH`
+// stands for any byte, e.g. `\x0a` for a bare LF or `\x00` for a NUL.
+pub fn encode(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for (row, line) in text.split('\n').enumerate() {
+        if row > 0 {
+            bytes.extend_from_slice(b"\r\n");
+        }
+        let mut rest = line;
+        while let Some(at) = rest.find("\\x") {
+            bytes.extend_from_slice(&rest.as_bytes()[..at]);
+            let hex = rest.get(at + 2..at + 4).unwrap_or_default();
+            let byte = u8::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.chars().all(|c| c.is_ascii_hexdigit()))
+                .ok_or_else(|| format!("line {}: \\x needs two hex digits", row + 1))?;
+            bytes.push(byte);
+            rest = &rest[at + 4..];
+        }
+        bytes.extend_from_slice(rest.as_bytes());
+    }
+    Ok(bytes)
+}
+
+// A starting point for the editor built from the request as it's set up,
+// with Host and Content-Length filled in.
+pub fn template(method: &str, target: &Target, headers: &[(String, String)], body: Option<&str>) -> String {
+    // A literal `\x` has to be written as an escape itself.
+    let escape = |text: &str| text.replace("\\x", "\\x5cx");
+    let mut lines = vec![format!("{} {} HTTP/1.1", method, escape(&target.path))];
+    if !headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("host")) {
+        lines.push(format!("Host: {}", target.authority()));
+    }
+    for (key, value) in headers {
+        if !key.eq_ignore_ascii_case("content-length") {
+            lines.push(format!("{}: {}", escape(key), escape(value)));
+        }
+    }
+    let body = body.map(escape).unwrap_or_default();
+    if !body.is_empty() {
+        let length = encode(&body).map(|bytes| bytes.len()).unwrap_or(body.len());
+        lines.push(format!("Content-Length: {}", length));
+    }
+    lines.push(String::new());
+    lines.push(body);
+    lines.join("\n")
+}
+
+// Things about the request bytes a server is likely to trip over, which may
+// well be the point; they're only pointed out.
+pub fn warnings(request: &[u8]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let Some((head, body)) = split_head(request) else {
+        warnings.push("no blank line after the headers".to_string());
+        return warnings;
+    };
+    let head = String::from_utf8_lossy(head);
+    let lengths: Vec<&str> = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.trim())
+        .collect();
+    if let [length] = lengths.as_slice() {
+        if length.parse::<usize>().ok() != Some(body.len()) {
+            warnings.push(format!("Content-Length says {} but the body is {} bytes", length, body.len()));
+        }
+    } else if lengths.len() > 1 {
+        warnings.push(format!("{} Content-Length headers", lengths.len()));
+    }
+    warnings
+}
+
+// What was written and read on the connection.
+#[derive(Clone, Debug, Default)]
+pub struct Exchange {
+    pub sent: Vec<u8>,
+    pub received: Vec<u8>,
+    // How reading ended.
+    pub ended: String,
+    pub elapsed: Duration,
+}
+
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+// Writes `request` to a fresh connection as is and reads until the
+// response looks complete and the server goes quiet, the connection closes,
+// or the timeout. Proxies aren't used; resolve overrides and the TLS
+// settings are.
+pub async fn send(
+    target: &Target,
+    request: Vec<u8>,
+    settings: &RequestSettings,
+    identity: Option<&ClientIdentity>,
+) -> Result<Exchange, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let overrides = settings.resolve.clone().unwrap_or_default();
+    let connect = async {
+        match resolve::lookup(&overrides, &target.host) {
+            Some(entry) => TcpStream::connect((entry.address, target.port)).await,
+            None => TcpStream::connect((target.host.as_str(), target.port)).await,
+        }
+    };
+    let tcp = match settings.connect_timeout_ms {
+        Some(ms) => tokio::time::timeout(Duration::from_millis(ms), connect)
+            .await
+            .map_err(|_| format!("connecting to {} timed out", target.label()))??,
+        None => connect.await?,
+    };
+    tcp.set_nodelay(true)?;
+    let mut stream: Box<dyn Io> = if target.tls {
+        // Only HTTP/1.1 is spoken here, so that's all that's offered.
+        let connector = tokio_native_tls::TlsConnector::from(tls::native_tls_builder(settings, identity)?.request_alpns(&["http/1.1"]).build()?);
+        Box::new(connector.connect(&target.host, tcp).await?)
+    } else {
+        Box::new(tcp)
+    };
+
+    stream.write_all(&request).await?;
+    stream.flush().await?;
+    let head = request.starts_with(b"HEAD ");
+    let deadline = started + settings.timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_TIMEOUT);
+    let mut received = Vec::new();
+    let mut progress = Progress::default();
+    let mut buffer = vec![0; 16 * 1024];
+    let ended = loop {
+        let complete = progress.complete(&received, head);
+        let left = deadline.saturating_duration_since(Instant::now());
+        let wait = if complete { left.min(QUIET) } else { left };
+        match tokio::time::timeout(wait, stream.read(&mut buffer)).await {
+            Err(_) if complete => break "the response was complete and nothing more came".to_string(),
+            Err(_) => break "timed out waiting for the rest of the response".to_string(),
+            Ok(Ok(0)) => break "the server closed the connection".to_string(),
+            Ok(Ok(n)) if received.len() + n >= MAX_RECEIVED => {
+                received.extend_from_slice(&buffer[..n.min(MAX_RECEIVED - received.len())]);
+                break format!("stopped reading after {} bytes", MAX_RECEIVED);
+            }
+            Ok(Ok(n)) => received.extend_from_slice(&buffer[..n]),
+            Ok(Err(err)) => break format!("reading failed: {}", err),
+        }
+    };
+    Ok(Exchange {
+        sent: request,
+        received,
+        ended,
+        elapsed: started.elapsed(),
+    })
+}
+
+// How far the bytes read so far hold complete responses, so that after a
+// read only the response still coming in is looked at again.
+#[derive(Default)]
+struct Progress {
+    // Where the first response not known to be complete starts.
+    at: usize,
+    count: usize,
+}
+
+impl Progress {
+    // Whether the last response `parse` would find in `bytes` is complete.
+    // `bytes` only ever grows between calls.
+    fn complete(&mut self, bytes: &[u8], head: bool) -> bool {
+        loop {
+            let rest = &bytes[self.at..];
+            if self.count == MAX_MESSAGES || (self.count > 0 && !rest.starts_with(b"HTTP/")) {
+                return self.count > 0;
+            }
+            let (message, _, used) = scan(rest, head);
+            if !message.complete {
+                return false;
+            }
+            self.at += used;
+            self.count += 1;
+        }
+    }
+}
+
+// One response read leniently out of the received bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub status_line: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    // How the end of the body was found.
+    pub framing: String,
+    pub complete: bool,
+    pub problems: Vec<String>,
+}
+
+fn split_head(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let crlf = bytes.windows(4).position(|window| window == b"\r\n\r\n");
+    // A blank line of bare LFs counts when it comes first; the body after
+    // a CRLF one isn't searched.
+    let lf = bytes[..crlf.map_or(bytes.len(), |at| at + 1)]
+        .windows(2)
+        .position(|window| window == b"\n\n");
+    let (at, length) = match (crlf, lf) {
+        (_, Some(lf)) => (lf, 2),
+        (Some(crlf), None) => (crlf, 4),
+        (None, None) => return None,
+    };
+    Some((&bytes[..at], &bytes[at + length..]))
+}
+
+// Every response in `bytes`, interim ones and those to pipelined requests
+// included, with whatever doesn't follow the spec noted on each. `head` is
+// whether the request was a HEAD, whose responses have no body.
+pub fn parse(bytes: &[u8], head: bool) -> Vec<Message> {
+    let mut messages = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() && messages.len() < MAX_MESSAGES {
+        let (message, used) = parse_one(rest, head);
+        let stop = !message.complete || used == 0;
+        messages.push(message);
+        if stop {
+            break;
+        }
+        rest = &rest[used..];
+        // Anything but another response is left as it is.
+        if !rest.is_empty() && !rest.starts_with(b"HTTP/") {
+            if let Some(last) = messages.last_mut() {
+                last.problems.push(format!("{} more bytes after the response", rest.len()));
+            }
+            break;
+        }
+    }
+    messages
+}
+
+fn parse_one(bytes: &[u8], head: bool) -> (Message, usize) {
+    let (mut message, body, used) = scan(bytes, head);
+    for range in body {
+        message.body.extend_from_slice(&bytes[range]);
+    }
+    (message, used)
+}
+
+// Everything `parse_one` does but copying the body out: where in `bytes`
+// the body is, in pieces for a chunked one, comes back instead.
+fn scan(bytes: &[u8], head: bool) -> (Message, Vec<Range<usize>>, usize) {
+    let mut body_ranges = Vec::new();
+    let mut message = Message {
+        status_line: String::new(),
+        headers: Vec::new(),
+        body: Vec::new(),
+        framing: String::new(),
+        complete: false,
+        problems: Vec::new(),
+    };
+    let Some((head_bytes, body)) = split_head(bytes) else {
+        message.status_line = String::from_utf8_lossy(bytes.split(|byte| *byte == b'\n').next().unwrap_or_default()).trim_end().to_string();
+        message.problems.push("the headers haven't ended".to_string());
+        return (message, body_ranges, bytes.len());
+    };
+    let head_length = bytes.len() - body.len();
+    let text = String::from_utf8_lossy(head_bytes);
+    if text.replace("\r\n", "").contains('\n') {
+        message.problems.push("lines end in a bare LF".to_string());
+    }
+    let mut lines = text.lines().map(|line| line.trim_end_matches('\r'));
+    message.status_line = lines.next().unwrap_or_default().to_string();
+    let status = match message.status_line.split(' ').collect::<Vec<_>>().as_slice() {
+        [version, code, ..] if version.starts_with("HTTP/") => code.parse::<u16>().ok(),
+        _ => None,
+    };
+    let Some(status) = status else {
+        message.problems.push("not an HTTP status line".to_string());
+        return (message, body_ranges, bytes.len());
+    };
+    for line in lines {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            message.problems.push(format!("folded header line: {:?}", line));
+            if let Some((_, value)) = message.headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        match line.split_once(':') {
+            Some((key, value)) => {
+                if key != key.trim() {
+                    message.problems.push(format!("whitespace around the header name {:?}", key));
+                }
+                message.headers.push((key.trim().to_string(), value.trim().to_string()));
+            }
+            None => message.problems.push(format!("header line without a colon: {:?}", line)),
+        }
+    }
+
+    let values = |name: &str| -> Vec<String> {
+        message
+            .headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.to_lowercase())
+            .collect()
+    };
+    let encodings = values("transfer-encoding");
+    let lengths = values("content-length");
+    let chunked = encodings.last().is_some_and(|encoding| encoding.split(',').next_back().unwrap_or_default().trim() == "chunked");
+    if !encodings.is_empty() && !lengths.is_empty() {
+        message.problems.push("both Transfer-Encoding and Content-Length".to_string());
+    }
+
+    if head || (100..200).contains(&status) || status == 204 || status == 304 {
+        message.framing = "no body".to_string();
+        message.complete = true;
+        return (message, body_ranges, head_length);
+    }
+    if chunked {
+        message.framing = "chunked".to_string();
+        let used = decode_chunked(body, &mut message, &mut body_ranges);
+        let body_ranges = body_ranges.into_iter().map(|range| head_length + range.start..head_length + range.end).collect();
+        return (message, body_ranges, head_length + used);
+    }
+    if !lengths.is_empty() {
+        let mut distinct = lengths.clone();
+        distinct.sort();
+        distinct.dedup();
+        if distinct.len() > 1 {
+            message.problems.push(format!("conflicting Content-Length values: {}", lengths.join(", ")));
+        }
+        let Ok(length) = distinct[0].trim().parse::<usize>() else {
+            message.problems.push(format!("Content-Length {:?} is not a number", distinct[0]));
+            message.framing = "until the connection closed".to_string();
+            body_ranges.push(head_length..bytes.len());
+            return (message, body_ranges, bytes.len());
+        };
+        message.framing = format!("Content-Length {}", length);
+        message.complete = body.len() >= length;
+        if !message.complete {
+            message.problems.push(format!("the body is {} of {} bytes", body.len(), length));
+        }
+        let used = head_length + length.min(body.len());
+        body_ranges.push(head_length..used);
+        return (message, body_ranges, used);
+    }
+    message.framing = "until the connection closed".to_string();
+    body_ranges.push(head_length..bytes.len());
+    (message, body_ranges, bytes.len())
+}
+
+// Finds the chunks of a chunked body, adding where each one's data is in
+// `bytes` to `body` and any trailers to `message`, and returns how many
+// bytes the body took, trailers included.
+fn decode_chunked(bytes: &[u8], message: &mut Message, body: &mut Vec<Range<usize>>) -> usize {
+    let mut at = 0;
+    loop {
+        let Some(end) = bytes[at..].windows(2).position(|window| window == b"\r\n") else {
+            message.problems.push("the chunked body hasn't ended".to_string());
+            return bytes.len();
+        };
+        let line = String::from_utf8_lossy(&bytes[at..at + end]).to_string();
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let Ok(size) = usize::from_str_radix(size, 16) else {
+            message.problems.push(format!("bad chunk size line {:?}", line));
+            return bytes.len();
+        };
+        at += end + 2;
+        if size == 0 {
+            // Trailers, up to an empty line.
+            loop {
+                let Some(end) = bytes[at..].windows(2).position(|window| window == b"\r\n") else {
+                    message.problems.push("the chunked body hasn't ended".to_string());
+                    return bytes.len();
+                };
+                let trailer = String::from_utf8_lossy(&bytes[at..at + end]).to_string();
+                at += end + 2;
+                if trailer.is_empty() {
+                    message.complete = true;
+                    return at;
+                }
+                if let Some((key, value)) = trailer.split_once(':') {
+                    message.headers.push((key.trim().to_string(), value.trim().to_string()));
+                }
+            }
+        }
+        if size > bytes.len() - at {
+            body.push(at..bytes.len());
+            message.problems.push(format!("the last chunk is {} of {} bytes", bytes.len() - at, size));
+            return bytes.len();
+        }
+        body.push(at..at + size);
+        at += size;
+        if bytes.get(at..at + 2) != Some(b"\r\n") {
+            message.problems.push("a chunk isn't followed by CRLF".to_string());
+            if bytes.len() < at + 2 {
+                return bytes.len();
+            }
+        }
+        at += 2;
+    }
+}
+
+// The bytes as text with everything unprintable escaped; CR and LF show as
+// `\r` and `\n` with the line broken after each LF.
+pub fn escaped(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for byte in bytes {
+        match byte {
+            b'\r' => text.push_str("\\r"),
+            b'\n' => text.push_str("\\n\n"),
+            b'\t' => text.push_str("\\t"),
+            b'\\' => text.push_str("\\\\"),
+            0x20..=0x7e => text.push(*byte as char),
+            _ => text.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    text
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    hexdump::hex_dump(bytes, HEX_DUMP_LIMIT)
+}
+
+// The parsed responses as text, one after another.
+pub fn describe(messages: &[Message]) -> String {
+    let mut text = String::new();
+    for (i, message) in messages.iter().enumerate() {
+        if i > 0 {
+            text.push_str("\n\n");
+        }
+        text.push_str(&format!("{}\n", message.status_line));
+        for (key, value) in &message.headers {
+            text.push_str(&format!("  {}: {}\n", key, value));
+        }
+        text.push_str(&format!("Body: {} bytes, {}\n", message.body.len(), message.framing));
+        for problem in &message.problems {
+            text.push_str(&format!("! {}\n", problem));
+        }
+        if !message.body.is_empty() {
+            let content_type = message
+                .headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+                .map(|(_, value)| value.as_str());
+            text.push('\n');
+            if hexdump::is_binary(&message.body, content_type) {
+                text.push_str(&hex(&message.body));
+            } else {
+                text.push_str(&String::from_utf8_lossy(&message.body));
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_line_breaks_and_escapes() {
+        assert_eq!(encode("GET / HTTP/1.1\nHost: a\n\n").unwrap(), b"GET / HTTP/1.1\r\nHost: a\r\n\r\n");
+        assert_eq!(encode("a\\x0ab\\x00\\xFF\\x5cx").unwrap(), b"a\nb\x00\xff\\x");
+        assert!(encode("\\x4").is_err());
+        assert!(encode("ok\n\\xzz").unwrap_err().starts_with("line 2:"));
+        assert!(encode("\\x+1").is_err());
+    }
+
+    #[test]
+    fn parses_pipelined_responses() {
+        let bytes = b"HTTP/1.1 100 Continue\r\n\r\n\
+                      HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello\
+                      HTTP/1.1 204 No Content\r\n\r\n";
+        let messages = parse(bytes, false);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].framing, "no body");
+        assert_eq!(messages[1].status_line, "HTTP/1.1 200 OK");
+        assert_eq!(messages[1].headers, vec![("Content-Length".to_string(), "5".to_string())]);
+        assert_eq!(messages[1].body, b"hello");
+        assert_eq!(messages[1].framing, "Content-Length 5");
+        assert!(messages.iter().all(|message| message.complete && message.problems.is_empty()));
+
+        let messages = parse(b"HTTP/1.1 200 OK\nContent-Length: 2\n\nhi\r\njunk", false);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].body, b"hi");
+        assert_eq!(messages[0].problems, vec!["lines end in a bare LF", "6 more bytes after the response"]);
+    }
+
+    #[test]
+    fn notes_truncated_responses() {
+        let (message, used) = parse_one(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello", false);
+        assert!(!message.complete);
+        assert_eq!(message.body, b"hello");
+        assert_eq!(message.problems, vec!["the body is 5 of 10 bytes"]);
+        assert_eq!(used, 44);
+
+        let (message, used) = parse_one(b"HTTP/1.1 200 OK\r\nContent-Le", false);
+        assert!(!message.complete);
+        assert_eq!(message.status_line, "HTTP/1.1 200 OK");
+        assert_eq!(message.problems, vec!["the headers haven't ended"]);
+        assert_eq!(used, 27);
+
+        let (message, _) = parse_one(b"HTTP/1.0 200 OK\r\n\r\nuntil close", false);
+        assert!(!message.complete);
+        assert_eq!(message.framing, "until the connection closed");
+        assert_eq!(message.body, b"until close");
+
+        assert_eq!(parse(b"", false), Vec::new());
+        assert_eq!(parse(b"SSH-2.0-OpenSSH\r\n\r\n", false)[0].problems, vec!["not an HTTP status line"]);
+    }
+
+    #[test]
+    fn decodes_chunked_bodies_with_trailers() {
+        let bytes = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n\
+                      5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nChecksum: abc\r\n\r\n\
+                      HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+        let messages = parse(bytes, false);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].framing, "chunked");
+        assert_eq!(messages[0].body, b"hello, world");
+        assert_eq!(messages[0].headers.last(), Some(&("Checksum".to_string(), "abc".to_string())));
+        assert!(messages[0].complete && messages[0].problems.is_empty());
+        assert!(messages[1].complete);
+    }
+
+    #[test]
+    fn stops_at_truncated_chunks() {
+        let mut message = parse_one(b"HTTP/1.1 200 OK\r\n\r\n", false).0;
+        let mut body = Vec::new();
+        assert_eq!(decode_chunked(b"5\r\nhello\r\na\r\nwor", &mut message, &mut body), 16);
+        assert_eq!(body, vec![3..8, 13..16]);
+        assert_eq!(message.problems, vec!["the last chunk is 3 of 10 bytes"]);
+        assert!(!message.complete);
+
+        // A size near usize::MAX must not overflow.
+        let mut body = Vec::new();
+        assert_eq!(decode_chunked(b"ffffffffffffffff\r\nab", &mut message, &mut body), 20);
+        assert_eq!(body, vec![18..20]);
+
+        let mut body = Vec::new();
+        decode_chunked(b"2\r\nabXY1\r\nc\r\n0\r\n\r\n", &mut message, &mut body);
+        assert_eq!(message.problems.last().unwrap(), "a chunk isn't followed by CRLF");
+        decode_chunked(b"zz\r\n", &mut message, &mut body);
+        assert_eq!(message.problems.last().unwrap(), "bad chunk size line \"zz\"");
+        decode_chunked(b"0\r\nTrailer: x\r\n", &mut message, &mut body);
+        assert_eq!(message.problems.last().unwrap(), "the chunked body hasn't ended");
+    }
+
+    #[test]
+    fn head_responses_have_no_body() {
+        let bytes = b"HTTP/1.1 200 OK\r\nContent-Length: 1000\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+        let messages = parse(bytes, true);
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().all(|message| message.complete && message.body.is_empty() && message.framing == "no body"));
+
+        let messages = parse(bytes, false);
+        assert!(!messages[0].complete);
+        assert_eq!(messages[0].body.len(), 47);
+    }
+
+    #[test]
+    fn progress_agrees_with_parse() {
+        let bytes: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n\
+                             HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n\
+                             HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi\
+                             HTTP/1.1 200 OK\r\n\r\nto the end";
+        let mut progress = Progress::default();
+        for end in 0..=bytes.len() {
+            let expected = parse(&bytes[..end], false).last().is_some_and(|message| message.complete);
+            assert_eq!(progress.complete(&bytes[..end], false), expected, "after {} bytes", end);
+        }
+        assert_eq!(progress.count, 3);
+    }
+}
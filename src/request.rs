@@ -1,5 +1,543 @@
-pub async fn send_get_request(url: &str) -> Result<String, reqwest::Error> {
-    let response = reqwest::get(url).await?;
-    let body = response.text().await?;
-    Ok(body)
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+
+use reqwest::Url;
+use reqwest_cookie_store::CookieStoreMutex;
+use tempfile::TempPath;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::hexdump;
+use crate::proxy;
+use crate::resolve;
+use crate::settings::{HttpVersion, RequestSettings};
+use crate::signing::SigningDebug;
+use crate::stream::{self, BodySink, Progress};
+use crate::timing::{self, Timings};
+use crate::tls::{self, ClientCert};
+use crate::unix;
+use crate::wire;
+
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+// Past this the dump is too long to scroll through; saving the response is
+// the better way to look at the rest.
+const HEX_DUMP_LIMIT: usize = 256 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+// One redirect response that was followed on the way to the final response.
+#[derive(Clone, Debug)]
+pub struct Hop {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub location: String,
+    pub headers: Vec<(String, String)>,
+    pub elapsed: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub method: String,
+    pub status: u16,
+    pub url: String,
+    pub route: String,
+    pub resolved: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub size: u64,
+    pub spooled: Option<Arc<TempPath>>,
+    pub elapsed: Duration,
+    pub timings: Timings,
+    pub redirects: Vec<Hop>,
+    pub signing: Option<SigningDebug>,
+    pub wire: String,
+}
+
+impl Response {
+    // A signature is usually rejected with one of these, so that's when the
+    // canonical request is worth showing.
+    pub fn signature_rejected(&self) -> bool {
+        self.signing.is_some() && (self.status == 401 || self.status == 403)
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_binary(&self) -> bool {
+        hexdump::is_binary(&self.body, self.content_type())
+    }
+
+    pub fn render(&self) -> String {
+        let mut message = String::from_utf8_lossy(&self.body).to_string();
+        if let Some(path) = &self.spooled {
+            message.push_str(&format!(
+                "\n\n--- Showing the first {} of {} bytes, the whole body is in {} ---\n",
+                self.body.len(),
+                self.size,
+                path.display()
+            ));
+        }
+        if let (true, Some(debug)) = (self.signature_rejected(), &self.signing) {
+            message.push_str(&format!(
+                "\n\n--- Signature rejected ({}) ---\n\nCanonical request:\n{}\n\nString to sign:\n{}\n",
+                self.status, debug.canonical_request, debug.string_to_sign
+            ));
+        }
+        message
+    }
+
+    pub fn render_hex(&self) -> String {
+        hexdump::hex_dump(&self.body, HEX_DUMP_LIMIT)
+    }
+
+    // Wrapped at 76 columns like MIME does, so it fits the panel.
+    pub fn render_base64(&self) -> String {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&self.body);
+        encoded
+            .as_bytes()
+            .chunks(76)
+            .map(|line| String::from_utf8_lossy(line).to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn render_info(&self) -> String {
+        format!(
+            "Request:   {} {}\nStatus:    {}\nTime:      {} ms\nRedirects: {}\nRoute:     {}\nResolve:   {}\n",
+            self.method,
+            self.url,
+            self.status,
+            self.elapsed.as_millis(),
+            self.redirects.len(),
+            self.route,
+            self.resolved.as_deref().unwrap_or("DNS")
+        ) + "\nTiming:\n" + &self.timings.waterfall()
+    }
+
+    pub fn render_redirects(&self) -> String {
+        if self.redirects.is_empty() {
+            return format!("No redirects\n\n{} {}  ({} ms)\n", self.status, self.url, self.elapsed.as_millis());
+        }
+
+        let mut lines = String::new();
+        for (i, hop) in self.redirects.iter().enumerate() {
+            lines.push_str(&format!(
+                "{}. {} {} {}  ({} ms)\n   -> {}\n",
+                i + 1,
+                hop.status,
+                hop.method,
+                hop.url,
+                hop.elapsed.as_millis(),
+                hop.location
+            ));
+            for (key, value) in &hop.headers {
+                lines.push_str(&format!("   {}: {}\n", key, value));
+            }
+            lines.push('\n');
+        }
+        lines.push_str(&format!(
+            "{}. {} {}  ({} ms)\n",
+            self.redirects.len() + 1,
+            self.status,
+            self.url,
+            self.elapsed.as_millis()
+        ));
+        for (key, value) in &self.headers {
+            lines.push_str(&format!("   {}: {}\n", key, value));
+        }
+        lines
+    }
+}
+
+// The client certificate depends on the host, so a new client is built for
+// every host a request (or its redirects) talks to.
+pub fn build_client(
+    settings: &RequestSettings,
+    cookies: Arc<CookieStoreMutex>,
+    host: &str,
+    passphrases: &HashMap<String, String>,
+) -> Result<reqwest::Client, Box<dyn std::error::Error>> {
+    Ok(client_builder(settings, cookies, host, passphrases)?.build()?)
+}
+
+fn client_builder(
+    settings: &RequestSettings,
+    cookies: Arc<CookieStoreMutex>,
+    host: &str,
+    passphrases: &HashMap<String, String>,
+) -> Result<reqwest::ClientBuilder, Box<dyn std::error::Error>> {
+    // Redirects are followed by `send_request` itself so every hop can be
+    // recorded.
+    let mut builder = reqwest::Client::builder()
+        .cookie_provider(cookies)
+        .redirect(reqwest::redirect::Policy::none());
+
+    if let Some(ms) = settings.connect_timeout_ms {
+        builder = builder.connect_timeout(Duration::from_millis(ms));
+    }
+    if let Some(ms) = settings.timeout_ms {
+        builder = builder.timeout(Duration::from_millis(ms));
+    }
+    if settings.verify_tls == Some(false) {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    match settings.http_version {
+        Some(HttpVersion::Http1Only) => builder = builder.http1_only(),
+        Some(HttpVersion::Http2PriorKnowledge) => builder = builder.http2_prior_knowledge(),
+        Some(HttpVersion::Auto) | None => {}
+    }
+    if settings.decompress == Some(false) {
+        builder = builder.no_gzip().no_brotli().no_deflate();
+    }
+    builder = proxy::apply(builder, settings)?;
+    if let Some(overrides) = &settings.resolve {
+        builder = resolve::apply(builder, overrides);
+    }
+    if let Some(path) = &settings.ca_bundle {
+        for certificate in tls::load_ca_bundle(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let Some(client_cert) = ClientCert::for_host(settings, host) {
+        builder = builder.identity(client_cert.load(passphrases)?.reqwest);
+    }
+
+    Ok(builder)
+}
+
+pub fn host_of(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_default()
+}
+
+fn scheme_of(url: &str) -> String {
+    Url::parse(url).map(|url| url.scheme().to_string()).unwrap_or_default()
+}
+
+fn header_pairs(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(key, value)| (key.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+        .collect()
+}
+
+// reqwest keeps the interesting part (e.g. the certificate error) in the
+// source chain.
+pub fn describe_error(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+// What came back for a request: a response read to the end, or a
+// successful text/event-stream response handed over still open, to be read
+// as the events arrive.
+pub enum Reply {
+    Complete(Box<Response>),
+    EventStream(reqwest::Response),
+}
+
+pub async fn send_request(
+    request: Request,
+    settings: &RequestSettings,
+    cookies: Arc<CookieStoreMutex>,
+    passphrases: &HashMap<String, String>,
+    progress: Option<&UnboundedSender<Progress>>,
+) -> Result<Response, Box<dyn std::error::Error>> {
+    match send(request, settings, cookies, passphrases, progress, false).await? {
+        Reply::Complete(response) => Ok(*response),
+        Reply::EventStream(_) => Err("the response is an event stream".into()),
+    }
+}
+
+// Like `send_request`, but an event stream isn't read to the end.
+pub async fn send_or_stream(
+    request: Request,
+    settings: &RequestSettings,
+    cookies: Arc<CookieStoreMutex>,
+    passphrases: &HashMap<String, String>,
+    progress: Option<&UnboundedSender<Progress>>,
+) -> Result<Reply, Box<dyn std::error::Error>> {
+    send(request, settings, cookies, passphrases, progress, true).await
+}
+
+// Waits for `future` until the deadline of the timeout setting. It's kept
+// here rather than by reqwest so an event stream can stay open past it.
+async fn before<T>(
+    deadline: Option<Instant>,
+    future: impl std::future::Future<Output = Result<T, reqwest::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    match deadline {
+        Some(deadline) => Ok(tokio::time::timeout_at(deadline.into(), future)
+            .await
+            .map_err(|_| "the request timed out (timeout setting)")??),
+        None => Ok(future.await?),
+    }
+}
+
+async fn send(
+    mut request: Request,
+    settings: &RequestSettings,
+    cookies: Arc<CookieStoreMutex>,
+    passphrases: &HashMap<String, String>,
+    progress: Option<&UnboundedSender<Progress>>,
+    streams: bool,
+) -> Result<Reply, Box<dyn std::error::Error>> {
+    if let Some(target) = unix::target(&request.url, settings) {
+        let signing = settings
+            .signing
+            .as_ref()
+            .map(|signing| signing.sign(&mut request, chrono::Utc::now()));
+        let mut response = unix::send(request, settings, target, progress).await?;
+        response.signing = signing;
+        return Ok(Reply::Complete(Box::new(response)));
+    }
+
+    let deadline = settings.timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+    let client_settings = RequestSettings {
+        timeout_ms: None,
+        ..settings.clone()
+    };
+    let mut host = host_of(&request.url);
+    let mut scheme = scheme_of(&request.url);
+    let mut recorder = timing::Recorder::default();
+    let mut client = recorder
+        .apply(client_builder(&client_settings, cookies.clone(), &host, passphrases)?, &client_settings, &request.url)?
+        .build()?;
+    let follow = settings.follow_redirects != Some(false);
+    let max_redirects = settings.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS);
+    let mut redirects = Vec::new();
+    let mut wire_log = String::new();
+    // The credentials are for the host the request was made to; once a
+    // redirect leaves it, nothing is signed any more.
+    let mut signer = settings.signing.clone();
+
+    loop {
+        // Signing has to see the request exactly as it will go over the wire,
+        // so every hop is signed again.
+        let signing = signer.as_ref().map(|signing| signing.sign(&mut request, chrono::Utc::now()));
+
+        let method = reqwest::Method::from_bytes(request.method.as_bytes()).unwrap_or(reqwest::Method::GET);
+        let mut builder = client.request(method, &request.url);
+        for (key, value) in &request.headers {
+            builder = builder.header(key, value);
+        }
+        if let Some(body) = request.body.clone() {
+            builder = builder.body(body);
+        }
+        let mut outgoing = builder.build()?;
+        let outgoing_url = outgoing.url().clone();
+        wire::add_client_headers(outgoing.headers_mut(), &outgoing_url, settings, &cookies);
+        let sent_headers = header_pairs(outgoing.headers());
+
+        let mut timings = Timings::default();
+        let started = Instant::now();
+        let mut response = match before(deadline, client.execute(outgoing)).await {
+            Ok(response) => response,
+            Err(err) => return Err(recorder.failure().map(Into::into).unwrap_or(err)),
+        };
+        timings.ttfb = started.elapsed();
+        recorder.take(&request.url, &mut timings);
+        let status = response.status();
+        let headers = header_pairs(response.headers());
+        wire_log.push_str(&wire::request(
+            &request.method,
+            &outgoing_url,
+            // hyper always speaks HTTP/1.1 unless HTTP/2 was negotiated, even
+            // when an older server answers with HTTP/1.0.
+            match response.version() {
+                reqwest::Version::HTTP_2 => reqwest::Version::HTTP_2,
+                _ => reqwest::Version::HTTP_11,
+            },
+            &sent_headers,
+            request.body.as_deref().map(str::as_bytes),
+        ));
+        wire_log.push_str(&wire::response(response.version(), status, &headers));
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string());
+
+        if let (true, true, Some(location)) = (follow, status.is_redirection(), location) {
+            if redirects.len() >= max_redirects {
+                return Err(format!("too many redirects (max {})", max_redirects).into());
+            }
+            let next = Url::parse(&request.url)?.join(&location)?;
+            redirects.push(Hop {
+                method: request.method.clone(),
+                url: request.url.clone(),
+                status: status.as_u16(),
+                location,
+                headers,
+                elapsed: started.elapsed(),
+            });
+
+            // Same rules browsers follow: 303 always becomes a GET, and so do
+            // 301/302 after a POST. 307/308 replay the request as is.
+            if status.as_u16() == 303 || (matches!(status.as_u16(), 301 | 302) && request.method == "POST") {
+                request.method = "GET".to_string();
+                request.body = None;
+                request.headers.retain(|(key, _)| {
+                    !key.eq_ignore_ascii_case("content-type") && !key.eq_ignore_ascii_case("content-length")
+                });
+            }
+            request.url = next.to_string();
+            wire_log.push_str(&format!("\n* Following redirect to {}\n\n", request.url));
+            if host_of(&request.url) != host {
+                request.headers.retain(|(key, _)| !key.eq_ignore_ascii_case("authorization"));
+                if let Some(signing) = signer.take() {
+                    signing.unsign(&mut request);
+                }
+            }
+            // The client depends on the host for the certificate and on the
+            // scheme for how the connection is timed.
+            if host_of(&request.url) != host || scheme_of(&request.url) != scheme {
+                host = host_of(&request.url);
+                scheme = scheme_of(&request.url);
+                client = recorder
+                    .apply(client_builder(&client_settings, cookies.clone(), &host, passphrases)?, &client_settings, &request.url)?
+                    .build()?;
+            }
+            continue;
+        }
+
+        let url = response.url().to_string();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let event_stream = content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.to_lowercase().starts_with("text/event-stream"));
+        if streams && event_stream && status.is_success() {
+            return Ok(Reply::EventStream(response));
+        }
+        let mut sink = BodySink::new(
+            settings.memory_cap_kb.unwrap_or(stream::DEFAULT_MEMORY_CAP_KB),
+            content_type.as_deref(),
+        );
+        loop {
+            let Some(chunk) = before(deadline, response.chunk()).await? else {
+                break;
+            };
+            sink.push(&chunk).await?;
+            sink.report(progress);
+        }
+        let size = sink.received();
+        let (body, spooled) = sink.finish().await?;
+        timings.download = started.elapsed() - timings.ttfb;
+
+        return Ok(Reply::Complete(Box::new(Response {
+            method: request.method,
+            status: status.as_u16(),
+            route: proxy::route(settings, &url).describe(),
+            resolved: settings
+                .resolve
+                .as_deref()
+                .and_then(|overrides| resolve::lookup(overrides, &host_of(&url)))
+                .map(|entry| format!("{} -> {} (override)", entry.host, entry.address)),
+            url,
+            headers,
+            body,
+            size,
+            spooled,
+            elapsed: started.elapsed(),
+            timings,
+            redirects,
+            signing,
+            wire: wire_log,
+        })));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::Signing;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Answers every request with `reply`, and hands over the head of each
+    // request it got.
+    async fn server(reply: String) -> (u16, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut head = Vec::new();
+                let mut buffer = [0; 4096];
+                while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => head.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                let _ = sender.send(String::from_utf8_lossy(&head).to_lowercase());
+                let _ = stream.write_all(reply.as_bytes()).await;
+            }
+        });
+        (port, receiver)
+    }
+
+    #[tokio::test]
+    async fn stops_signing_when_a_redirect_leaves_the_host() {
+        let (other, mut other_heads) = server("HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()).await;
+        let (first, mut first_heads) = server(format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://localhost:{}/next\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            other
+        ))
+        .await;
+
+        let mut signing = Signing::from_provider("aws-sigv4").unwrap();
+        signing.set("access_key", "AKIDEXAMPLE".to_string());
+        signing.set("secret_key", "secret".to_string());
+        signing.set("session_token", "token".to_string());
+        let settings = RequestSettings {
+            signing: Some(signing),
+            proxy: Some("none".to_string()),
+            ..RequestSettings::default()
+        };
+        let request = Request {
+            method: "GET".to_string(),
+            url: format!("http://127.0.0.1:{}/", first),
+            headers: vec![("Accept".to_string(), "*/*".to_string())],
+            body: None,
+        };
+        let response = send_request(request, &settings, Arc::new(CookieStoreMutex::default()), &HashMap::new(), None)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert!(response.signing.is_none());
+
+        let signed = first_heads.recv().await.unwrap();
+        assert!(signed.contains("authorization: aws4-hmac-sha256"));
+        assert!(signed.contains("x-amz-security-token: token"));
+        let followed = other_heads.recv().await.unwrap();
+        assert!(followed.starts_with("get /next "));
+        assert!(followed.contains("accept: */*"));
+        for header in ["authorization", "x-amz-date", "x-amz-security-token"] {
+            assert!(!followed.contains(header), "{} went to the other host:\n{}", header, followed);
+        }
+    }
 }
@@ -0,0 +1,58 @@
+use std::net::{IpAddr, SocketAddr};
+
+use reqwest::ClientBuilder;
+use serde::{Deserialize, Serialize};
+
+// Sends requests for `host` to `address` instead of what DNS says. The URL,
+// Host header and TLS SNI keep using `host`, like curl's --resolve.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ResolveOverride {
+    pub host: String,
+    pub address: IpAddr,
+}
+
+pub fn apply(mut builder: ClientBuilder, overrides: &[ResolveOverride]) -> ClientBuilder {
+    for entry in overrides {
+        // reqwest ignores this port and uses the one from the URL.
+        builder = builder.resolve(&entry.host, SocketAddr::new(entry.address, 0));
+    }
+    builder
+}
+
+pub fn lookup<'a>(overrides: &'a [ResolveOverride], host: &str) -> Option<&'a ResolveOverride> {
+    overrides.iter().find(|entry| entry.host.eq_ignore_ascii_case(host))
+}
+
+// `host=address, host2=address2` as shown and edited in the settings view.
+pub fn format_overrides(overrides: &[ResolveOverride]) -> String {
+    overrides
+        .iter()
+        .map(|entry| format!("{}={}", entry.host, entry.address))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn parse_overrides(value: &str) -> Result<Option<Vec<ResolveOverride>>, String> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    value
+        .split(',')
+        .map(|entry| {
+            let (host, address) = entry
+                .split_once('=')
+                .ok_or_else(|| "expected host=address, ...".to_string())?;
+            let address = address
+                .trim()
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse()
+                .map_err(|_| format!("{} is not an IP address", address.trim()))?;
+            Ok(ResolveOverride {
+                host: host.trim().to_string(),
+                address,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(Some)
+}
@@ -1,6 +1,11 @@
 use dirs::config_dir;
 use serde::{Deserialize, Serialize};
-use std::{fs, io::Write};
+use std::{collections::BTreeMap, fs, io::Write, path::PathBuf};
+
+use crate::diff::Snapshot;
+use crate::settings::RequestSettings;
+use crate::timing::Timings;
+use crate::websocket::Template;
 
 #[derive(Serialize, Deserialize, Debug)]
 struct History {
@@ -9,35 +14,171 @@ struct History {
     //  Danik is working on it
     //header: String,
     url: String,
+    // With the credentials masked; see `RequestSettings::redacted`.
+    #[serde(default)]
+    settings: RequestSettings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timings: Option<Timings>,
+    // File in the snapshot directory holding the response, only kept for the
+    // newest entries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snapshot: Option<String>,
+    // Older sessions kept the response inline; it's moved to a file on load.
+    #[serde(default, skip_serializing)]
+    response: Option<Snapshot>,
 }
 
+// How many history entries keep their response for comparing later.
+const SNAPSHOTS_KEPT: usize = 50;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Session {
     history: Vec<History>,
+    #[serde(default)]
+    pub defaults: RequestSettings,
+    // The settings of the request being edited.
+    #[serde(default)]
+    pub request: RequestSettings,
+    #[serde(default = "default_environment")]
+    pub environment: String,
+    #[serde(default)]
+    pub environments: BTreeMap<String, RequestSettings>,
+    #[serde(default)]
+    pub diff_ignore: Vec<String>,
+    #[serde(default)]
+    pub ws_templates: Vec<Template>,
+    #[serde(default)]
+    pub grpc_protos: Vec<String>,
+    // Where the SOAP view loads its WSDL from.
+    #[serde(default)]
+    pub wsdl: String,
+    // Responses saved under a name to compare against, by name, with the
+    // file in the snapshot directory holding each.
+    #[serde(default)]
+    examples: BTreeMap<String, String>,
+}
+
+fn default_environment() -> String {
+    "default".to_string()
 }
 
 impl Session {
     pub fn new() -> Self {
         let mut session = Self {
             history: Vec::new(),
+            defaults: RequestSettings::default(),
+            request: RequestSettings::default(),
+            environment: default_environment(),
+            environments: BTreeMap::new(),
+            diff_ignore: Vec::new(),
+            ws_templates: Vec::new(),
+            grpc_protos: Vec::new(),
+            wsdl: String::new(),
+            examples: BTreeMap::new(),
         };
         session.load().unwrap_or_default();
         session
     }
 
-    pub fn push_history(&mut self, request: &str, /*header: String,*/ url: String) {
+    pub fn push_history(&mut self, request: &str, /*header: String,*/ url: String, settings: RequestSettings, timings: Option<Timings>, response: Option<Snapshot>) {
         let history = History {
             date: chrono::offset::Local::now().to_string(),
             action: request.to_string(),
             //header: header,
             url: url,
+            settings: settings.redacted(),
+            timings,
+            // Comparing later is a nice-to-have, the entry goes in either way.
+            snapshot: response.and_then(|response| write_snapshot(&response).ok()),
+            response: None,
         };
 
         self.history.push(history);
+        self.prune_snapshots();
+
+        self.save().unwrap();
+    }
+
+    // Drops the snapshots of all but the newest entries.
+    fn prune_snapshots(&mut self) {
+        for h in self.history.iter_mut().rev().filter(|h| h.snapshot.is_some()).skip(SNAPSHOTS_KEPT) {
+            if let Some(file) = h.snapshot.take() {
+                let _ = fs::remove_file(snapshot_dir().join(file));
+            }
+        }
+    }
+
+    // Saving under a name that's already taken replaces that example.
+    pub fn save_example(&mut self, name: String, response: &Snapshot) -> Result<(), Box<dyn std::error::Error>> {
+        let file = write_snapshot(response)?;
+        if let Some(previous) = self.examples.insert(name, file) {
+            let _ = fs::remove_file(snapshot_dir().join(previous));
+        }
+        self.save()
+    }
+
+    pub fn set_defaults(&mut self, defaults: RequestSettings) {
+        self.defaults = defaults;
+        self.save().unwrap();
+    }
+
+    pub fn set_request_settings(&mut self, request: RequestSettings) {
+        self.request = request;
+        self.save().unwrap();
+    }
+
+    pub fn set_environment(&mut self, environment: String) {
+        self.environment = environment;
+        self.save().unwrap();
+    }
+
+    pub fn environment_settings(&self) -> RequestSettings {
+        self.settings_for(&self.environment)
+    }
 
+    pub fn settings_for(&self, environment: &str) -> RequestSettings {
+        self.environments.get(environment).cloned().unwrap_or_default()
+    }
+
+    pub fn set_environment_settings(&mut self, settings: RequestSettings) {
+        self.environments.insert(self.environment.clone(), settings);
+        self.save().unwrap();
+    }
+
+    pub fn set_diff_ignore(&mut self, ignore: Vec<String>) {
+        self.diff_ignore = ignore;
+        self.save().unwrap();
+    }
+
+    pub fn set_ws_templates(&mut self, templates: Vec<Template>) {
+        self.ws_templates = templates;
+        self.save().unwrap();
+    }
+
+    pub fn set_grpc_protos(&mut self, protos: Vec<String>) {
+        self.grpc_protos = protos;
+        self.save().unwrap();
+    }
+
+    pub fn set_wsdl(&mut self, wsdl: String) {
+        self.wsdl = wsdl;
         self.save().unwrap();
     }
 
+    // Responses kept in history, newest first, then the saved examples, each
+    // with a label to pick them by.
+    pub fn snapshots(&self) -> Vec<(String, Snapshot)> {
+        let history = self.history.iter().rev().filter_map(|h| {
+            let snapshot = read_snapshot(h.snapshot.as_ref()?)?;
+            Some((format!("{} {} {}", h.date, h.action, h.url), snapshot))
+        });
+        let examples = self
+            .examples
+            .iter()
+            .filter_map(|(name, file)| Some((format!("example {}", name), read_snapshot(file)?)));
+        history.chain(examples).collect()
+    }
+
     fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let config_path = config_dir().unwrap().join("postsmith");
         if !config_path.exists() {
@@ -60,6 +201,33 @@ impl Session {
             let data = fs::read_to_string(file_path)?;
             let session: Session = serde_json::from_str(&data)?;
             self.history = session.history;
+            self.defaults = session.defaults;
+            self.request = session.request;
+            self.environment = session.environment;
+            self.environments = session.environments;
+            self.diff_ignore = session.diff_ignore;
+            self.ws_templates = session.ws_templates;
+            self.grpc_protos = session.grpc_protos;
+            self.wsdl = session.wsdl;
+            self.examples = session.examples;
+
+            let mut changed = false;
+            for h in self.history.iter_mut() {
+                if let Some(response) = h.response.take() {
+                    h.snapshot = write_snapshot(&response).ok();
+                    changed = true;
+                }
+                // Older sessions wrote credentials into the history as is.
+                let redacted = h.settings.redacted();
+                if redacted != h.settings {
+                    h.settings = redacted;
+                    changed = true;
+                }
+            }
+            if changed {
+                self.prune_snapshots();
+                self.save()?;
+            }
         }
         Ok(())
     }
@@ -67,7 +235,29 @@ impl Session {
     pub fn get_history(&self) -> String {
         self.history
             .iter()
-            .map(|h| format!("{} - {} - {}\n", h.date, h.action, h.url))
+            .map(|h| match &h.timings {
+                Some(timings) => format!("{} - {} - {} - {}\n", h.date, h.action, h.url, timings.summary()),
+                None => format!("{} - {} - {}\n", h.date, h.action, h.url),
+            })
             .collect()
     }
 }
+
+fn snapshot_dir() -> PathBuf {
+    config_dir().unwrap().join("postsmith/snapshots")
+}
+
+// Snapshots live in files of their own so the session file stays small
+// enough to rewrite on every change.
+fn write_snapshot(snapshot: &Snapshot) -> Result<String, Box<dyn std::error::Error>> {
+    let dir = snapshot_dir();
+    fs::create_dir_all(&dir)?;
+    let file = format!("{}.json", chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default());
+    fs::write(dir.join(&file), serde_json::to_vec(snapshot)?)?;
+    Ok(file)
+}
+
+fn read_snapshot(file: &str) -> Option<Snapshot> {
+    let data = fs::read(snapshot_dir().join(file)).ok()?;
+    serde_json::from_slice(&data).ok()
+}
@@ -0,0 +1,600 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::request::Request;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Signing {
+    AwsSigV4 {
+        access_key: String,
+        secret_key: String,
+        session_token: String,
+        region: String,
+        service: String,
+    },
+    Hmac {
+        key_id: String,
+        secret: String,
+        header: String,
+        signed_headers: Vec<String>,
+    },
+}
+
+// What went into the signature, kept around so a rejected request can be
+// compared against what the server computed.
+#[derive(Clone, Debug)]
+pub struct SigningDebug {
+    pub canonical_request: String,
+    pub string_to_sign: String,
+}
+
+impl Signing {
+    pub fn all_providers() -> Vec<&'static str> {
+        vec!["none", "aws-sigv4", "hmac"]
+    }
+
+    pub fn from_provider(name: &str) -> Option<Signing> {
+        match name {
+            "aws-sigv4" => Some(Signing::AwsSigV4 {
+                access_key: String::new(),
+                secret_key: String::new(),
+                session_token: String::new(),
+                region: "us-east-1".to_string(),
+                service: "execute-api".to_string(),
+            }),
+            "hmac" => Some(Signing::Hmac {
+                key_id: String::new(),
+                secret: String::new(),
+                header: "Authorization".to_string(),
+                signed_headers: vec!["host".to_string(), "date".to_string()],
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn provider(&self) -> &'static str {
+        match self {
+            Signing::AwsSigV4 { .. } => "aws-sigv4",
+            Signing::Hmac { .. } => "hmac",
+        }
+    }
+
+    pub fn fields(&self) -> Vec<&'static str> {
+        match self {
+            Signing::AwsSigV4 { .. } => vec!["access_key", "secret_key", "session_token", "region", "service"],
+            Signing::Hmac { .. } => vec!["key_id", "secret", "header", "signed_headers"],
+        }
+    }
+
+    pub fn get(&self, field: &str) -> String {
+        match self {
+            Signing::AwsSigV4 { access_key, secret_key, session_token, region, service } => match field {
+                "access_key" => access_key.clone(),
+                "secret_key" => secret_key.clone(),
+                "session_token" => session_token.clone(),
+                "region" => region.clone(),
+                "service" => service.clone(),
+                _ => String::new(),
+            },
+            Signing::Hmac { key_id, secret, header, signed_headers } => match field {
+                "key_id" => key_id.clone(),
+                "secret" => secret.clone(),
+                "header" => header.clone(),
+                "signed_headers" => signed_headers.join(","),
+                _ => String::new(),
+            },
+        }
+    }
+
+    // The fields that are credentials rather than configuration.
+    pub fn secrets(&self) -> Vec<&'static str> {
+        match self {
+            Signing::AwsSigV4 { .. } => vec!["secret_key", "session_token"],
+            Signing::Hmac { .. } => vec!["secret"],
+        }
+    }
+
+    pub fn set(&mut self, field: &str, value: String) {
+        match self {
+            Signing::AwsSigV4 { access_key, secret_key, session_token, region, service } => match field {
+                "access_key" => *access_key = value,
+                "secret_key" => *secret_key = value,
+                "session_token" => *session_token = value,
+                "region" => *region = value,
+                "service" => *service = value,
+                _ => {}
+            },
+            Signing::Hmac { key_id, secret, header, signed_headers } => match field {
+                "key_id" => *key_id = value,
+                "secret" => *secret = value,
+                "header" => *header = value,
+                "signed_headers" => {
+                    *signed_headers = value
+                        .split(',')
+                        .map(|h| h.trim().to_lowercase())
+                        .filter(|h| !h.is_empty())
+                        .collect()
+                }
+                _ => {}
+            },
+        }
+    }
+
+    // Adds the signature headers to the request. Has to run last, once the
+    // url, headers and body are final.
+    pub fn sign(&self, request: &mut Request, now: DateTime<Utc>) -> SigningDebug {
+        match self {
+            Signing::AwsSigV4 { access_key, secret_key, session_token, region, service } => {
+                let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+                let date = now.format("%Y%m%d").to_string();
+                let payload_hash = sha256_hex(request.body.as_deref().unwrap_or("").as_bytes());
+
+                // A signature left over from an earlier hop must not end up
+                // in the headers being signed.
+                remove_header(request, "authorization");
+                set_header(request, "x-amz-date", &amz_date);
+                // Only S3 wants the payload hash as a header; the SDKs leave
+                // it off for every other service.
+                if service == "s3" {
+                    set_header(request, "x-amz-content-sha256", &payload_hash);
+                }
+                if !session_token.is_empty() {
+                    set_header(request, "x-amz-security-token", session_token);
+                }
+
+                let (canonical_headers, signed_headers) = canonical_headers(request, None);
+                let canonical_request = format!(
+                    "{}\n{}\n{}\n{}\n{}\n{}",
+                    request.method,
+                    canonical_path(&request.url, service != "s3"),
+                    canonical_query(&request.url),
+                    canonical_headers,
+                    signed_headers,
+                    payload_hash
+                );
+
+                let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+                let string_to_sign = format!(
+                    "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+                    amz_date,
+                    scope,
+                    sha256_hex(canonical_request.as_bytes())
+                );
+
+                let mut key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+                for part in [region.as_str(), service.as_str(), "aws4_request"] {
+                    key = hmac_sha256(&key, part.as_bytes());
+                }
+                let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+                set_header(
+                    request,
+                    "Authorization",
+                    &format!(
+                        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                        access_key, scope, signed_headers, signature
+                    ),
+                );
+
+                SigningDebug {
+                    canonical_request,
+                    string_to_sign,
+                }
+            }
+            Signing::Hmac { key_id, secret, header, signed_headers } => {
+                remove_header(request, header);
+                if signed_headers.iter().any(|h| h == "date") && find_header(request, "date").is_none() {
+                    set_header(request, "Date", &now.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
+                }
+
+                let (canonical_headers, signed) = canonical_headers(request, Some(signed_headers));
+                let query = canonical_query(&request.url);
+                let canonical_request = format!(
+                    "{}\n{}{}\n{}\n{}",
+                    request.method,
+                    url_path(&request.url),
+                    if query.is_empty() { String::new() } else { format!("?{}", query) },
+                    canonical_headers,
+                    sha256_hex(request.body.as_deref().unwrap_or("").as_bytes())
+                );
+                let string_to_sign = canonical_request.clone();
+                let signature = hex::encode(hmac_sha256(secret.as_bytes(), string_to_sign.as_bytes()));
+
+                set_header(
+                    request,
+                    header,
+                    &format!(
+                        "HMAC-SHA256 Credential={}, SignedHeaders={}, Signature={}",
+                        key_id, signed, signature
+                    ),
+                );
+
+                SigningDebug {
+                    canonical_request,
+                    string_to_sign,
+                }
+            }
+        }
+    }
+
+    // Takes off every header `sign` adds, so none of them (the session
+    // token included) goes to a host the credentials aren't meant for.
+    pub fn unsign(&self, request: &mut Request) {
+        match self {
+            Signing::AwsSigV4 { .. } => {
+                for name in ["authorization", "x-amz-date", "x-amz-content-sha256", "x-amz-security-token"] {
+                    remove_header(request, name);
+                }
+            }
+            Signing::Hmac { header, .. } => remove_header(request, header),
+        }
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn find_header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn remove_header(request: &mut Request, name: &str) {
+    request.headers.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+}
+
+fn set_header(request: &mut Request, name: &str, value: &str) {
+    remove_header(request, name);
+    request.headers.push((name.to_string(), value.to_string()));
+}
+
+// RFC 3986 unreserved characters stay as they are, everything else is
+// percent-encoded, which is what SigV4 expects.
+fn uri_encode(value: impl AsRef<[u8]>) -> String {
+    value
+        .as_ref()
+        .iter()
+        .map(|&b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn url_path(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) if !url.path().is_empty() => url.path().to_string(),
+        _ => "/".to_string(),
+    }
+}
+
+// Each segment is decoded and encoded again so the result doesn't depend on
+// how the URL was typed. Every service but S3 then encodes it a second time.
+fn canonical_path(url: &str, double: bool) -> String {
+    url_path(url)
+        .split('/')
+        .map(|segment| {
+            let encoded = uri_encode(percent_decode_str(segment).collect::<Vec<u8>>());
+            if double { uri_encode(encoded) } else { encoded }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(url: &str) -> String {
+    let Ok(url) = Url::parse(url) else {
+        return String::new();
+    };
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| (uri_encode(key.as_bytes()), uri_encode(value.as_bytes())))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn host(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            _ => String::new(),
+        },
+        Err(_) => String::new(),
+    }
+}
+
+// Returns the canonical header block and the `;`-joined list of signed header
+// names. With `only` set, just those headers are signed; otherwise every header
+// on the request plus `host`.
+fn canonical_headers(request: &Request, only: Option<&Vec<String>>) -> (String, String) {
+    let mut headers: Vec<(String, String)> = vec![("host".to_string(), host(&request.url))];
+    for (key, value) in &request.headers {
+        let key = key.to_lowercase();
+        if key == "host" {
+            headers.retain(|(k, _)| k != "host");
+        }
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        match headers.iter_mut().find(|(k, _)| *k == key) {
+            Some(existing) => existing.1 = format!("{},{}", existing.1, value),
+            None => headers.push((key, value)),
+        }
+    }
+    if let Some(only) = only {
+        headers.retain(|(key, _)| only.contains(key));
+    }
+    headers.sort();
+
+    let block = headers
+        .iter()
+        .map(|(key, value)| format!("{}:{}\n", key, value))
+        .collect::<String>();
+    let names = headers
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    (block, names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // Vectors from the AWS SigV4 test suite; they all share these credentials
+    // and this timestamp.
+    fn aws_example(service: &str, region: &str) -> Signing {
+        Signing::AwsSigV4 {
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: String::new(),
+            region: region.to_string(),
+            service: service.to_string(),
+        }
+    }
+
+    fn suite_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap()
+    }
+
+    fn request(method: &str, url: &str, headers: &[(&str, &str)]) -> Request {
+        Request {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            body: None,
+        }
+    }
+
+    fn authorization(request: &Request) -> &str {
+        find_header(request, "authorization").unwrap()
+    }
+
+    fn assert_signature(request: &Request, signed_headers: &str, signature: &str) {
+        assert_eq!(
+            authorization(request),
+            format!(
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+                 SignedHeaders={}, Signature={}",
+                signed_headers, signature
+            )
+        );
+    }
+
+    #[test]
+    fn sigv4_get_vanilla() {
+        let mut req = request("GET", "https://example.amazonaws.com/", &[]);
+        let debug = aws_example("service", "us-east-1").sign(&mut req, suite_time());
+        assert_eq!(
+            debug.canonical_request,
+            "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            debug.string_to_sign,
+            "AWS4-HMAC-SHA256\n20150830T123600Z\n20150830/us-east-1/service/aws4_request\n\
+             bb579772317eb040ac9ed261061d46c1f17a8133879d6129b6e1c25292927e63"
+        );
+        assert_signature(
+            &req,
+            "host;x-amz-date",
+            "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31",
+        );
+    }
+
+    #[test]
+    fn sigv4_post_vanilla() {
+        let mut req = request("POST", "https://example.amazonaws.com/", &[]);
+        aws_example("service", "us-east-1").sign(&mut req, suite_time());
+        assert_signature(
+            &req,
+            "host;x-amz-date",
+            "5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b",
+        );
+    }
+
+    #[test]
+    fn sigv4_query_order_key_case() {
+        let mut req = request("GET", "https://example.amazonaws.com/?Param2=value2&Param1=value1", &[]);
+        aws_example("service", "us-east-1").sign(&mut req, suite_time());
+        assert_signature(
+            &req,
+            "host;x-amz-date",
+            "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500",
+        );
+    }
+
+    #[test]
+    fn sigv4_header_value_trim() {
+        let mut req = request(
+            "GET",
+            "https://example.amazonaws.com/",
+            &[("My-Header1", " value1"), ("My-Header2", " \"a   b   c\"")],
+        );
+        aws_example("service", "us-east-1").sign(&mut req, suite_time());
+        assert_signature(
+            &req,
+            "host;my-header1;my-header2;x-amz-date",
+            "acc3ed3afb60bb290fc8d2dd0098b9911fcaa05412b367055dee359757a9c736",
+        );
+    }
+
+    #[test]
+    fn sigv4_header_key_duplicate() {
+        let mut req = request(
+            "GET",
+            "https://example.amazonaws.com/",
+            &[("My-Header1", "value2"), ("My-Header1", "value2"), ("My-Header1", "value1")],
+        );
+        aws_example("service", "us-east-1").sign(&mut req, suite_time());
+        assert_signature(
+            &req,
+            "host;my-header1;x-amz-date",
+            "c9d5ea9f3f72853aea855b47ea873832890dbdd183b4468f858259531a5138ea",
+        );
+    }
+
+    #[test]
+    fn sigv4_session_token() {
+        let token = "6e86291e8372ff2a2260956d9b8aae1d763fbf315fa00fa31553b73ebf194267";
+        let mut signing = aws_example("service", "us-east-1");
+        signing.set("session_token", token.to_string());
+        let mut req = request("GET", "https://example.amazonaws.com/", &[]);
+        signing.sign(&mut req, suite_time());
+        assert_eq!(find_header(&req, "x-amz-security-token"), Some(token));
+        assert_signature(
+            &req,
+            "host;x-amz-date;x-amz-security-token",
+            "07ec1639c89043aa0e3e2de82b96708f198cceab042d4a97044c66dd9f74e7f8",
+        );
+    }
+
+    #[test]
+    fn unsign_takes_off_every_signing_header() {
+        let mut signing = aws_example("s3", "us-east-1");
+        signing.set("session_token", "token".to_string());
+        let mut req = request("GET", "https://example.amazonaws.com/", &[("Accept", "*/*")]);
+        signing.sign(&mut req, suite_time());
+        signing.unsign(&mut req);
+        assert_eq!(req.headers, vec![("Accept".to_string(), "*/*".to_string())]);
+
+        let hmac = Signing::Hmac {
+            key_id: "id".to_string(),
+            secret: "secret".to_string(),
+            header: "X-Signature".to_string(),
+            signed_headers: vec!["host".to_string()],
+        };
+        let mut req = request("GET", "https://example.com/", &[]);
+        hmac.sign(&mut req, suite_time());
+        assert!(find_header(&req, "x-signature").is_some());
+        hmac.unsign(&mut req);
+        assert!(req.headers.is_empty());
+    }
+
+    #[test]
+    fn sigv4_double_url_encode() {
+        let mut req = request(
+            "POST",
+            "https://lambda.us-east-2.amazonaws.com/2015-03-31/functions/\
+             arn%3Aaws%3Alambda%3Aus-west-2%3A892717189312%3Afunction%3Amy-rusty-fun/invocations",
+            &[],
+        );
+        let now = Utc.with_ymd_and_hms(2021, 5, 11, 15, 40, 45).unwrap();
+        let debug = aws_example("lambda", "us-east-2").sign(&mut req, now);
+        assert_eq!(
+            debug.canonical_request,
+            "POST\n/2015-03-31/functions/\
+             arn%253Aaws%253Alambda%253Aus-west-2%253A892717189312%253Afunction%253Amy-rusty-fun/invocations\n\n\
+             host:lambda.us-east-2.amazonaws.com\nx-amz-date:20210511T154045Z\n\nhost;x-amz-date\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            debug.string_to_sign,
+            "AWS4-HMAC-SHA256\n20210511T154045Z\n20210511/us-east-2/lambda/aws4_request\n\
+             684dbb3c92a8b6b1e452e23e523c1ea941c713a4c13500bb9f3bdad1e19afaf7"
+        );
+    }
+
+    #[test]
+    fn sigv4_s3_encodes_once_and_hashes_payload() {
+        let mut req = request("PUT", "https://bucket.s3.amazonaws.com/a%20b/c", &[]);
+        let debug = aws_example("s3", "us-east-1").sign(&mut req, suite_time());
+        assert!(debug.canonical_request.starts_with("PUT\n/a%20b/c\n"));
+        assert_eq!(
+            find_header(&req, "x-amz-content-sha256"),
+            Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+    }
+
+    // Redirects sign the same request again, with the previous signature
+    // still attached.
+    #[test]
+    fn signing_again_ignores_the_old_signature() {
+        let signing = aws_example("service", "us-east-1");
+        let mut req = request("GET", "https://example.amazonaws.com/", &[]);
+        signing.sign(&mut req, suite_time());
+        let debug = signing.sign(&mut req, suite_time());
+        assert!(!debug.canonical_request.contains("authorization"));
+        assert_signature(
+            &req,
+            "host;x-amz-date",
+            "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31",
+        );
+        assert_eq!(req.headers.iter().filter(|(k, _)| k.eq_ignore_ascii_case("authorization")).count(), 1);
+    }
+
+    // RFC 4231, test case 2.
+    #[test]
+    fn hmac_sha256_known_answer() {
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn hmac_signing_known_answer() {
+        let signing = Signing::Hmac {
+            key_id: "key".to_string(),
+            secret: "secret".to_string(),
+            header: "Authorization".to_string(),
+            signed_headers: vec!["host".to_string(), "date".to_string()],
+        };
+        let mut req = request("GET", "https://example.com/path?b=2&a=1", &[]);
+        let debug = signing.sign(&mut req, suite_time());
+        assert_eq!(find_header(&req, "date"), Some("Sun, 30 Aug 2015 12:36:00 GMT"));
+        assert_eq!(
+            debug.canonical_request,
+            "GET\n/path?a=1&b=2\ndate:Sun, 30 Aug 2015 12:36:00 GMT\nhost:example.com\n\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        let expected = "HMAC-SHA256 Credential=key, SignedHeaders=date;host, \
+                        Signature=4225739d3791086d07d079db6dbec3a75f97bf14b92c7239744ccb4bfee7aac4";
+        assert_eq!(authorization(&req), expected);
+
+        signing.sign(&mut req, suite_time());
+        assert_eq!(authorization(&req), expected);
+    }
+}
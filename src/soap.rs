@@ -0,0 +1,765 @@
+use std::collections::HashMap;
+
+use roxmltree::{Document, Node, NodeId, ParsingOptions};
+
+const WSDL_NS: &str = "http://schemas.xmlsoap.org/wsdl/";
+const SOAP11_BINDING_NS: &str = "http://schemas.xmlsoap.org/wsdl/soap/";
+const SOAP12_BINDING_NS: &str = "http://schemas.xmlsoap.org/wsdl/soap12/";
+const XSD_NS: &str = "http://www.w3.org/2001/XMLSchema";
+// How far the envelope template goes into nested types.
+const TEMPLATE_DEPTH: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Version {
+    Soap11,
+    Soap12,
+}
+
+impl Version {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Version::Soap11 => "SOAP 1.1",
+            Version::Soap12 => "SOAP 1.2",
+        }
+    }
+
+    pub fn envelope_namespace(self) -> &'static str {
+        match self {
+            Version::Soap11 => "http://schemas.xmlsoap.org/soap/envelope/",
+            Version::Soap12 => "http://www.w3.org/2003/05/soap-envelope",
+        }
+    }
+
+    // SOAP 1.2 has no SOAPAction header, the action goes in the content
+    // type instead.
+    pub fn content_type(self, action: &str) -> String {
+        match self {
+            Version::Soap11 => "text/xml; charset=utf-8".to_string(),
+            Version::Soap12 if action.is_empty() => "application/soap+xml; charset=utf-8".to_string(),
+            Version::Soap12 => format!("application/soap+xml; charset=utf-8; action=\"{}\"", action),
+        }
+    }
+
+    pub fn soap_action(self, action: &str) -> Option<String> {
+        match self {
+            Version::Soap11 => Some(format!("\"{}\"", action)),
+            Version::Soap12 => None,
+        }
+    }
+}
+
+// One operation of one port in a WSDL, with the envelope to send for it.
+#[derive(Clone, Debug)]
+pub struct Operation {
+    pub service: String,
+    pub port: String,
+    pub name: String,
+    pub action: String,
+    pub version: Version,
+    // The port's address, if the WSDL gives one.
+    pub endpoint: Option<String>,
+    pub envelope: String,
+}
+
+impl Operation {
+    pub fn label(&self) -> String {
+        format!("{}.{}", self.port, self.name)
+    }
+}
+
+fn options() -> ParsingOptions {
+    ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    }
+}
+
+// The local part of a QName like `tns:Foo`.
+fn local(qname: &str) -> &str {
+    qname.rsplit(':').next().unwrap_or(qname)
+}
+
+fn children<'a, 'input, 'n>(node: Node<'a, 'input>, namespace: &'n str, name: &'n str) -> impl Iterator<Item = Node<'a, 'input>> + 'n
+where
+    'a: 'n,
+    'input: 'n,
+{
+    node.children().filter(move |child| child.has_tag_name((namespace, name)))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, namespace: &str, name: &str) -> Option<Node<'a, 'input>> {
+    children(node, namespace, name).next()
+}
+
+// The SOAP extension element `name` under a binding, port or operation,
+// and which version of SOAP it's for.
+fn soap_child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<(Version, Node<'a, 'input>)> {
+    node.children().find_map(|child| match child.tag_name().namespace() {
+        Some(SOAP11_BINDING_NS) if child.tag_name().name() == name => Some((Version::Soap11, child)),
+        Some(SOAP12_BINDING_NS) if child.tag_name().name() == name => Some((Version::Soap12, child)),
+        _ => None,
+    })
+}
+
+fn named<'a, 'input>(node: Node<'a, 'input>, name: &'static str) -> HashMap<&'a str, Node<'a, 'input>> {
+    children(node, WSDL_NS, name)
+        .filter_map(|child| Some((child.attribute("name")?, child)))
+        .collect()
+}
+
+// Every SOAP operation of every port in `wsdl`. Only WSDL 1.1 with the
+// schemas inline is understood; types from imported schemas come out as
+// `?` in the envelopes.
+pub fn operations(wsdl: &str) -> Result<Vec<Operation>, String> {
+    let doc = Document::parse_with_options(wsdl, options()).map_err(|err| format!("the WSDL isn't XML: {}", err))?;
+    let definitions = doc.root_element();
+    if !definitions.has_tag_name((WSDL_NS, "definitions")) {
+        return Err("not a WSDL 1.1 document".to_string());
+    }
+    let target = definitions.attribute("targetNamespace").unwrap_or_default();
+    let messages = named(definitions, "message");
+    let port_types = named(definitions, "portType");
+    let bindings = named(definitions, "binding");
+    let schemas: Vec<Node> = child(definitions, WSDL_NS, "types")
+        .map(|types| children(types, XSD_NS, "schema").collect())
+        .unwrap_or_default();
+
+    let mut operations = Vec::new();
+    for service in children(definitions, WSDL_NS, "service") {
+        for port in children(service, WSDL_NS, "port") {
+            let Some(binding) = port.attribute("binding").and_then(|binding| bindings.get(local(binding))) else {
+                continue;
+            };
+            // Ports with HTTP bindings have no envelopes to build.
+            let Some((version, soap_binding)) = soap_child(*binding, "binding") else {
+                continue;
+            };
+            let endpoint = soap_child(port, "address").and_then(|(_, address)| address.attribute("location"));
+            let port_type = binding.attribute("type").and_then(|port_type| port_types.get(local(port_type)));
+            for operation in children(*binding, WSDL_NS, "operation") {
+                let name = operation.attribute("name").unwrap_or_default();
+                let soap_operation = soap_child(operation, "operation").map(|(_, node)| node);
+                let style = soap_operation
+                    .and_then(|node| node.attribute("style"))
+                    .or_else(|| soap_binding.attribute("style"))
+                    .unwrap_or("document");
+                let body = child(operation, WSDL_NS, "input").and_then(|input| soap_child(input, "body")).map(|(_, node)| node);
+                let message = port_type
+                    .and_then(|port_type| children(*port_type, WSDL_NS, "operation").find(|node| node.attribute("name") == Some(name)))
+                    .and_then(|node| child(node, WSDL_NS, "input"))
+                    .and_then(|input| input.attribute("message"))
+                    .and_then(|message| messages.get(local(message)));
+                // `parts` on soap:body picks which parts go in the body.
+                let wanted = body.and_then(|body| body.attribute("parts")).map(|parts| parts.split_whitespace().collect::<Vec<_>>());
+                let parts: Vec<Node> = message
+                    .map(|message| {
+                        children(*message, WSDL_NS, "part")
+                            .filter(|part| wanted.as_ref().is_none_or(|wanted| wanted.contains(&part.attribute("name").unwrap_or_default())))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let mut template = Template::new(&schemas);
+                let content = if style == "rpc" {
+                    let namespace = body.and_then(|body| body.attribute("namespace")).unwrap_or(target);
+                    let wrapper = template.prefixed(namespace, name);
+                    let inner = parts.iter().flat_map(|part| template.part(*part, true)).collect();
+                    template.wrap(wrapper, Vec::new(), Shape::Children(inner))
+                } else {
+                    parts.iter().flat_map(|part| template.part(*part, false)).collect()
+                };
+                operations.push(Operation {
+                    service: service.attribute("name").unwrap_or_default().to_string(),
+                    port: port.attribute("name").unwrap_or_default().to_string(),
+                    name: name.to_string(),
+                    action: soap_operation.and_then(|node| node.attribute("soapAction")).unwrap_or_default().to_string(),
+                    version,
+                    endpoint: endpoint.map(|endpoint| endpoint.to_string()),
+                    envelope: template.envelope(version, content),
+                });
+            }
+        }
+    }
+    if operations.is_empty() {
+        return Err("the WSDL has no SOAP operations".to_string());
+    }
+    Ok(operations)
+}
+
+enum Shape {
+    Text,
+    Children(Vec<String>),
+}
+
+// Builds the body of an envelope from the schemas in a WSDL, with `?`
+// wherever a value goes, like the skeletons other SOAP clients generate.
+struct Template<'a, 'input> {
+    schemas: &'a [Node<'a, 'input>],
+    // Namespaces used so far; the nth one gets the prefix `ns<n>`.
+    namespaces: Vec<String>,
+}
+
+impl<'a, 'input> Template<'a, 'input> {
+    fn new(schemas: &'a [Node<'a, 'input>]) -> Self {
+        Self {
+            schemas,
+            namespaces: Vec::new(),
+        }
+    }
+
+    fn prefixed(&mut self, namespace: &str, name: &str) -> String {
+        if namespace.is_empty() {
+            return name.to_string();
+        }
+        let i = match self.namespaces.iter().position(|known| known == namespace) {
+            Some(i) => i,
+            None => {
+                self.namespaces.push(namespace.to_string());
+                self.namespaces.len() - 1
+            }
+        };
+        format!("ns{}:{}", i + 1, name)
+    }
+
+    // The top-level schema component `kind` (element, complexType, ...)
+    // that the QName `reference` in `context` points at. Nothing for the
+    // built-in XSD types.
+    fn global(&self, kind: &str, reference: &str, context: Node) -> Option<Node<'a, 'input>> {
+        let prefix = reference.split_once(':').map(|(prefix, _)| prefix);
+        let namespace = context.lookup_namespace_uri(prefix).unwrap_or_default();
+        if namespace == XSD_NS {
+            return None;
+        }
+        let name = local(reference);
+        self.schemas
+            .iter()
+            .filter(|schema| schema.attribute("targetNamespace").unwrap_or_default() == namespace)
+            .chain(self.schemas.iter())
+            .find_map(|schema| children(*schema, XSD_NS, kind).find(|node| node.attribute("name") == Some(name)))
+    }
+
+    fn part(&mut self, part: Node, rpc: bool) -> Vec<String> {
+        let name = part.attribute("name").unwrap_or_default().to_string();
+        let mut outer = Vec::new();
+        if let Some(element) = part.attribute("element") {
+            let lines = match self.global("element", element, part) {
+                Some(element) => self.element(element, element, 0, &mut outer),
+                None => vec![format!("<{0}>?</{0}>", local(element))],
+            };
+            return if rpc { self.wrap(name, Vec::new(), Shape::Children(lines)) } else { lines };
+        }
+        let complex = part.attribute("type").and_then(|type_name| self.global("complexType", type_name, part));
+        self.typed(name, complex, 0, &mut outer)
+    }
+
+    fn element(&mut self, element: Node<'a, 'input>, occurs: Node, depth: usize, outer: &mut Vec<NodeId>) -> Vec<String> {
+        if let Some(reference) = element.attribute("ref") {
+            return match self.global("element", reference, element) {
+                Some(global) => self.element(global, occurs, depth, outer),
+                None => vec![format!("<{0}>?</{0}>", local(reference))],
+            };
+        }
+        let mut lines = Vec::new();
+        let optional = occurs.attribute("minOccurs") == Some("0");
+        match occurs.attribute("maxOccurs") {
+            Some(max) if max != "1" && optional => lines.push("<!--Zero or more repetitions:-->".to_string()),
+            Some(max) if max != "1" => lines.push("<!--1 or more repetitions:-->".to_string()),
+            _ if optional => lines.push("<!--Optional:-->".to_string()),
+            _ => {}
+        }
+
+        let schema = element.ancestors().find(|node| node.has_tag_name((XSD_NS, "schema")));
+        let global = element.parent_element() == schema;
+        let form = element
+            .attribute("form")
+            .or_else(|| schema.and_then(|schema| schema.attribute("elementFormDefault")))
+            .unwrap_or("unqualified");
+        let name = element.attribute("name").unwrap_or_default();
+        let name = if global || form == "qualified" {
+            let namespace = schema.and_then(|schema| schema.attribute("targetNamespace")).unwrap_or_default();
+            self.prefixed(namespace, name)
+        } else {
+            name.to_string()
+        };
+
+        let complex = match element.attribute("type") {
+            Some(type_name) => self.global("complexType", type_name, element),
+            None => child(element, XSD_NS, "complexType"),
+        };
+        lines.extend(self.typed(name, complex, depth, outer));
+        lines
+    }
+
+    // An element called `name` holding a value of the complex type, or a
+    // simple value without one.
+    fn typed(&mut self, name: String, complex: Option<Node<'a, 'input>>, depth: usize, outer: &mut Vec<NodeId>) -> Vec<String> {
+        let Some(complex) = complex else {
+            return self.wrap(name, Vec::new(), Shape::Text);
+        };
+        // Recursive types stop at the first repeat.
+        if depth >= TEMPLATE_DEPTH || outer.contains(&complex.id()) {
+            return self.wrap(name, Vec::new(), Shape::Children(Vec::new()));
+        }
+        outer.push(complex.id());
+        let mut attributes = Vec::new();
+        let mut lines = Vec::new();
+        let text = self.complex(complex, depth, outer, &mut attributes, &mut lines);
+        outer.pop();
+        self.wrap(name, attributes, if text { Shape::Text } else { Shape::Children(lines) })
+    }
+
+    fn wrap(&mut self, name: String, attributes: Vec<String>, shape: Shape) -> Vec<String> {
+        let attributes: String = attributes.iter().map(|attribute| format!(" {}=\"?\"", attribute)).collect();
+        match shape {
+            Shape::Text => vec![format!("<{}{}>?</{}>", name, attributes, name)],
+            Shape::Children(lines) if lines.is_empty() => vec![format!("<{}{}/>", name, attributes)],
+            Shape::Children(lines) => {
+                let mut wrapped = vec![format!("<{}{}>", name, attributes)];
+                wrapped.extend(lines.into_iter().map(|line| format!("  {}", line)));
+                wrapped.push(format!("</{}>", name));
+                wrapped
+            }
+        }
+    }
+
+    // Collects the attributes and child elements of a complex type (or of
+    // the derivation or attribute group inside one). True when it has
+    // simple content, i.e. a value instead of children.
+    fn complex(
+        &mut self,
+        node: Node<'a, 'input>,
+        depth: usize,
+        outer: &mut Vec<NodeId>,
+        attributes: &mut Vec<String>,
+        lines: &mut Vec<String>,
+    ) -> bool {
+        let mut text = false;
+        for item in node.children().filter(|item| item.tag_name().namespace() == Some(XSD_NS)) {
+            match item.tag_name().name() {
+                "sequence" | "all" | "choice" | "group" => lines.extend(self.particle(item, depth, outer)),
+                "attribute" if item.attribute("use") != Some("prohibited") => {
+                    if let Some(name) = item.attribute("name").or_else(|| item.attribute("ref").map(local)) {
+                        attributes.push(name.to_string());
+                    }
+                }
+                "attributeGroup" => {
+                    if let Some(group) = item.attribute("ref").and_then(|reference| self.global("attributeGroup", reference, item)) {
+                        self.complex(group, depth, outer, attributes, lines);
+                    }
+                }
+                "complexContent" | "simpleContent" => {
+                    text = item.tag_name().name() == "simpleContent";
+                    for derivation in item.children().filter(|derivation| derivation.tag_name().namespace() == Some(XSD_NS)) {
+                        // An extension comes after what it extends; a
+                        // restriction spells out everything it keeps.
+                        let base = derivation
+                            .attribute("base")
+                            .filter(|_| derivation.tag_name().name() == "extension")
+                            .and_then(|base| self.global("complexType", base, derivation))
+                            .filter(|base| !outer.contains(&base.id()));
+                        if let Some(base) = base {
+                            outer.push(base.id());
+                            text |= self.complex(base, depth, outer, attributes, lines);
+                            outer.pop();
+                        }
+                        self.complex(derivation, depth, outer, attributes, lines);
+                    }
+                }
+                _ => {}
+            }
+        }
+        text
+    }
+
+    fn particle(&mut self, node: Node<'a, 'input>, depth: usize, outer: &mut Vec<NodeId>) -> Vec<String> {
+        let items = || node.children().filter(|item| item.tag_name().namespace() == Some(XSD_NS));
+        match node.tag_name().name() {
+            "element" => self.element(node, node, depth + 1, outer),
+            "sequence" | "all" => items().flat_map(|item| self.particle(item, depth, outer)).collect(),
+            "choice" => {
+                let choices: Vec<Node> = items().filter(|item| item.tag_name().name() != "annotation").collect();
+                let mut lines = Vec::new();
+                if choices.len() > 1 {
+                    lines.push(format!("<!--One of {} choices:-->", choices.len()));
+                }
+                if let Some(first) = choices.first() {
+                    lines.extend(self.particle(*first, depth, outer));
+                }
+                lines
+            }
+            "group" => match node.attribute("ref").and_then(|reference| self.global("group", reference, node)) {
+                Some(group) => children(group, XSD_NS, "sequence")
+                    .chain(children(group, XSD_NS, "choice"))
+                    .chain(children(group, XSD_NS, "all"))
+                    .flat_map(|item| self.particle(item, depth, outer))
+                    .collect(),
+                None => Vec::new(),
+            },
+            "any" => vec!["<!--Any element can go here-->".to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn envelope(&self, version: Version, body: Vec<String>) -> String {
+        let mut declarations = format!(" xmlns:soapenv=\"{}\"", version.envelope_namespace());
+        for (i, namespace) in self.namespaces.iter().enumerate() {
+            declarations.push_str(&format!(" xmlns:ns{}=\"{}\"", i + 1, escape(namespace)));
+        }
+        let mut lines = vec![
+            format!("<soapenv:Envelope{}>", declarations),
+            "  <soapenv:Header/>".to_string(),
+            "  <soapenv:Body>".to_string(),
+        ];
+        lines.extend(body.into_iter().map(|line| format!("    {}", line)));
+        lines.push("  </soapenv:Body>".to_string());
+        lines.push("</soapenv:Envelope>".to_string());
+        lines.join("\n")
+    }
+}
+
+// A fault from either version of SOAP, with the 1.2 parts mapped onto the
+// 1.1 ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fault {
+    pub code: String,
+    pub reason: String,
+    // faultactor in 1.1, Role (or Node) in 1.2.
+    pub actor: Option<String>,
+    // The detail element's contents, pretty-printed.
+    pub detail: Option<String>,
+}
+
+// A response envelope: its version, the fault if it is one, and the whole
+// envelope pretty-printed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reply {
+    pub version: Version,
+    pub fault: Option<Fault>,
+    pub pretty: String,
+}
+
+// None when the body isn't a SOAP envelope.
+pub fn reply(body: &[u8]) -> Option<Reply> {
+    let input = std::str::from_utf8(body).ok()?;
+    let doc = Document::parse_with_options(input, options()).ok()?;
+    let envelope = doc.root_element();
+    let version = [Version::Soap11, Version::Soap12]
+        .into_iter()
+        .find(|version| envelope.has_tag_name((version.envelope_namespace(), "Envelope")))?;
+    let namespace = version.envelope_namespace();
+    let fault = child(envelope, namespace, "Body")
+        .and_then(|body| child(body, namespace, "Fault"))
+        .map(|fault| match version {
+            Version::Soap11 => {
+                // The parts of a 1.1 fault aren't namespaced.
+                let part = |name: &str| fault.children().find(|node| node.tag_name().name() == name);
+                let text = |name: &str| part(name).and_then(|node| node.text()).map(|text| text.trim().to_string());
+                Fault {
+                    code: text("faultcode").unwrap_or_default(),
+                    reason: text("faultstring").unwrap_or_default(),
+                    actor: text("faultactor"),
+                    detail: part("detail").map(|detail| pretty_children(input, detail)),
+                }
+            }
+            Version::Soap12 => {
+                let value = |node: Node| child(node, namespace, "Value").and_then(|value| value.text()).map(|text| text.trim().to_string());
+                // The code and its subcodes, most general first.
+                let mut codes = Vec::new();
+                let mut code = child(fault, namespace, "Code");
+                while let Some(node) = code {
+                    codes.extend(value(node));
+                    code = child(node, namespace, "Subcode");
+                }
+                let text = |name: &str| child(fault, namespace, name).and_then(|node| node.text()).map(|text| text.trim().to_string());
+                Fault {
+                    code: codes.join(" / "),
+                    reason: child(fault, namespace, "Reason")
+                        .and_then(|reason| child(reason, namespace, "Text"))
+                        .and_then(|node| node.text())
+                        .map(|text| text.trim().to_string())
+                        .unwrap_or_default(),
+                    actor: text("Role").or_else(|| text("Node")),
+                    detail: child(fault, namespace, "Detail").map(|detail| pretty_children(input, detail)),
+                }
+            }
+        });
+    let mut lines = Vec::new();
+    pretty_node(input, envelope, 0, &mut lines);
+    Some(Reply {
+        version,
+        fault,
+        pretty: lines.join("\n"),
+    })
+}
+
+fn pretty_children(input: &str, node: Node) -> String {
+    let mut lines = Vec::new();
+    for child in node.children() {
+        pretty_node(input, child, 0, &mut lines);
+    }
+    lines.join("\n")
+}
+
+// Re-indents XML under `node`, keeping tags as written, prefixes and all.
+fn pretty_node(input: &str, node: Node, indent: usize, lines: &mut Vec<String>) {
+    let pad = "  ".repeat(indent);
+    if node.is_text() {
+        let text = node.text().unwrap_or_default().trim();
+        if !text.is_empty() {
+            lines.push(format!("{}{}", pad, escape(text)));
+        }
+        return;
+    }
+    if node.is_comment() {
+        lines.push(format!("{}<!--{}-->", pad, node.text().unwrap_or_default()));
+        return;
+    }
+    if !node.is_element() {
+        return;
+    }
+    let tag = start_tag(input, node);
+    let name: String = tag[1..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+        .collect();
+    let content: Vec<Node> = node
+        .children()
+        .filter(|child| child.is_element() || child.is_comment() || child.text().is_some_and(|text| !text.trim().is_empty()))
+        .collect();
+    match content.as_slice() {
+        [] => lines.push(format!("{}{}", pad, tag.trim_end_matches('>').trim_end_matches('/').trim_end().to_string() + "/>")),
+        [only] if only.is_text() => lines.push(format!(
+            "{}{}{}</{}>",
+            pad,
+            tag,
+            escape(only.text().unwrap_or_default().trim()),
+            name
+        )),
+        _ => {
+            lines.push(format!("{}{}", pad, tag));
+            for child in content {
+                pretty_node(input, child, indent + 1, lines);
+            }
+            lines.push(format!("{}</{}>", pad, name));
+        }
+    }
+}
+
+// The start tag of `node` as written in `input`, with runs of whitespace
+// between attributes squeezed to one space.
+fn start_tag(input: &str, node: Node) -> String {
+    let mut tag = String::new();
+    let mut quote = None;
+    let mut space = false;
+    for c in input[node.range().start..].chars() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c.is_whitespace() => {
+                space = true;
+                continue;
+            }
+            None => {}
+        }
+        if space {
+            if c != '>' && c != '/' {
+                tag.push(' ');
+            }
+            space = false;
+        }
+        tag.push(c);
+        if quote.is_none() && c == '>' {
+            break;
+        }
+    }
+    tag
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WSDL: &str = r#"<?xml version="1.0"?>
+<definitions xmlns="http://schemas.xmlsoap.org/wsdl/" xmlns:soap="http://schemas.xmlsoap.org/wsdl/soap/"
+    xmlns:soap12="http://schemas.xmlsoap.org/wsdl/soap12/" xmlns:tns="urn:calc"
+    xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:calc">
+  <types>
+    <xs:schema targetNamespace="urn:calc" elementFormDefault="qualified">
+      <xs:element name="Add">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="a" type="xs:int"/>
+            <xs:element name="b" type="xs:int" minOccurs="0"/>
+            <xs:element name="tags" type="xs:string" minOccurs="0" maxOccurs="unbounded"/>
+          </xs:sequence>
+          <xs:attribute name="precision" type="xs:int"/>
+        </xs:complexType>
+      </xs:element>
+    </xs:schema>
+  </types>
+  <message name="AddIn"><part name="parameters" element="tns:Add"/></message>
+  <message name="EchoIn"><part name="text" type="xs:string"/></message>
+  <portType name="CalcType">
+    <operation name="Add"><input message="tns:AddIn"/></operation>
+    <operation name="Echo"><input message="tns:EchoIn"/></operation>
+  </portType>
+  <binding name="Calc11" type="tns:CalcType">
+    <soap:binding style="document" transport="http://schemas.xmlsoap.org/soap/http"/>
+    <operation name="Add">
+      <soap:operation soapAction="urn:calc#Add"/>
+      <input><soap:body use="literal"/></input>
+    </operation>
+  </binding>
+  <binding name="Calc12" type="tns:CalcType">
+    <soap12:binding style="rpc" transport="http://schemas.xmlsoap.org/soap/http"/>
+    <operation name="Echo">
+      <soap12:operation soapAction="urn:calc#Echo"/>
+      <input><soap12:body use="literal" namespace="urn:echo"/></input>
+    </operation>
+  </binding>
+  <service name="Calculator">
+    <port name="Calc11Port" binding="tns:Calc11"><soap:address location="http://example.com/calc"/></port>
+    <port name="Calc12Port" binding="tns:Calc12"><soap12:address location="http://example.com/calc12"/></port>
+  </service>
+</definitions>"#;
+
+    #[test]
+    fn builds_document_and_rpc_envelopes() {
+        let operations = operations(WSDL).unwrap();
+        assert_eq!(operations.len(), 2);
+
+        let add = &operations[0];
+        assert_eq!(add.label(), "Calc11Port.Add");
+        assert_eq!(add.service, "Calculator");
+        assert_eq!(add.version, Version::Soap11);
+        assert_eq!(add.action, "urn:calc#Add");
+        assert_eq!(add.endpoint.as_deref(), Some("http://example.com/calc"));
+        assert_eq!(
+            add.envelope,
+            "<soapenv:Envelope xmlns:soapenv=\"http://schemas.xmlsoap.org/soap/envelope/\" xmlns:ns1=\"urn:calc\">\n  \
+             <soapenv:Header/>\n  \
+             <soapenv:Body>\n    \
+             <ns1:Add precision=\"?\">\n      \
+             <ns1:a>?</ns1:a>\n      \
+             <!--Optional:-->\n      \
+             <ns1:b>?</ns1:b>\n      \
+             <!--Zero or more repetitions:-->\n      \
+             <ns1:tags>?</ns1:tags>\n    \
+             </ns1:Add>\n  \
+             </soapenv:Body>\n\
+             </soapenv:Envelope>"
+        );
+
+        let echo = &operations[1];
+        assert_eq!(echo.label(), "Calc12Port.Echo");
+        assert_eq!(echo.version, Version::Soap12);
+        assert_eq!(echo.endpoint.as_deref(), Some("http://example.com/calc12"));
+        assert_eq!(
+            echo.envelope,
+            "<soapenv:Envelope xmlns:soapenv=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:ns1=\"urn:echo\">\n  \
+             <soapenv:Header/>\n  \
+             <soapenv:Body>\n    \
+             <ns1:Echo>\n      \
+             <text>?</text>\n    \
+             </ns1:Echo>\n  \
+             </soapenv:Body>\n\
+             </soapenv:Envelope>"
+        );
+    }
+
+    #[test]
+    fn puts_the_action_where_each_version_wants_it() {
+        assert_eq!(Version::Soap11.soap_action("urn:calc#Add").as_deref(), Some("\"urn:calc#Add\""));
+        assert_eq!(Version::Soap11.content_type("urn:calc#Add"), "text/xml; charset=utf-8");
+        assert_eq!(Version::Soap12.soap_action("urn:calc#Add"), None);
+        assert_eq!(
+            Version::Soap12.content_type("urn:calc#Add"),
+            "application/soap+xml; charset=utf-8; action=\"urn:calc#Add\""
+        );
+        assert_eq!(Version::Soap12.content_type(""), "application/soap+xml; charset=utf-8");
+    }
+
+    #[test]
+    fn rejects_documents_without_soap_operations() {
+        assert!(operations("not xml").is_err());
+        assert!(operations("<definitions/>").is_err());
+        assert!(operations(r#"<definitions xmlns="http://schemas.xmlsoap.org/wsdl/"/>"#).is_err());
+    }
+
+    #[test]
+    fn reads_soap11_faults() {
+        let body = br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
+  <s:Body>
+    <s:Fault>
+      <faultcode>s:Client</faultcode>
+      <faultstring> Bad input </faultstring>
+      <faultactor>urn:calc</faultactor>
+      <detail><e:Err xmlns:e="urn:err"><e:field>a</e:field></e:Err></detail>
+    </s:Fault>
+  </s:Body>
+</s:Envelope>"#;
+        let reply = reply(body).unwrap();
+        assert_eq!(reply.version, Version::Soap11);
+        assert_eq!(
+            reply.fault,
+            Some(Fault {
+                code: "s:Client".to_string(),
+                reason: "Bad input".to_string(),
+                actor: Some("urn:calc".to_string()),
+                detail: Some("<e:Err xmlns:e=\"urn:err\">\n  <e:field>a</e:field>\n</e:Err>".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn reads_soap12_faults() {
+        let body = br#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+ <env:Body>
+  <env:Fault>
+   <env:Code>
+    <env:Value>env:Sender</env:Value>
+    <env:Subcode><env:Value>m:BadAmount</env:Value></env:Subcode>
+   </env:Code>
+   <env:Reason><env:Text xml:lang="en">Amount too large</env:Text></env:Reason>
+   <env:Node>urn:node</env:Node>
+   <env:Detail><m:max xmlns:m="urn:m">100</m:max></env:Detail>
+  </env:Fault>
+ </env:Body>
+</env:Envelope>"#;
+        let reply = reply(body).unwrap();
+        assert_eq!(reply.version, Version::Soap12);
+        assert_eq!(
+            reply.fault,
+            Some(Fault {
+                code: "env:Sender / m:BadAmount".to_string(),
+                reason: "Amount too large".to_string(),
+                actor: Some("urn:node".to_string()),
+                detail: Some("<m:max xmlns:m=\"urn:m\">100</m:max>".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn pretty_prints_replies_without_faults() {
+        let body = br#"<s:Envelope   xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Header/><s:Body><r a="1 &lt; 2">1 &amp; 2</r></s:Body></s:Envelope>"#;
+        let reply = reply(body).unwrap();
+        assert_eq!(reply.fault, None);
+        assert_eq!(
+            reply.pretty,
+            "<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\">\n  \
+             <s:Header/>\n  \
+             <s:Body>\n    \
+             <r a=\"1 &lt; 2\">1 &amp; 2</r>\n  \
+             </s:Body>\n\
+             </s:Envelope>"
+        );
+
+        assert_eq!(super::reply(b"<html/>"), None);
+        assert_eq!(super::reply(b"{}"), None);
+    }
+}
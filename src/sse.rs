@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest_cookie_store::CookieStoreMutex;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::request::{self, Reply, Request};
+use crate::settings::RequestSettings;
+
+// A stream may start with one, which isn't part of the first field name.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+// How long to wait before reconnecting until the server sends its own
+// `retry`; browsers use a few seconds too.
+const DEFAULT_RETRY_MS: u64 = 3000;
+
+// One dispatched event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub time: String,
+    pub event: String,
+    pub id: Option<String>,
+    pub data: String,
+    pub retry: Option<u64>,
+}
+
+// Turns the bytes of an event stream into frames, following the parsing
+// rules of the HTML spec: `field: value` lines, `:` comments, and a blank
+// line to dispatch.
+#[derive(Default)]
+pub struct Parser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+    id: Option<String>,
+    retry: Option<u64>,
+    // Past the byte order mark, if the stream has one.
+    started: bool,
+    pub last_event_id: Option<String>,
+    pub reconnect_ms: Option<u64>,
+}
+
+impl Parser {
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Frame> {
+        self.buffer.extend_from_slice(bytes);
+        if !self.started {
+            // The mark may come split across chunks.
+            if self.buffer.len() < BOM.len() && BOM.starts_with(&self.buffer) {
+                return Vec::new();
+            }
+            if self.buffer.starts_with(BOM) {
+                self.buffer.drain(..BOM.len());
+            }
+            self.started = true;
+        }
+        let mut frames = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i < self.buffer.len() {
+            match self.buffer[i] {
+                b'\n' => {}
+                // A CR at the very end may be the first half of a CRLF.
+                b'\r' if i + 1 == self.buffer.len() => break,
+                b'\r' => {}
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            }
+            let line = String::from_utf8_lossy(&self.buffer[start..i]).to_string();
+            if self.buffer[i] == b'\r' && self.buffer.get(i + 1) == Some(&b'\n') {
+                i += 1;
+            }
+            i += 1;
+            start = i;
+            frames.extend(self.line(&line));
+        }
+        self.buffer.drain(..start);
+        frames
+    }
+
+    fn line(&mut self, line: &str) -> Option<Frame> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok();
+                self.reconnect_ms = self.retry;
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Frame> {
+        // The id sticks even for events without data, so a reconnect
+        // resumes after them.
+        let id = self.id.take();
+        if id.is_some() {
+            self.last_event_id = id.clone();
+        }
+        let event = std::mem::take(&mut self.event);
+        let data = std::mem::take(&mut self.data);
+        let retry = self.retry.take();
+        if data.is_empty() {
+            return None;
+        }
+        Some(Frame {
+            time: chrono::Local::now().format("%H:%M:%S%.3f").to_string(),
+            event: if event.is_empty() { "message".to_string() } else { event },
+            id,
+            data: data.join("\n"),
+            retry,
+        })
+    }
+
+    // Whatever was half received is dropped when the connection goes.
+    fn reset(&mut self) {
+        self.buffer.clear();
+        self.started = false;
+        self.event.clear();
+        self.data.clear();
+        self.id = None;
+        self.retry = None;
+    }
+}
+
+pub enum Command {
+    Reconnect,
+    Stop,
+}
+
+pub enum Event {
+    Frame(Frame),
+    Status(String),
+    Open,
+    Closed,
+}
+
+// A subscription running on the runtime. Dropping it stops listening.
+pub struct Subscription {
+    pub commands: UnboundedSender<Command>,
+    pub events: UnboundedReceiver<Event>,
+}
+
+// `first` is the response that turned out to be an event stream; it's read
+// before `request` is ever sent again.
+pub fn subscribe(
+    runtime: &tokio::runtime::Runtime,
+    request: Request,
+    first: Option<reqwest::Response>,
+    settings: RequestSettings,
+    cookies: Arc<CookieStoreMutex>,
+    passphrases: HashMap<String, String>,
+) -> Subscription {
+    let (commands, mut receiver) = mpsc::unbounded_channel();
+    let (events, event_receiver) = mpsc::unbounded_channel();
+    runtime.spawn(async move {
+        run(request, first, &settings, cookies, &passphrases, &mut receiver, &events).await;
+        let _ = events.send(Event::Closed);
+    });
+    Subscription {
+        commands,
+        events: event_receiver,
+    }
+}
+
+// Connects, reads until the stream drops, waits `retry` and connects again
+// with Last-Event-ID, the way EventSource does. Connecting goes through the
+// usual request path, so redirects, signing and client certificates work
+// like for any request. An error status or a response that isn't an event
+// stream ends it for good.
+async fn run(
+    request: Request,
+    mut first: Option<reqwest::Response>,
+    settings: &RequestSettings,
+    cookies: Arc<CookieStoreMutex>,
+    passphrases: &HashMap<String, String>,
+    commands: &mut UnboundedReceiver<Command>,
+    events: &UnboundedSender<Event>,
+) {
+    let status = |text: String| {
+        let _ = events.send(Event::Status(text));
+    };
+    let mut parser = Parser::default();
+
+    'connect: loop {
+        let connected = match first.take() {
+            Some(response) => Ok(response),
+            None => {
+                let mut outgoing = request.clone();
+                outgoing
+                    .headers
+                    .retain(|(key, _)| !key.eq_ignore_ascii_case("last-event-id") && !key.eq_ignore_ascii_case("cache-control"));
+                outgoing.headers.push(("Cache-Control".to_string(), "no-cache".to_string()));
+                if let Some(id) = &parser.last_event_id {
+                    outgoing.headers.push(("Last-Event-ID".to_string(), id.clone()));
+                }
+                status(match &parser.last_event_id {
+                    Some(id) => format!("Connecting, resuming after {}...", id),
+                    None => "Connecting...".to_string(),
+                });
+                tokio::select! {
+                    reply = request::send_or_stream(outgoing, settings, cookies.clone(), passphrases, None) => match reply {
+                        Ok(Reply::EventStream(response)) => Ok(response),
+                        Ok(Reply::Complete(response)) if !(200..300).contains(&response.status) => {
+                            return status(format!("Stopped: the server answered {}", response.status));
+                        }
+                        Ok(Reply::Complete(response)) => {
+                            return status(format!(
+                                "Stopped: the response is {}, not text/event-stream",
+                                response.content_type().unwrap_or("untyped")
+                            ));
+                        }
+                        Err(err) => Err(request::describe_error(err.as_ref())),
+                    },
+                    command = commands.recv() => match command {
+                        Some(Command::Reconnect) => continue,
+                        Some(Command::Stop) | None => return status("Stopped".to_string()),
+                    },
+                }
+            }
+        };
+
+        let reason = match connected {
+            Ok(mut response) => {
+                status(format!("Connected ({})", response.status()));
+                let _ = events.send(Event::Open);
+                loop {
+                    tokio::select! {
+                        chunk = response.chunk() => match chunk {
+                            Ok(Some(chunk)) => {
+                                for frame in parser.feed(&chunk) {
+                                    let _ = events.send(Event::Frame(frame));
+                                }
+                            }
+                            Ok(None) => break "the server closed the stream".to_string(),
+                            Err(err) => break request::describe_error(&err),
+                        },
+                        command = commands.recv() => match command {
+                            Some(Command::Reconnect) => {
+                                parser.reset();
+                                continue 'connect;
+                            }
+                            Some(Command::Stop) | None => return status("Stopped".to_string()),
+                        },
+                    }
+                }
+            }
+            Err(reason) => reason,
+        };
+        parser.reset();
+
+        let wait = parser.reconnect_ms.unwrap_or(DEFAULT_RETRY_MS);
+        status(format!("Disconnected: {}. Reconnecting in {} ms (r: now, x: stop)", reason, wait));
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(wait)) => {}
+            command = commands.recv() => match command {
+                Some(Command::Reconnect) => {}
+                Some(Command::Stop) | None => return status("Stopped".to_string()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The frames from feeding `chunks` one after the other, without times.
+    fn frames(parser: &mut Parser, chunks: &[&[u8]]) -> Vec<(String, Option<String>, String, Option<u64>)> {
+        chunks
+            .iter()
+            .flat_map(|chunk| parser.feed(chunk))
+            .map(|frame| (frame.event, frame.id, frame.data, frame.retry))
+            .collect()
+    }
+
+    fn message(data: &str) -> (String, Option<String>, String, Option<u64>) {
+        ("message".to_string(), None, data.to_string(), None)
+    }
+
+    #[test]
+    fn a_leading_byte_order_mark_is_dropped() {
+        assert_eq!(frames(&mut Parser::default(), &[b"\xEF\xBB\xBFdata: first\n\n"]), vec![message("first")]);
+        // Split across chunks, and only at the very start.
+        let mut parser = Parser::default();
+        assert_eq!(frames(&mut parser, &[b"\xEF", b"\xBB", b"\xBFdata: a\n\n"]), vec![message("a")]);
+        assert_eq!(frames(&mut parser, &[b"\xEF\xBB\xBFdata: b\n\n"]), Vec::new());
+        // A new connection may start with one again.
+        parser.reset();
+        assert_eq!(frames(&mut parser, &[b"\xEF\xBB\xBFdata: c\n\n"]), vec![message("c")]);
+    }
+
+    #[test]
+    fn line_ends_may_be_split_across_chunks() {
+        let mut parser = Parser::default();
+        assert_eq!(frames(&mut parser, &[b"data: a\r", b"\n\r", b"\n"]), vec![message("a")]);
+        // Lone CRs end lines too, once the next byte shows no LF follows.
+        assert_eq!(frames(&mut parser, &[b"data: b\r\r"]), Vec::new());
+        assert_eq!(frames(&mut parser, &[b": next\n"]), vec![message("b")]);
+        assert_eq!(frames(&mut parser, &[b"da", b"ta: c", b"\n", b"\n"]), vec![message("c")]);
+    }
+
+    #[test]
+    fn multi_line_data_is_joined() {
+        let mut parser = Parser::default();
+        assert_eq!(
+            frames(&mut parser, &[b"data: one\ndata:two\ndata\ndata:  three\n\n"]),
+            vec![message("one\ntwo\n\n three")]
+        );
+    }
+
+    #[test]
+    fn comments_and_unknown_fields_are_skipped() {
+        let mut parser = Parser::default();
+        assert_eq!(frames(&mut parser, &[b": keep-alive\n\n"]), Vec::new());
+        assert_eq!(
+            frames(&mut parser, &[b":comment\nevent: tick\nfoo: bar\ndata: 1\n\n"]),
+            vec![("tick".to_string(), None, "1".to_string(), None)]
+        );
+        // Events without data aren't dispatched, and don't leak their type.
+        assert_eq!(frames(&mut parser, &[b"event: empty\n\ndata: 2\n\n"]), vec![message("2")]);
+    }
+
+    #[test]
+    fn ids_with_nul_are_ignored() {
+        let mut parser = Parser::default();
+        assert_eq!(
+            frames(&mut parser, &[b"id: 7\ndata: a\n\n"]),
+            vec![("message".to_string(), Some("7".to_string()), "a".to_string(), None)]
+        );
+        assert_eq!(frames(&mut parser, &[b"id: 8\0\ndata: b\n\n"]), vec![message("b")]);
+        assert_eq!(parser.last_event_id.as_deref(), Some("7"));
+        // An id sticks even without data.
+        assert_eq!(frames(&mut parser, &[b"id: 9\n\n"]), Vec::new());
+        assert_eq!(parser.last_event_id.as_deref(), Some("9"));
+    }
+
+    #[test]
+    fn retry_takes_only_digits() {
+        let mut parser = Parser::default();
+        assert_eq!(
+            frames(&mut parser, &[b"retry: 1500\ndata: a\n\n"]),
+            vec![("message".to_string(), None, "a".to_string(), Some(1500))]
+        );
+        assert_eq!(frames(&mut parser, &[b"retry: 2s\nretry:\nretry: -1\ndata: b\n\n"]), vec![message("b")]);
+        assert_eq!(parser.reconnect_ms, Some(1500));
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use tempfile::TempPath;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::UnboundedSender;
+
+pub const DEFAULT_MEMORY_CAP_KB: u64 = 8192;
+
+// Sent while the body of the final response is still arriving.
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub received: u64,
+    pub text: String,
+}
+
+// Collects a response body as it arrives. The first `cap` bytes are kept in
+// memory for display; once a body outgrows that, all of it goes to a temp
+// file instead so nothing is lost. The file is deleted once the last copy of
+// the response holding it is dropped.
+pub struct BodySink {
+    memory: Vec<u8>,
+    cap: usize,
+    received: u64,
+    shown: usize,
+    ndjson: bool,
+    spool: Option<(TempPath, File)>,
+}
+
+impl BodySink {
+    pub fn new(cap_kb: u64, content_type: Option<&str>) -> Self {
+        let content_type = content_type.unwrap_or_default().to_lowercase();
+        Self {
+            memory: Vec::new(),
+            cap: (cap_kb as usize).saturating_mul(1024),
+            received: 0,
+            shown: 0,
+            ndjson: ["ndjson", "jsonl", "json-seq"].iter().any(|kind| content_type.contains(kind)),
+            spool: None,
+        }
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    pub async fn push(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        self.received += chunk.len() as u64;
+        let room = self.cap.saturating_sub(self.memory.len());
+        if self.spool.is_none() && chunk.len() > room {
+            let (file, path) = tempfile::Builder::new()
+                .prefix("postsmith-")
+                .suffix(".body")
+                .tempfile()?
+                .into_parts();
+            let mut file = File::from_std(file);
+            file.write_all(&self.memory).await?;
+            self.spool = Some((path, file));
+        }
+        self.memory.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if let Some((_, file)) = &mut self.spool {
+            file.write_all(chunk).await?;
+        }
+        Ok(())
+    }
+
+    // Text that arrived since the last call. NDJSON only comes out in whole
+    // lines so every record shows up complete; anything else is cut at the
+    // last full UTF-8 character.
+    fn take_text(&mut self) -> String {
+        let pending = &self.memory[self.shown..];
+        let end = if self.ndjson {
+            pending.iter().rposition(|b| *b == b'\n').map(|i| i + 1).unwrap_or(0)
+        } else {
+            match std::str::from_utf8(pending) {
+                Ok(_) => pending.len(),
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => pending.len(),
+            }
+        };
+        let text = String::from_utf8_lossy(&pending[..end]).to_string();
+        self.shown += end;
+        text
+    }
+
+    pub fn report(&mut self, progress: Option<&UnboundedSender<Progress>>) {
+        if let Some(progress) = progress {
+            // Nobody listening any more just means nobody wants the preview.
+            let _ = progress.send(Progress {
+                received: self.received,
+                text: self.take_text(),
+            });
+        }
+    }
+
+    pub async fn finish(self) -> std::io::Result<(Vec<u8>, Option<Arc<TempPath>>)> {
+        let spooled = match self.spool {
+            Some((path, mut file)) => {
+                file.flush().await?;
+                Some(Arc::new(path))
+            }
+            None => None,
+        };
+        Ok((self.memory, spooled))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn spool_goes_away_with_the_response() {
+        let mut sink = BodySink::new(1, None);
+        sink.push(&[b'a'; 600]).await.unwrap();
+        sink.push(&[b'b'; 600]).await.unwrap();
+        let (body, spooled) = sink.finish().await.unwrap();
+        assert_eq!(body.len(), 1024);
+
+        let path = spooled.unwrap();
+        assert_eq!(std::fs::read(&*path).unwrap().len(), 1200);
+        let copy = path.clone();
+        let file = path.to_path_buf();
+        drop(path);
+        assert!(file.exists());
+        drop(copy);
+        assert!(!file.exists());
+    }
+}
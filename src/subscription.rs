@@ -0,0 +1,373 @@
+use std::sync::Arc;
+
+use futures_util::{SinkExt, StreamExt};
+use reqwest_cookie_store::CookieStoreMutex;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::request::describe_error;
+use crate::settings::RequestSettings;
+use crate::tls::ClientIdentity;
+use crate::websocket::{self, Connection, Direction, Entry, Event, Outgoing};
+
+pub const PROTOCOL: &str = "graphql-transport-ws";
+// Only one subscription runs per connection, so its id never changes.
+const ID: &str = "1";
+
+// GraphQL endpoints are usually given as http(s) URLs; subscriptions are
+// served on the same path over ws(s).
+pub fn websocket_url(url: &str) -> String {
+    let url = url.trim();
+    match url.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("https") => format!("wss://{}", rest),
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") => format!("ws://{}", rest),
+        _ => url.to_string(),
+    }
+}
+
+// Runs the subscription in `body` (a GraphQL request body) and logs every
+// message of the protocol, with `next` payloads pretty-printed. The log
+// and controls are the same as for a plain WebSocket.
+pub fn subscribe(
+    runtime: &tokio::runtime::Runtime,
+    url: String,
+    headers: Vec<(String, String)>,
+    settings: RequestSettings,
+    identity: Option<ClientIdentity>,
+    cookies: Arc<CookieStoreMutex>,
+    body: String,
+) -> Connection {
+    let (commands, mut receiver) = mpsc::unbounded_channel();
+    let (events, event_receiver) = mpsc::unbounded_channel();
+    runtime.spawn(async move {
+        if let Err(err) = run(&url, &headers, &settings, identity.as_ref(), &cookies, &body, &mut receiver, &events).await {
+            let _ = events.send(Event::Log(Entry::new(Direction::Info, "error", describe_error(err.as_ref()))));
+        }
+        let _ = events.send(Event::Closed);
+    });
+    Connection {
+        commands,
+        events: event_receiver,
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run(
+    url: &str,
+    headers: &[(String, String)],
+    settings: &RequestSettings,
+    identity: Option<&ClientIdentity>,
+    cookies: &CookieStoreMutex,
+    body: &str,
+    commands: &mut UnboundedReceiver<Outgoing>,
+    events: &UnboundedSender<Event>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let log = |entry: Entry| {
+        let _ = events.send(Event::Log(entry));
+    };
+    let payload: Value = serde_json::from_str(body)?;
+    let socket = websocket::handshake(&websocket_url(url), headers, settings, identity, cookies, Some(PROTOCOL), events).await?;
+    let (mut sink, mut stream) = socket.split();
+
+    sink.send(Message::Text(json!({ "type": "connection_init", "payload": {} }).to_string())).await?;
+    log(Entry::new(Direction::Sent, "init", String::new()));
+    // Whether the subscription is still running and needs a `complete` when
+    // we're the ones stopping it.
+    let mut running = false;
+    let mut closing = false;
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                let message = match command {
+                    Some(Outgoing::Text(text)) => Message::Text(text),
+                    Some(Outgoing::Binary(bytes)) => Message::Binary(bytes),
+                    Some(Outgoing::Ping) => Message::Text(json!({ "type": "ping" }).to_string()),
+                    // Already closing, nothing more to send.
+                    None if closing => break,
+                    Some(Outgoing::Close) if closing => continue,
+                    Some(Outgoing::Close) | None => {
+                        closing = true;
+                        if running {
+                            running = false;
+                            sink.send(Message::Text(json!({ "id": ID, "type": "complete" }).to_string())).await?;
+                            log(Entry::new(Direction::Sent, "complete", String::new()));
+                        }
+                        let close = websocket::close();
+                        log(Entry::of(Direction::Sent, &close));
+                        sink.send(close).await?;
+                        if command.is_none() {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                log(Entry::of(Direction::Sent, &message));
+                sink.send(message).await?;
+            }
+            incoming = stream.next() => {
+                let Some(message) = incoming else {
+                    break;
+                };
+                let message = message?;
+                let text = match &message {
+                    Message::Text(text) => text,
+                    _ => {
+                        log(Entry::of(Direction::Received, &message));
+                        if matches!(message, Message::Ping(_) | Message::Close(_)) {
+                            let _ = sink.flush().await;
+                        }
+                        continue;
+                    }
+                };
+                let Ok(frame) = serde_json::from_str::<Value>(text) else {
+                    log(Entry::of(Direction::Received, &message));
+                    continue;
+                };
+                match frame["type"].as_str().unwrap_or_default() {
+                    "connection_ack" => {
+                        log(Entry::new(Direction::Received, "ack", String::new()));
+                        sink.send(Message::Text(json!({ "id": ID, "type": "subscribe", "payload": payload }).to_string())).await?;
+                        log(Entry::new(Direction::Sent, "subscribe", payload["query"].as_str().unwrap_or_default().to_string()));
+                        running = true;
+                    }
+                    "next" => log(Entry::new(Direction::Received, "next", pretty(&frame["payload"]))),
+                    "error" => {
+                        running = false;
+                        log(Entry::new(Direction::Received, "error", pretty(&frame["payload"])));
+                        closing = true;
+                        let close = websocket::close();
+                        log(Entry::of(Direction::Sent, &close));
+                        sink.send(close).await?;
+                    }
+                    "complete" => {
+                        running = false;
+                        log(Entry::new(Direction::Received, "complete", "the server ended the subscription".to_string()));
+                        closing = true;
+                        let close = websocket::close();
+                        log(Entry::of(Direction::Sent, &close));
+                        sink.send(close).await?;
+                    }
+                    "ping" => {
+                        log(Entry::new(Direction::Received, "ping", String::new()));
+                        sink.send(Message::Text(json!({ "type": "pong" }).to_string())).await?;
+                        log(Entry::new(Direction::Sent, "pong", String::new()));
+                    }
+                    "pong" => log(Entry::new(Direction::Received, "pong", String::new())),
+                    _ => log(Entry::of(Direction::Received, &message)),
+                }
+            }
+        }
+    }
+    log(Entry::new(Direction::Info, "closed", "connection closed".to_string()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tokio_tungstenite::WebSocketStream;
+
+    type Server = WebSocketStream<TcpStream>;
+
+    const BODY: &str = r#"{"query": "subscription { ticks }"}"#;
+
+    // Accepts one connection, agreeing to the protocol only if the client
+    // asked for it, and hands it to `script`.
+    async fn serve<F, Fut>(script: F) -> String
+    where
+        F: FnOnce(Server) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = ()> + Send,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let socket = tokio_tungstenite::accept_hdr_async(stream, agree).await.unwrap();
+            script(socket).await;
+        });
+        url
+    }
+
+    // The error type is tungstenite's, however big clippy finds it.
+    #[allow(clippy::result_large_err)]
+    fn agree(request: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+        let asked = request.headers().get("sec-websocket-protocol").and_then(|value| value.to_str().ok());
+        assert_eq!(asked, Some(PROTOCOL));
+        response.headers_mut().insert("sec-websocket-protocol", PROTOCOL.parse().unwrap());
+        Ok(response)
+    }
+
+    // The next text frame as JSON; the close frame comes back as None.
+    async fn receive(socket: &mut Server) -> Option<Value> {
+        while let Some(message) = socket.next().await {
+            match message.unwrap() {
+                Message::Text(text) => return Some(serde_json::from_str(&text).unwrap()),
+                Message::Close(_) => return None,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    async fn send(socket: &mut Server, frame: Value) {
+        socket.send(Message::Text(frame.to_string())).await.unwrap();
+    }
+
+    // Up to `subscribe`, which is checked to carry the request body.
+    async fn start(socket: &mut Server) {
+        assert_eq!(receive(socket).await.unwrap()["type"], "connection_init");
+        send(socket, json!({ "type": "connection_ack" })).await;
+        let subscribe = receive(socket).await.unwrap();
+        assert_eq!(subscribe["type"], "subscribe");
+        assert_eq!(subscribe["id"], ID);
+        assert_eq!(subscribe["payload"]["query"], "subscription { ticks }");
+    }
+
+    // Drains the socket so the close handshake finishes.
+    async fn finish(mut socket: Server) {
+        while let Some(Ok(_)) = socket.next().await {}
+    }
+
+    // Runs the client until the connection is gone, sending `commands` once
+    // it's open, and returns the log as (direction, kind, text).
+    async fn client(url: &str, commands: Vec<Outgoing>) -> Vec<(Direction, &'static str, String)> {
+        let settings = RequestSettings {
+            proxy: Some("none".to_string()),
+            ..RequestSettings::default()
+        };
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        for command in commands {
+            sender.send(command).unwrap();
+        }
+        let (events, mut log) = mpsc::unbounded_channel();
+        let cookies = CookieStoreMutex::default();
+        run(url, &[], &settings, None, &cookies, BODY, &mut receiver, &events).await.unwrap();
+        let mut entries = Vec::new();
+        while let Ok(event) = log.try_recv() {
+            if let Event::Log(entry) = event {
+                entries.push((entry.direction, entry.kind, entry.text));
+            }
+        }
+        entries
+    }
+
+    fn kinds(entries: &[(Direction, &'static str, String)]) -> Vec<(Direction, &'static str)> {
+        entries.iter().map(|(direction, kind, _)| (*direction, *kind)).collect()
+    }
+
+    #[tokio::test]
+    async fn runs_until_the_server_completes() {
+        let url = serve(|mut socket| async move {
+            start(&mut socket).await;
+            send(&mut socket, json!({ "type": "ping" })).await;
+            assert_eq!(receive(&mut socket).await.unwrap(), json!({ "type": "pong" }));
+            send(&mut socket, json!({ "id": ID, "type": "next", "payload": { "data": { "ticks": 1 } } })).await;
+            send(&mut socket, json!({ "id": ID, "type": "next", "payload": { "data": { "ticks": 2 } } })).await;
+            send(&mut socket, json!({ "id": ID, "type": "complete" })).await;
+            assert_eq!(receive(&mut socket).await, None);
+            finish(socket).await;
+        })
+        .await;
+
+        let entries = client(&url, Vec::new()).await;
+        assert_eq!(
+            kinds(&entries),
+            vec![
+                (Direction::Info, "connect"),
+                (Direction::Info, "open"),
+                (Direction::Sent, "init"),
+                (Direction::Received, "ack"),
+                (Direction::Sent, "subscribe"),
+                (Direction::Received, "ping"),
+                (Direction::Sent, "pong"),
+                (Direction::Received, "next"),
+                (Direction::Received, "next"),
+                (Direction::Received, "complete"),
+                (Direction::Sent, "close"),
+                (Direction::Received, "close"),
+                (Direction::Info, "closed"),
+            ]
+        );
+        assert!(entries[1].2.contains(PROTOCOL));
+        assert_eq!(entries[7].2, pretty(&json!({ "data": { "ticks": 1 } })));
+    }
+
+    #[tokio::test]
+    async fn stops_on_an_error() {
+        let url = serve(|mut socket| async move {
+            start(&mut socket).await;
+            send(&mut socket, json!({ "id": ID, "type": "error", "payload": [{ "message": "no ticks today" }] })).await;
+            assert_eq!(receive(&mut socket).await, None);
+            finish(socket).await;
+        })
+        .await;
+
+        let entries = client(&url, Vec::new()).await;
+        let error = entries.iter().find(|(direction, kind, _)| *direction == Direction::Received && *kind == "error");
+        assert!(error.unwrap().2.contains("no ticks today"));
+        // A subscription the server failed needs no `complete`.
+        assert!(!entries.iter().any(|(_, kind, _)| *kind == "complete"));
+        assert_eq!(entries.last().unwrap().1, "closed");
+    }
+
+    #[tokio::test]
+    async fn pings_and_completes_when_stopped() {
+        let url = serve(|mut socket| async move {
+            assert_eq!(receive(&mut socket).await.unwrap()["type"], "connection_init");
+            // The commands are already waiting, the ping goes out first.
+            assert_eq!(receive(&mut socket).await.unwrap(), json!({ "type": "ping" }));
+            send(&mut socket, json!({ "type": "pong" })).await;
+            send(&mut socket, json!({ "type": "connection_ack" })).await;
+            assert_eq!(receive(&mut socket).await.unwrap()["type"], "subscribe");
+            send(&mut socket, json!({ "id": ID, "type": "next", "payload": { "data": { "ticks": 1 } } })).await;
+            assert_eq!(receive(&mut socket).await.unwrap(), json!({ "id": ID, "type": "complete" }));
+            assert_eq!(receive(&mut socket).await, None);
+            finish(socket).await;
+        })
+        .await;
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let (events, mut log) = mpsc::unbounded_channel();
+        let settings = RequestSettings {
+            proxy: Some("none".to_string()),
+            ..RequestSettings::default()
+        };
+        sender.send(Outgoing::Ping).unwrap();
+        let client = tokio::spawn(async move {
+            let cookies = CookieStoreMutex::default();
+            run(&url, &[], &settings, None, &cookies, BODY, &mut receiver, &events).await.unwrap();
+        });
+        // Stop once the first result is in.
+        let mut entries = Vec::new();
+        while let Some(event) = log.recv().await {
+            if let Event::Log(entry) = event {
+                if entry.kind == "next" {
+                    sender.send(Outgoing::Close).unwrap();
+                }
+                entries.push((entry.direction, entry.kind, entry.text));
+            }
+        }
+        client.await.unwrap();
+
+        let kinds = kinds(&entries);
+        assert!(kinds.contains(&(Direction::Received, "pong")));
+        let next = kinds.iter().position(|kind| *kind == (Direction::Received, "next")).unwrap();
+        assert_eq!(
+            kinds[next + 1..],
+            [
+                (Direction::Sent, "complete"),
+                (Direction::Sent, "close"),
+                (Direction::Received, "close"),
+                (Direction::Info, "closed"),
+            ]
+        );
+    }
+}
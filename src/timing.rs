@@ -0,0 +1,612 @@
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{ClientBuilder, Proxy};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tower::{Layer, Service};
+
+use crate::proxy::{self, Route};
+use crate::request::describe_error;
+use crate::resolve;
+use crate::settings::RequestSettings;
+
+const BAR_WIDTH: usize = 40;
+
+// Where the time of one request went. Phases that didn't happen (DNS for an
+// IP literal or a pinned host, TLS for plain http, everything for a reused
+// connection) are None.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Timings {
+    pub dns: Option<Duration>,
+    pub connect: Option<Duration>,
+    pub tls: Option<Duration>,
+    // The handshake happened but is part of `connect`, as it couldn't be
+    // timed on its own (through an https:// proxy).
+    #[serde(default)]
+    pub tls_in_connect: bool,
+    pub ttfb: Duration,
+    pub download: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.ttfb + self.download
+    }
+
+    // Time the server spent before answering: the time to first byte minus
+    // everything it took to get connected.
+    fn waiting(&self) -> Duration {
+        let setup = self.dns.unwrap_or_default() + self.connect.unwrap_or_default() + self.tls.unwrap_or_default();
+        self.ttfb.saturating_sub(setup)
+    }
+
+    pub fn summary(&self) -> String {
+        let phase = |name: &str, duration: Option<Duration>| duration.map(|d| format!("{} {}", name, millis(d)));
+        [
+            phase("dns", self.dns),
+            phase(if self.tls_in_connect { "connect+tls" } else { "connect" }, self.connect),
+            phase("tls", self.tls),
+            phase("ttfb", Some(self.ttfb)),
+            phase("download", Some(self.download)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" / ")
+    }
+
+    pub fn waterfall(&self) -> String {
+        let phases = [
+            ("DNS lookup", self.dns),
+            (if self.tls_in_connect { "TCP + TLS" } else { "TCP connect" }, self.connect),
+            ("TLS handshake", self.tls),
+            ("Waiting", Some(self.waiting())),
+            ("Download", Some(self.download)),
+        ];
+        let total = self.total().max(phases.iter().filter_map(|(_, d)| *d).sum()).as_secs_f64();
+
+        let mut lines = String::new();
+        let mut start = 0.0;
+        for (name, duration) in phases {
+            let Some(duration) = duration else {
+                let note = if name == "TLS handshake" && self.tls_in_connect {
+                    "n/a (timed with the TCP connect)"
+                } else {
+                    "-"
+                };
+                lines.push_str(&format!("  {:<14} {:>10}\n", name, note));
+                continue;
+            };
+            let length = duration.as_secs_f64();
+            let (offset, width) = if total > 0.0 {
+                let offset = (start / total * BAR_WIDTH as f64).round() as usize;
+                let end = ((start + length) / total * BAR_WIDTH as f64).round() as usize;
+                (offset.min(BAR_WIDTH - 1), end.saturating_sub(offset).max(1))
+            } else {
+                (0, 1)
+            };
+            lines.push_str(&format!(
+                "  {:<14} {:>10} |{}{}{}|\n",
+                name,
+                millis(duration),
+                " ".repeat(offset),
+                "█".repeat(width.min(BAR_WIDTH - offset)),
+                " ".repeat(BAR_WIDTH - offset - width.min(BAR_WIDTH - offset))
+            ));
+            start += length;
+        }
+        lines.push_str(&format!(
+            "  {:<14} {:>10}\n  {:<14} {:>10}\n",
+            "First byte",
+            millis(self.ttfb),
+            "Total",
+            millis(self.total())
+        ));
+        lines
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+// Filled in while a client opens a connection, so the phases are those of
+// the connection the request really went over. Nothing is recorded for a
+// request sent on a pooled connection.
+//
+// reqwest does the TCP connect and the TLS handshake in one step, so for
+// https the connection is opened here instead: the client is pointed at a
+// CONNECT tunnel on the loopback interface that connects to the host (or
+// through the proxy it routes to) and times that, and reqwest does the
+// handshake through it. Plain http is timed with a resolver and a layer
+// around reqwest's connector.
+#[derive(Clone, Default)]
+pub struct Recorder {
+    recorded: Arc<Mutex<Recorded>>,
+    // Open for as long as a client built with it may connect.
+    tunnel: Option<Arc<Tunnel>>,
+}
+
+#[derive(Default)]
+struct Recorded {
+    dns: Option<Duration>,
+    // Set by the tunnel: the TCP connect to the host, or getting a tunnel
+    // through the proxy, and when the connection was handed to reqwest.
+    connect: Option<Duration>,
+    tunneled: Option<Instant>,
+    // From asking for a connection until it was ready, and when that was.
+    setup: Option<Duration>,
+    ready: Option<Instant>,
+    // Why the tunnel couldn't connect; reqwest only sees a refused CONNECT.
+    failure: Option<String>,
+}
+
+impl Recorder {
+    pub fn apply(
+        &mut self,
+        builder: ClientBuilder,
+        settings: &RequestSettings,
+        url: &str,
+    ) -> Result<ClientBuilder, Box<dyn std::error::Error>> {
+        self.tunnel = None;
+        let mut builder = builder;
+        if url.get(..8).is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://")) && tunnels(settings, url) {
+            let tunnel = Tunnel::open(settings.clone(), self.recorded.clone())?;
+            // Other proxies were already taken into account by `tunnels`.
+            builder = builder
+                .no_proxy()
+                .proxy(Proxy::https(format!("http://127.0.0.1:{}", tunnel.port))?.basic_auth(TUNNEL_USER, &tunnel.token));
+            self.tunnel = Some(Arc::new(tunnel));
+        }
+        Ok(builder.dns_resolver(Arc::new(self.clone())).connector_layer(self.clone()))
+    }
+
+    // Takes what was recorded for the last connection.
+    pub fn take(&self, url: &str, timings: &mut Timings) {
+        let recorded = std::mem::take(&mut *self.recorded.lock().unwrap());
+        match (recorded.tunneled, recorded.ready) {
+            (Some(tunneled), Some(ready)) => {
+                timings.dns = recorded.dns;
+                timings.connect = recorded.connect;
+                timings.tls = Some(ready.saturating_duration_since(tunneled));
+                timings.tls_in_connect = false;
+            }
+            _ => {
+                timings.dns = recorded.dns;
+                timings.connect = recorded.setup.map(|setup| setup.saturating_sub(recorded.dns.unwrap_or_default()));
+                timings.tls = None;
+                timings.tls_in_connect = timings.connect.is_some() && url.starts_with("https://");
+            }
+        }
+    }
+
+    // Why the last connection couldn't be made, when the tunnel knows
+    // better than reqwest.
+    pub fn failure(&self) -> Option<String> {
+        self.recorded.lock().unwrap().failure.take()
+    }
+}
+
+const TUNNEL_USER: &str = "postsmith";
+
+// Whether the tunnel can open the connection for `url` itself. It speaks
+// to http and SOCKS5 proxies like the WebSocket connections do; with any
+// other proxy reqwest connects and the handshake is timed with the connect.
+fn tunnels(settings: &RequestSettings, url: &str) -> bool {
+    match proxy::route(settings, url) {
+        Route::Direct(_) => true,
+        Route::Proxy(proxy, _) => reqwest::Url::parse(&proxy)
+            .is_ok_and(|proxy| matches!(proxy.scheme(), "http" | "socks5" | "socks5h")),
+    }
+}
+
+// A CONNECT proxy on the loopback interface for one client. It only lets
+// in requests carrying its token, so other local users can't use it.
+struct Tunnel {
+    port: u16,
+    token: String,
+    task: JoinHandle<()>,
+}
+
+impl Tunnel {
+    fn open(settings: RequestSettings, recorded: Arc<Mutex<Recorded>>) -> Result<Tunnel, Box<dyn std::error::Error>> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let listener = TcpListener::from_std(listener)?;
+        let port = listener.local_addr()?.port();
+        let mut token = [0; 16];
+        openssl::rand::rand_bytes(&mut token)?;
+        let token = hex::encode(token);
+        let authorization = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", TUNNEL_USER, token))
+        );
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (settings, recorded, authorization) = (settings.clone(), recorded.clone(), authorization.clone());
+                tokio::spawn(async move {
+                    let _ = relay(stream, &authorization, &settings, &recorded).await;
+                });
+            }
+        });
+        Ok(Tunnel { port, token, task })
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// Answers one CONNECT from reqwest and passes bytes both ways once the
+// connection is open.
+async fn relay(
+    mut client: TcpStream,
+    authorization: &str,
+    settings: &RequestSettings,
+    recorded: &Mutex<Recorded>,
+) -> std::io::Result<()> {
+    // reqwest waits for the answer before it starts the handshake, so
+    // nothing past the head arrives yet.
+    let mut head = Vec::new();
+    let mut buffer = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = client.read(&mut buffer).await?;
+        if n == 0 || head.len() > 16 * 1024 {
+            return Ok(());
+        }
+        head.extend_from_slice(&buffer[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let authorized = head.lines().skip(1).any(|line| {
+        line.split_once(':').is_some_and(|(key, value)| {
+            key.trim().eq_ignore_ascii_case("proxy-authorization") && value.trim() == authorization
+        })
+    });
+    if !authorized {
+        return client.write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").await;
+    }
+    let target = head
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("CONNECT "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|authority| authority.rsplit_once(':'))
+        .and_then(|(host, port)| Some((host.trim_matches(['[', ']']).to_string(), port.parse::<u16>().ok()?)));
+    let Some((host, port)) = target else {
+        return client.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+    };
+
+    match open(settings, &host, port, recorded).await {
+        Ok(mut upstream) => {
+            client.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await?;
+            recorded.lock().unwrap().tunneled = Some(Instant::now());
+            tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+            Ok(())
+        }
+        Err(err) => {
+            recorded.lock().unwrap().failure = Some(err);
+            client.write_all(b"HTTP/1.1 502 Bad Gateway\r\n\r\n").await
+        }
+    }
+}
+
+// The connection reqwest would have made to `host:port`, with the lookup
+// and the connect timed. Through a proxy, getting the tunnel counts as the
+// connect.
+async fn open(settings: &RequestSettings, host: &str, port: u16, recorded: &Mutex<Recorded>) -> Result<TcpStream, String> {
+    let url = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(_)) => format!("https://[{}]:{}/", host, port),
+        _ => format!("https://{}:{}/", host, port),
+    };
+    if let Route::Proxy(..) = proxy::route(settings, &url) {
+        let started = Instant::now();
+        let stream = proxy::connect(settings, &url, host, port).await.map_err(|err| describe_error(&*err))?;
+        recorded.lock().unwrap().connect = Some(started.elapsed());
+        return Ok(stream);
+    }
+
+    let pinned = settings.resolve.as_deref().and_then(|overrides| resolve::lookup(overrides, host));
+    let addresses: Vec<SocketAddr> = match (pinned, host.parse::<IpAddr>()) {
+        (Some(entry), _) => vec![SocketAddr::new(entry.address, port)],
+        (None, Ok(address)) => vec![SocketAddr::new(address, port)],
+        (None, Err(_)) => {
+            let started = Instant::now();
+            let addresses = tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|err| format!("could not resolve {}: {}", host, err))?
+                .collect();
+            recorded.lock().unwrap().dns = Some(started.elapsed());
+            addresses
+        }
+    };
+    let timeout = settings.connect_timeout_ms.map(Duration::from_millis).unwrap_or(proxy::CONNECT_TIMEOUT);
+    let started = Instant::now();
+    let stream = tokio::time::timeout(timeout, TcpStream::connect(addresses.as_slice()))
+        .await
+        .map_err(|_| format!("connecting to {}:{} timed out", host, port))?
+        .map_err(|err| format!("could not connect to {}:{}: {}", host, port, err))?;
+    recorded.lock().unwrap().connect = Some(started.elapsed());
+    let _ = stream.set_nodelay(true);
+    Ok(stream)
+}
+
+// Overrides from the resolve setting never get here, reqwest answers those
+// itself, so `dns` stays None for them like for IP literals.
+impl Resolve for Recorder {
+    fn resolve(&self, name: Name) -> Resolving {
+        let recorder = self.clone();
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let started = Instant::now();
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            recorder.recorded.lock().unwrap().dns = Some(started.elapsed());
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+impl<S> Layer<S> for Recorder {
+    type Service = TimedConnect<S>;
+
+    fn layer(&self, inner: S) -> TimedConnect<S> {
+        TimedConnect {
+            inner,
+            recorder: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TimedConnect<S> {
+    inner: S,
+    recorder: Recorder,
+}
+
+impl<S, R> Service<R> for TimedConnect<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let started = Instant::now();
+        let recorder = self.recorder.clone();
+        let connecting = self.inner.call(request);
+        Box::pin(async move {
+            let connection = connecting.await?;
+            let mut recorded = recorder.recorded.lock().unwrap();
+            recorded.setup = Some(started.elapsed());
+            recorded.ready = Some(Instant::now());
+            drop(recorded);
+            Ok(connection)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use tokio::io::{AsyncRead, AsyncWrite};
+
+    fn direct() -> RequestSettings {
+        RequestSettings {
+            proxy: Some("none".to_string()),
+            ..RequestSettings::default()
+        }
+    }
+
+    // Answers every request on a connection with a small keep-alive reply.
+    async fn answer<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S) {
+        let mut buffer = [0; 4096];
+        while matches!(stream.read(&mut buffer).await, Ok(n) if n > 0) {
+            if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").await.is_err() {
+                break;
+            }
+        }
+    }
+
+    async fn server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(answer(stream));
+            }
+        });
+        port
+    }
+
+    // The same over TLS, with a self-signed certificate.
+    async fn tls_server() -> u16 {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "localhost").unwrap();
+        let name = name.build();
+        let mut certificate = X509Builder::new().unwrap();
+        certificate.set_version(2).unwrap();
+        certificate.set_subject_name(&name).unwrap();
+        certificate.set_issuer_name(&name).unwrap();
+        certificate.set_pubkey(&key).unwrap();
+        certificate.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        certificate.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        certificate.sign(&key, MessageDigest::sha256()).unwrap();
+        let identity = native_tls::Identity::from_pkcs8(
+            &certificate.build().to_pem().unwrap(),
+            &key.private_key_to_pem_pkcs8().unwrap(),
+        )
+        .unwrap();
+        let acceptor = tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(stream) = acceptor.accept(stream).await {
+                        answer(stream).await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    // An http proxy that tunnels CONNECT requests and counts them.
+    async fn connect_proxy() -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let log = log.clone();
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut byte = [0; 1];
+                    while !head.ends_with(b"\r\n\r\n") {
+                        stream.read_exact(&mut byte).await.unwrap();
+                        head.push(byte[0]);
+                    }
+                    let target = String::from_utf8_lossy(&head).split(' ').nth(1).unwrap().to_string();
+                    log.lock().unwrap().push(target.clone());
+                    let mut upstream = TcpStream::connect(target).await.unwrap();
+                    stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+                });
+            }
+        });
+        (port, seen)
+    }
+
+    async fn get(recorder: &mut Recorder, settings: &RequestSettings, url: &str) -> Timings {
+        let builder = reqwest::Client::builder().danger_accept_invalid_certs(true);
+        let client = recorder.apply(builder, settings, url).unwrap().build().unwrap();
+        client.get(url).send().await.unwrap().bytes().await.unwrap();
+        let mut timings = Timings::default();
+        recorder.take(url, &mut timings);
+        // The pooled connection is reused, there's nothing to time.
+        client.get(url).send().await.unwrap().bytes().await.unwrap();
+        let mut reused = Timings::default();
+        recorder.take(url, &mut reused);
+        assert_eq!((reused.dns, reused.connect, reused.tls), (None, None, None));
+        timings
+    }
+
+    #[tokio::test]
+    async fn records_the_connection_of_the_request() {
+        let port = server().await;
+        let mut recorder = Recorder::default();
+
+        let timings = get(&mut recorder, &direct(), &format!("http://localhost:{}/", port)).await;
+        assert!(timings.dns.is_some());
+        assert!(timings.connect.is_some());
+        assert_eq!(timings.tls, None);
+        assert!(!timings.tls_in_connect);
+
+        // No lookup for an IP literal.
+        let timings = get(&mut recorder, &direct(), &format!("http://127.0.0.1:{}/", port)).await;
+        assert_eq!(timings.dns, None);
+        assert!(timings.connect.is_some());
+    }
+
+    #[tokio::test]
+    async fn times_the_tls_handshake_on_its_own() {
+        let port = tls_server().await;
+        let mut recorder = Recorder::default();
+
+        let timings = get(&mut recorder, &direct(), &format!("https://localhost:{}/", port)).await;
+        assert!(timings.dns.is_some());
+        assert!(timings.connect.is_some());
+        assert!(timings.tls.is_some());
+        assert!(!timings.tls_in_connect);
+        assert!(timings.summary().contains(" / tls "));
+
+        // A pinned address skips the lookup.
+        let settings = RequestSettings {
+            resolve: resolve::parse_overrides("example.test=127.0.0.1").unwrap(),
+            ..direct()
+        };
+        let timings = get(&mut recorder, &settings, &format!("https://example.test:{}/", port)).await;
+        assert_eq!(timings.dns, None);
+        assert!(timings.connect.is_some() && timings.tls.is_some());
+    }
+
+    #[tokio::test]
+    async fn times_https_through_a_proxy() {
+        let port = tls_server().await;
+        let (proxy_port, seen) = connect_proxy().await;
+        let settings = RequestSettings {
+            proxy: Some(format!("http://127.0.0.1:{}", proxy_port)),
+            ..RequestSettings::default()
+        };
+        let mut recorder = Recorder::default();
+
+        let timings = get(&mut recorder, &settings, &format!("https://127.0.0.1:{}/", port)).await;
+        assert_eq!(*seen.lock().unwrap(), vec![format!("127.0.0.1:{}", port)]);
+        assert_eq!(timings.dns, None);
+        assert!(timings.connect.is_some());
+        assert!(timings.tls.is_some());
+    }
+
+    #[tokio::test]
+    async fn reports_why_the_tunnel_failed() {
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("https://127.0.0.1:{}/", closed);
+        let mut recorder = Recorder::default();
+        let client = recorder.apply(reqwest::Client::builder(), &direct(), &url).unwrap().build().unwrap();
+        assert!(client.get(&url).send().await.is_err());
+        let failure = recorder.failure().unwrap();
+        assert!(failure.starts_with(&format!("could not connect to 127.0.0.1:{}", closed)), "{}", failure);
+    }
+
+    #[tokio::test]
+    async fn tunnel_turns_away_requests_without_the_token() {
+        let mut recorder = Recorder::default();
+        let _client = recorder.apply(reqwest::Client::builder(), &direct(), "https://localhost/").unwrap().build().unwrap();
+        let port = recorder.tunnel.as_ref().unwrap().port;
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream.write_all(b"CONNECT localhost:443 HTTP/1.1\r\nHost: localhost:443\r\n\r\n").await.unwrap();
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer).await.unwrap();
+        assert!(answer.starts_with("HTTP/1.1 407"));
+    }
+
+    #[test]
+    fn waterfall_marks_tls_as_unavailable() {
+        let timings = Timings {
+            connect: Some(Duration::from_millis(30)),
+            tls_in_connect: true,
+            ttfb: Duration::from_millis(50),
+            ..Timings::default()
+        };
+        let waterfall = timings.waterfall();
+        assert!(waterfall.contains("TCP + TLS"));
+        assert!(waterfall.contains("n/a"));
+        assert!(timings.summary().contains("connect+tls 30"));
+    }
+}
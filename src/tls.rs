@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use std::fs;
+
+use reqwest::{Certificate, Identity};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::RequestSettings;
+
+// A client certificate used only for requests to `host`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HostCert {
+    pub host: String,
+    pub cert: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub key: String,
+}
+
+// Certificate (and key, for PEM) picked for a request: a per-host mapping
+// wins over the default client certificate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientCert {
+    pub cert: String,
+    pub key: String,
+}
+
+impl ClientCert {
+    pub fn for_host(settings: &RequestSettings, host: &str) -> Option<ClientCert> {
+        let mapped = settings
+            .host_certs
+            .iter()
+            .flatten()
+            .find(|mapping| mapping.host.eq_ignore_ascii_case(host));
+        if let Some(mapping) = mapped {
+            return Some(ClientCert {
+                cert: mapping.cert.clone(),
+                key: mapping.key.clone(),
+            });
+        }
+        settings.client_cert.as_ref().map(|cert| ClientCert {
+            cert: cert.clone(),
+            key: settings.client_key.clone().unwrap_or_default(),
+        })
+    }
+
+    pub fn is_pkcs12(&self) -> bool {
+        let cert = self.cert.to_lowercase();
+        cert.ends_with(".p12") || cert.ends_with(".pfx")
+    }
+
+    // The file a passphrase unlocks: the PKCS#12 bundle, or the PEM key.
+    pub fn secret_file(&self) -> &str {
+        if self.is_pkcs12() {
+            &self.cert
+        } else {
+            &self.key
+        }
+    }
+
+    // PKCS#12 files always need a passphrase, even if it is empty; PEM keys
+    // only when they are encrypted. A key that can't be read is left for
+    // `load` to report.
+    pub fn needs_passphrase(&self) -> bool {
+        if self.is_pkcs12() {
+            return true;
+        }
+        !self.key.is_empty() && fs::read(&self.key).is_ok_and(|key| is_encrypted(&key))
+    }
+
+    // `passphrases` is keyed by `secret_file`.
+    pub fn load(&self, passphrases: &HashMap<String, String>) -> Result<ClientIdentity, Box<dyn std::error::Error>> {
+        let cert = fs::read(&self.cert).map_err(|e| format!("could not read client certificate {}: {}", self.cert, e))?;
+        let failed = |e: &dyn std::error::Error| format!("could not load client certificate {}: {}", self.cert, e);
+        if self.is_pkcs12() {
+            let passphrase = passphrases
+                .get(&self.cert)
+                .ok_or_else(|| format!("no passphrase for {}", self.cert))?;
+            return Ok(ClientIdentity {
+                reqwest: Identity::from_pkcs12_der(&cert, passphrase).map_err(|e| failed(&e))?,
+                native: native_tls::Identity::from_pkcs12(&cert, passphrase).map_err(|e| failed(&e))?,
+            });
+        }
+        if self.key.is_empty() {
+            return Err(format!("client certificate {} needs a client_key", self.cert).into());
+        }
+        let key = fs::read(&self.key).map_err(|e| format!("could not read client key {}: {}", self.key, e))?;
+        let passphrase = match is_encrypted(&key) {
+            true => Some(
+                passphrases
+                    .get(&self.key)
+                    .ok_or_else(|| format!("no passphrase for {}", self.key))?,
+            ),
+            false => None,
+        };
+        let key = pkcs8_key(&key, passphrase.map(String::as_str)).map_err(|e| format!("could not read client key {}: {}", self.key, e))?;
+        Ok(ClientIdentity {
+            reqwest: Identity::from_pkcs8_pem(&cert, &key).map_err(|e| failed(&e))?,
+            native: native_tls::Identity::from_pkcs8(&cert, &key).map_err(|e| failed(&e))?,
+        })
+    }
+}
+
+// A client certificate read from disk once, in the forms reqwest and the
+// connections made with native-tls (WebSocket, gRPC, raw) each take.
+#[derive(Clone)]
+pub struct ClientIdentity {
+    pub reqwest: Identity,
+    pub native: native_tls::Identity,
+}
+
+// The certificate picked for `host`, if there is one.
+pub fn client_identity(
+    settings: &RequestSettings,
+    host: &str,
+    passphrases: &HashMap<String, String>,
+) -> Result<Option<ClientIdentity>, Box<dyn std::error::Error>> {
+    ClientCert::for_host(settings, host)
+        .map(|client_cert| client_cert.load(passphrases))
+        .transpose()
+}
+
+// Both legacy encrypted PEM (`Proc-Type: 4,ENCRYPTED`) and encrypted PKCS#8
+// (`BEGIN ENCRYPTED PRIVATE KEY`).
+fn is_encrypted(pem: &[u8]) -> bool {
+    String::from_utf8_lossy(pem).contains("ENCRYPTED")
+}
+
+// Both native-tls and reqwest only take unencrypted PKCS#8 keys, while
+// `openssl genrsa` and many CAs still hand out PKCS#1 (`BEGIN RSA PRIVATE
+// KEY`) or SEC1 (`BEGIN EC PRIVATE KEY`) ones, often encrypted.
+fn pkcs8_key(pem: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>, String> {
+    let text = String::from_utf8_lossy(pem);
+    let key = match passphrase {
+        Some(passphrase) => openssl::pkey::PKey::private_key_from_pem_passphrase(pem, passphrase.as_bytes())
+            .map_err(|_| "wrong passphrase or unreadable key".to_string())?,
+        None if text.contains("BEGIN RSA PRIVATE KEY") || text.contains("BEGIN EC PRIVATE KEY") => {
+            openssl::pkey::PKey::private_key_from_pem(pem).map_err(|e| e.to_string())?
+        }
+        None => return Ok(pem.to_vec()),
+    };
+    key.private_key_to_pem_pkcs8().map_err(|e| e.to_string())
+}
+
+pub fn load_ca_bundle(path: &str) -> Result<Vec<Certificate>, Box<dyn std::error::Error>> {
+    let pem = fs::read(path).map_err(|e| format!("could not read CA bundle {}: {}", path, e))?;
+    Certificate::from_pem_bundle(&pem).map_err(|e| format!("could not parse CA bundle {}: {}", path, e).into())
+}
+
+// For connections made without reqwest (WebSocket, gRPC, raw): the same TLS
+// settings as plain requests, with the client certificate loaded for the
+// host being connected to.
+pub fn native_tls_builder(
+    settings: &RequestSettings,
+    identity: Option<&ClientIdentity>,
+) -> Result<native_tls::TlsConnectorBuilder, Box<dyn std::error::Error>> {
+    let mut builder = native_tls::TlsConnector::builder();
+    if let Some(identity) = identity {
+        builder.identity(identity.native.clone());
+    }
+    if settings.verify_tls == Some(false) {
+        builder.danger_accept_invalid_certs(true);
+    }
+    if let Some(path) = &settings.ca_bundle {
+        let bundle = fs::read_to_string(path).map_err(|e| format!("could not read CA bundle {}: {}", path, e))?;
+        for pem in bundle.split_inclusive("-----END CERTIFICATE-----").filter(|pem| pem.contains("BEGIN")) {
+            builder.add_root_certificate(native_tls::Certificate::from_pem(pem.trim().as_bytes())?);
+        }
+    }
+    Ok(builder)
+}
+
+// `host=cert|key, host2=cert2` as shown and edited in the settings view.
+pub fn format_host_certs(host_certs: &[HostCert]) -> String {
+    host_certs
+        .iter()
+        .map(|mapping| {
+            if mapping.key.is_empty() {
+                format!("{}={}", mapping.host, mapping.cert)
+            } else {
+                format!("{}={}|{}", mapping.host, mapping.cert, mapping.key)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn parse_host_certs(value: &str) -> Result<Option<Vec<HostCert>>, String> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    value
+        .split(',')
+        .map(|mapping| {
+            let (host, paths) = mapping
+                .split_once('=')
+                .ok_or_else(|| "expected host=cert|key, ...".to_string())?;
+            let (cert, key) = paths.split_once('|').unwrap_or((paths, ""));
+            Ok(HostCert {
+                host: host.trim().to_string(),
+                cert: cert.trim().to_string(),
+                key: key.trim().to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::{BigNum, MsbOption};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::rsa::Rsa;
+    use openssl::ssl::{SslAcceptor, SslMethod, SslVerifyMode};
+    use openssl::x509::extension::{BasicConstraints, KeyUsage, SubjectAlternativeName};
+    use openssl::x509::{X509Builder, X509NameBuilder, X509};
+    use reqwest_cookie_store::CookieStoreMutex;
+
+    use crate::raw;
+    use crate::request::build_client;
+
+    fn certificate(cn: &str, key: &PKey<Private>, issuer: Option<(&X509, &PKey<Private>)>) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+        let name = name.build();
+        let mut serial = BigNum::new().unwrap();
+        serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_serial_number(&serial.to_asn1_integer().unwrap()).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        match issuer {
+            None => {
+                builder.set_issuer_name(&name).unwrap();
+                builder.append_extension(BasicConstraints::new().critical().ca().build().unwrap()).unwrap();
+                builder
+                    .append_extension(KeyUsage::new().critical().key_cert_sign().crl_sign().build().unwrap())
+                    .unwrap();
+                builder.sign(key, MessageDigest::sha256()).unwrap();
+            }
+            Some((ca, ca_key)) => {
+                builder.set_issuer_name(ca.subject_name()).unwrap();
+                let san = SubjectAlternativeName::new()
+                    .dns("localhost")
+                    .build(&builder.x509v3_context(Some(ca), None))
+                    .unwrap();
+                builder.append_extension(san).unwrap();
+                builder.sign(ca_key, MessageDigest::sha256()).unwrap();
+            }
+        }
+        builder.build()
+    }
+
+    fn rsa_key() -> (Rsa<Private>, PKey<Private>) {
+        let rsa = Rsa::generate(2048).unwrap();
+        (rsa.clone(), PKey::from_rsa(rsa).unwrap())
+    }
+
+    struct Pki {
+        dir: PathBuf,
+        ca: X509,
+        server: (X509, PKey<Private>),
+    }
+
+    impl Pki {
+        // A CA with a server and a client certificate under it. The client
+        // key is written as PKCS#1, the way `openssl genrsa` writes it.
+        fn new(name: &str) -> Pki {
+            let dir = std::env::temp_dir().join(format!("postsmith-tls-{}-{}", std::process::id(), name));
+            fs::create_dir_all(&dir).unwrap();
+            let (_, ca_key) = rsa_key();
+            let ca = certificate("postsmith test CA", &ca_key, None);
+            let (_, server_key) = rsa_key();
+            let server = certificate("localhost", &server_key, Some((&ca, &ca_key)));
+            let (client_rsa, client_key) = rsa_key();
+            let client = certificate("postsmith client", &client_key, Some((&ca, &ca_key)));
+
+            fs::write(dir.join("ca.pem"), ca.to_pem().unwrap()).unwrap();
+            fs::write(dir.join("client.pem"), client.to_pem().unwrap()).unwrap();
+            fs::write(dir.join("client.key"), client_rsa.private_key_to_pem().unwrap()).unwrap();
+            fs::write(
+                dir.join("client-encrypted.key"),
+                client_rsa
+                    .private_key_to_pem_passphrase(openssl::symm::Cipher::aes_128_cbc(), b"secret")
+                    .unwrap(),
+            )
+            .unwrap();
+            fs::write(
+                dir.join("client-encrypted-pkcs8.key"),
+                client_key
+                    .private_key_to_pem_pkcs8_passphrase(openssl::symm::Cipher::aes_256_cbc(), b"secret")
+                    .unwrap(),
+            )
+            .unwrap();
+            let pkcs12 = openssl::pkcs12::Pkcs12::builder()
+                .name("postsmith client")
+                .pkey(&client_key)
+                .cert(&client)
+                .build2("secret")
+                .unwrap();
+            fs::write(dir.join("client.p12"), pkcs12.to_der().unwrap()).unwrap();
+            Pki {
+                dir,
+                ca,
+                server: (server, server_key),
+            }
+        }
+
+        fn path(&self, file: &str) -> String {
+            self.dir.join(file).to_string_lossy().to_string()
+        }
+
+        fn settings(&self, client_cert: Option<(&str, &str)>) -> RequestSettings {
+            RequestSettings {
+                ca_bundle: Some(self.path("ca.pem")),
+                client_cert: client_cert.map(|(cert, _)| self.path(cert)),
+                client_key: client_cert.map(|(_, key)| self.path(key)),
+                proxy: Some("none".to_string()),
+                ..Default::default()
+            }
+        }
+
+        // An HTTPS server that insists on a client certificate from the CA
+        // and answers with the certificate's common name.
+        fn serve(&self) -> u16 {
+            let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+            acceptor.set_certificate(&self.server.0).unwrap();
+            acceptor.set_private_key(&self.server.1).unwrap();
+            acceptor.cert_store_mut().add_cert(self.ca.clone()).unwrap();
+            acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+            let acceptor = Arc::new(acceptor.build());
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let Ok(mut stream) = acceptor.accept(stream) else {
+                        continue;
+                    };
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf) {
+                            Ok(0) | Err(_) => break,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let name = stream
+                        .ssl()
+                        .peer_certificate()
+                        .and_then(|cert| {
+                            let entry = cert.subject_name().entries_by_nid(Nid::COMMONNAME).next()?;
+                            Some(entry.data().as_utf8().ok()?.to_string())
+                        })
+                        .unwrap_or_default();
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        name.len(),
+                        name
+                    );
+                    let _ = stream.shutdown();
+                }
+            });
+            port
+        }
+    }
+
+    impl Drop for Pki {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    async fn get(settings: &RequestSettings, port: u16) -> Result<String, String> {
+        let cookies = Arc::new(CookieStoreMutex::default());
+        let client = build_client(settings, cookies, "localhost", &HashMap::new())
+            .map_err(|err| err.to_string())?;
+        let response = client
+            .get(format!("https://localhost:{}/", port))
+            .send()
+            .await
+            .map_err(|err| crate::request::describe_error(&err))?;
+        response.text().await.map_err(|err| err.to_string())
+    }
+
+    #[tokio::test]
+    async fn requests_present_a_pkcs1_client_certificate() {
+        let pki = Pki::new("reqwest");
+        let port = pki.serve();
+        let settings = pki.settings(Some(("client.pem", "client.key")));
+        assert_eq!(get(&settings, port).await.unwrap(), "postsmith client");
+    }
+
+    #[tokio::test]
+    async fn sent_requests_time_the_handshake_with_a_client_certificate() {
+        let pki = Pki::new("send");
+        let port = pki.serve();
+        let settings = pki.settings(Some(("client.pem", "client.key")));
+        let request = crate::request::Request {
+            method: "GET".to_string(),
+            url: format!("https://localhost:{}/", port),
+            headers: Vec::new(),
+            body: None,
+        };
+        let cookies = Arc::new(CookieStoreMutex::default());
+        let response = crate::request::send_request(request, &settings, cookies, &HashMap::new(), None)
+            .await
+            .unwrap();
+        assert_eq!(response.body, b"postsmith client");
+        assert!(response.timings.connect.is_some());
+        assert!(response.timings.tls.is_some());
+    }
+
+    #[tokio::test]
+    async fn requests_without_a_client_certificate_are_turned_away() {
+        let pki = Pki::new("anonymous");
+        let port = pki.serve();
+        assert!(get(&pki.settings(None), port).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn native_tls_connections_present_the_client_certificate() {
+        let pki = Pki::new("native");
+        let port = pki.serve();
+        let settings = pki.settings(Some(("client.pem", "client.key")));
+        let identity = client_identity(&settings, "localhost", &HashMap::new()).unwrap();
+        assert!(identity.is_some());
+
+        let target = raw::Target::parse(&format!("https://localhost:{}/", port)).unwrap();
+        let request = b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n".to_vec();
+        let exchange = raw::send(&target, request, &settings, identity.as_ref()).await.unwrap();
+        assert!(exchange.received.ends_with(b"\r\n\r\npostsmith client"), "{}", raw::escaped(&exchange.received));
+    }
+
+    #[tokio::test]
+    async fn pkcs12_certificates_need_their_passphrase() {
+        let pki = Pki::new("pkcs12");
+        let port = pki.serve();
+        let settings = RequestSettings {
+            client_cert: Some(pki.path("client.p12")),
+            ..pki.settings(None)
+        };
+        let err = client_identity(&settings, "localhost", &HashMap::new()).err().unwrap();
+        assert!(err.to_string().contains("no passphrase"));
+
+        let passphrases = HashMap::from([(pki.path("client.p12"), "secret".to_string())]);
+        let identity = client_identity(&settings, "localhost", &passphrases).unwrap();
+        let target = raw::Target::parse(&format!("https://localhost:{}/", port)).unwrap();
+        let request = b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n".to_vec();
+        let exchange = raw::send(&target, request, &settings, identity.as_ref()).await.unwrap();
+        assert!(exchange.received.ends_with(b"postsmith client"));
+    }
+
+    #[tokio::test]
+    async fn encrypted_keys_need_their_passphrase() {
+        let pki = Pki::new("encrypted");
+        let port = pki.serve();
+        for key in ["client-encrypted.key", "client-encrypted-pkcs8.key"] {
+            let settings = pki.settings(Some(("client.pem", key)));
+            let client_cert = ClientCert::for_host(&settings, "localhost").unwrap();
+            assert!(client_cert.needs_passphrase());
+            assert_eq!(client_cert.secret_file(), pki.path(key));
+            let err = client_identity(&settings, "localhost", &HashMap::new()).err().unwrap();
+            assert!(err.to_string().contains("no passphrase"), "{}", err);
+
+            let wrong = HashMap::from([(pki.path(key), "guess".to_string())]);
+            let err = client_identity(&settings, "localhost", &wrong).err().unwrap();
+            assert!(err.to_string().contains("wrong passphrase"), "{}", err);
+
+            let passphrases = HashMap::from([(pki.path(key), "secret".to_string())]);
+            let identity = client_identity(&settings, "localhost", &passphrases).unwrap();
+            let target = raw::Target::parse(&format!("https://localhost:{}/", port)).unwrap();
+            let request = b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n".to_vec();
+            let exchange = raw::send(&target, request, &settings, identity.as_ref()).await.unwrap();
+            assert!(exchange.received.ends_with(b"postsmith client"));
+        }
+    }
+
+    #[test]
+    fn plain_keys_need_no_passphrase() {
+        let pki = Pki::new("plain");
+        let settings = pki.settings(Some(("client.pem", "client.key")));
+        assert!(!ClientCert::for_host(&settings, "localhost").unwrap().needs_passphrase());
+    }
+}
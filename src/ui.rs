@@ -1,10 +1,26 @@
-use crate::components::{HistoryComponent, InputComponent, OutputComponent, SelectorComponent,  RequestComponent};
+use crate::components::{HistoryComponent, InputComponent, OutputComponent, SelectorComponent,  RequestComponent, SettingsComponent, CookiesComponent, PromptComponent, DiffComponent, WebSocketComponent, SseComponent, GraphqlComponent, GrpcComponent, JsonRpcComponent, SoapComponent, RawComponent};
+use crate::components::requesthea::RequestHeaders;
+use crate::components::selector::HttpMethod;
+use crate::cookies::CookieJar;
+use crate::download::DownloadProgress;
+use crate::request::{Reply, Request, Response};
+use crate::preview::Preview;
+use crate::settings::RequestSettings;
+use crate::sse::Subscription;
+use crate::stream::Progress;
 use crate::session::Session;
+use crate::tls::{ClientCert, ClientIdentity};
+use crate::websocket::Connection;
 use crossterm::event::KeyCode;
+use std::collections::HashMap;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::task::JoinHandle;
 
 use ratatui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Clear, Paragraph},
     Terminal, Frame,
 };
 
@@ -18,7 +34,36 @@ pub struct AppState {
     pub input_component: InputComponent,
     pub message_component: OutputComponent,
     pub history_component: HistoryComponent,
+    pub settings_component: SettingsComponent,
+    pub cookies_component: CookiesComponent,
+    pub prompt_component: PromptComponent,
+    pub diff_component: DiffComponent,
+    pub websocket_component: WebSocketComponent,
+    pub sse_component: SseComponent,
+    pub graphql_component: GraphqlComponent,
+    pub grpc_component: GrpcComponent,
+    pub jsonrpc_component: JsonRpcComponent,
+    pub soap_component: SoapComponent,
+    pub raw_component: RawComponent,
+    pub pending_prompt: Option<PendingPrompt>,
+    pub passphrases: HashMap<String, String>,
+    pub in_flight: Option<InFlight>,
+    pub last_request: Option<Request>,
+    // Ids for JSON-RPC calls count up across requests.
+    pub next_rpc_id: u64,
+    pub download: Option<Transfer>,
+    pub comparison: Option<Comparison>,
+    pub websocket: Option<Connection>,
+    pub event_stream: Option<Subscription>,
+    pub introspection: Option<Introspection>,
+    pub reflection: Option<Reflection>,
+    pub wsdl_fetch: Option<WsdlFetch>,
+    pub raw_send: Option<RawSend>,
+    pub inspection: Option<JoinHandle<Result<String, String>>>,
     pub active_block: ActiveBlock,
+    // Errors that don't belong to a response, like a cookie jar that
+    // couldn't be written. Shown on the bottom line until the next key.
+    pub notice: Option<String>,
     pub request_component: RequestComponent,
     pub runtime: tokio::runtime::Runtime,
     pub session: Session,
@@ -32,19 +77,136 @@ pub enum ActiveBlock {
     Message,
     Request,
     History,
-    
+    Settings,
+    Cookies,
+    Diff,
+    Graphql,
+    Grpc,
+    JsonRpc,
+    Soap,
+    Raw,
+}
+
+// A request running on the runtime while the UI keeps drawing what has
+// arrived so far.
+pub struct InFlight {
+    method: String,
+    url: String,
+    settings: RequestSettings,
+    // What went out and with which settings, to reconnect an event stream.
+    request: Request,
+    effective: RequestSettings,
+    progress: UnboundedReceiver<Progress>,
+    task: JoinHandle<Result<Received, String>>,
+}
+
+// A finished request, ready for the output panel.
+pub enum Received {
+    Response(Box<Response>, Option<Preview>),
+    EventStream(reqwest::Response),
+}
+
+// The same request sent to two environments at once.
+pub struct Comparison {
+    environments: (String, String),
+    // Saved once both responses are in.
+    jars: (CookieJar, CookieJar),
+    task: JoinHandle<(Result<Response, String>, Result<Response, String>)>,
+}
+
+// The schema of a GraphQL endpoint being fetched.
+pub struct Introspection {
+    url: String,
+    task: JoinHandle<Result<crate::graphql::Schema, String>>,
+}
+
+// gRPC services being listed through server reflection.
+pub struct Reflection {
+    url: String,
+    task: JoinHandle<Result<prost_reflect::DescriptorPool, String>>,
+}
+
+// A WSDL being downloaded for the SOAP view.
+pub struct WsdlFetch {
+    source: String,
+    task: JoinHandle<Result<Vec<crate::soap::Operation>, String>>,
+}
+
+// A raw request written to a socket, waiting for what comes back.
+pub struct RawSend {
+    task: JoinHandle<Result<crate::raw::Exchange, String>>,
+}
+
+// A response body being saved to a file.
+pub struct Transfer {
+    progress: UnboundedReceiver<DownloadProgress>,
+    task: JoinHandle<Result<String, String>>,
+}
+
+// What the answer typed into the prompt is for.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PendingPrompt {
+    Passphrase(String, Retry),
+    SavePath,
+    ExampleName,
+    CompareEnvironments,
+}
+
+// What was held up by asking for a passphrase.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Retry {
+    Send,
+    Reflect,
+    Raw,
+    Compare(String, String),
 }
 
 impl AppState {
     pub fn new() -> Self {
         let session = Session::new();
         let history = session.get_history();
+        let defaults = session.defaults.clone();
+        let jar = CookieJar::load(&session.environment);
+        let mut websocket_component = WebSocketComponent::new();
+        websocket_component.templates = session.ws_templates.clone();
+        let mut grpc_component = GrpcComponent::new();
+        grpc_component.protos = session.grpc_protos.clone();
+        let mut soap_component = SoapComponent::new();
+        soap_component.source = session.wsdl.clone();
+        let mut settings_component = SettingsComponent::new(session.environment.clone(), session.environment_settings(), defaults);
+        settings_component.request = session.request.clone();
         Self {
             method_component: SelectorComponent::new(),
             input_component: InputComponent::new(),
             message_component: OutputComponent::new(),
             history_component: HistoryComponent::new_with_history(history),
+            settings_component,
+            cookies_component: CookiesComponent::new(jar),
+            prompt_component: PromptComponent::new(),
+            diff_component: DiffComponent::new(),
+            websocket_component,
+            sse_component: SseComponent::new(),
+            graphql_component: GraphqlComponent::new(),
+            grpc_component,
+            jsonrpc_component: JsonRpcComponent::new(),
+            soap_component,
+            raw_component: RawComponent::new(),
+            pending_prompt: None,
+            passphrases: HashMap::new(),
+            in_flight: None,
+            last_request: None,
+            next_rpc_id: 1,
+            download: None,
+            comparison: None,
+            websocket: None,
+            event_stream: None,
+            introspection: None,
+            reflection: None,
+            wsdl_fetch: None,
+            raw_send: None,
+            inspection: None,
             active_block: ActiveBlock::Method,
+            notice: None,
             request_component: RequestComponent::new(),
             runtime: tokio::runtime::Runtime::new().unwrap(),
             session,
@@ -53,12 +215,225 @@ impl AppState {
     }
 
     pub fn handle_key_event(&mut self, key: KeyCode) -> bool {
+        self.notice = None;
+        if self.prompt_component.show_modal {
+            self.prompt_component.keybinds(key);
+            if !self.prompt_component.show_modal {
+                let answer = self.prompt_component.submitted.take();
+                match (self.pending_prompt.take(), answer) {
+                    (Some(PendingPrompt::Passphrase(file, retry)), Some(passphrase)) => {
+                        self.passphrases.insert(file, passphrase);
+                        match retry {
+                            Retry::Send => self.send(),
+                            Retry::Reflect => self.reflect(),
+                            Retry::Raw => self.send_raw(),
+                            Retry::Compare(first, second) => self.compare_environments(first, second),
+                        }
+                    }
+                    (Some(PendingPrompt::SavePath), Some(path)) if !path.trim().is_empty() => {
+                        self.save_response(path.trim().into());
+                    }
+                    (Some(PendingPrompt::ExampleName), Some(name)) if !name.trim().is_empty() => {
+                        if let Some(response) = &self.message_component.response {
+                            if let Err(e) = self.session.save_example(name.trim().to_string(), &crate::diff::Snapshot::of(response)) {
+                                self.notice = Some(format!("Could not save the example: {}", e));
+                            }
+                        }
+                    }
+                    (Some(PendingPrompt::CompareEnvironments), Some(answer)) => {
+                        if let Some((first, second)) = answer.split_once(',') {
+                            self.compare_environments(first.trim().to_string(), second.trim().to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            return false;
+        }
+
+        if self.active_block == ActiveBlock::Settings {
+            if key == KeyCode::Esc && !self.settings_component.is_editing {
+                self.active_block = ActiveBlock::Method;
+            } else {
+                self.settings_component.keybinds(key);
+                if self.settings_component.request != self.session.request {
+                    self.session.set_request_settings(self.settings_component.request.clone());
+                }
+                if self.settings_component.defaults != self.session.defaults {
+                    self.session.set_defaults(self.settings_component.defaults.clone());
+                }
+                if self.settings_component.environment != self.session.environment {
+                    self.session.set_environment(self.settings_component.environment.clone());
+                    self.settings_component.environment_settings = self.session.environment_settings();
+                    self.cookies_component.set_jar(CookieJar::load(&self.session.environment));
+                } else if self.settings_component.environment_settings != self.session.environment_settings() {
+                    self.session.set_environment_settings(self.settings_component.environment_settings.clone());
+                }
+            }
+            return false;
+        }
+
+        if self.active_block == ActiveBlock::Cookies {
+            if key == KeyCode::Esc && !self.cookies_component.is_editing {
+                self.active_block = ActiveBlock::Method;
+            } else {
+                self.cookies_component.keybinds(key);
+                self.notice = self.cookies_component.save_error.take();
+            }
+            return false;
+        }
+
+        if self.active_block == ActiveBlock::Diff {
+            if key == KeyCode::Esc && !self.diff_component.is_editing {
+                self.active_block = ActiveBlock::Method;
+            } else {
+                self.diff_component.keybinds(key);
+                if self.diff_component.ignore != self.session.diff_ignore {
+                    self.session.set_diff_ignore(self.diff_component.ignore.clone());
+                }
+            }
+            return false;
+        }
+
+        if self.active_block == ActiveBlock::Graphql {
+            self.graphql_component.keybinds(key);
+            if self.graphql_component.wants_introspection {
+                self.graphql_component.wants_introspection = false;
+                self.introspect();
+            }
+            if self.graphql_component.done {
+                if let Ok(body) = self.graphql_component.body() {
+                    self.request_component.set_body(RequestHeaders::Graphql, body);
+                }
+                self.active_block = ActiveBlock::Request;
+            }
+            return false;
+        }
+
+        if self.active_block == ActiveBlock::Grpc {
+            self.grpc_component.keybinds(key);
+            if self.grpc_component.wants_reflection {
+                self.grpc_component.wants_reflection = false;
+                self.reflect();
+            }
+            if self.grpc_component.protos != self.session.grpc_protos {
+                self.session.set_grpc_protos(self.grpc_component.protos.clone());
+            }
+            if self.grpc_component.done {
+                self.method_component.detail = self.grpc_component.label();
+                self.active_block = ActiveBlock::Input;
+            }
+            return false;
+        }
+
+        if self.active_block == ActiveBlock::JsonRpc {
+            self.jsonrpc_component.keybinds(key);
+            if self.jsonrpc_component.done {
+                if let Ok(body) = self.jsonrpc_component.body() {
+                    self.request_component.set_body(RequestHeaders::JsonRpc, body);
+                }
+                self.active_block = ActiveBlock::Request;
+            }
+            return false;
+        }
+
+        if self.active_block == ActiveBlock::Soap {
+            self.soap_component.keybinds(key);
+            if self.soap_component.wants_wsdl {
+                self.soap_component.wants_wsdl = false;
+                self.load_wsdl();
+            }
+            if self.soap_component.source != self.session.wsdl {
+                self.session.set_wsdl(self.soap_component.source.clone());
+            }
+            if self.soap_component.done {
+                if let Some(operation) = self.soap_component.chosen.take() {
+                    self.use_soap_operation(operation);
+                }
+                self.active_block = ActiveBlock::Request;
+            }
+            return false;
+        }
+
+        if self.active_block == ActiveBlock::Raw {
+            self.raw_component.keybinds(key);
+            if self.raw_component.wants_template {
+                self.raw_component.wants_template = false;
+                self.raw_template();
+            }
+            if self.raw_component.wants_send {
+                self.raw_component.wants_send = false;
+                self.send_raw();
+            }
+            if self.raw_component.done {
+                self.active_block = ActiveBlock::Input;
+            }
+            return false;
+        }
+
+        // Picking GRPC as the method goes on to picking the gRPC method.
+        if self.active_block == ActiveBlock::Method && key == KeyCode::Enter && self.method_component.show_selection {
+            self.method_component.keybinds(key);
+            if self.method_component.method == HttpMethod::GRPC {
+                self.open_grpc();
+            }
+            return false;
+        }
+
+        // The GraphQL and JSON-RPC tabs have their own editors instead of
+        // the body input.
+        if self.active_block == ActiveBlock::Request
+            && key == KeyCode::Enter
+            && self.request_component.show_body
+            && !self.request_component.writable
+        {
+            match self.request_component.selected_body_tab() {
+                RequestHeaders::Graphql => {
+                    self.open_graphql();
+                    return false;
+                }
+                RequestHeaders::JsonRpc => {
+                    self.jsonrpc_component.open(self.request_component.stored_body(RequestHeaders::JsonRpc));
+                    self.active_block = ActiveBlock::JsonRpc;
+                    return false;
+                }
+                _ => {}
+            }
+        }
+
+        if self.active_block == ActiveBlock::Message && self.websocket_component.visible {
+            self.websocket_component.keybinds(key);
+            if self.websocket_component.templates != self.session.ws_templates {
+                self.session.set_ws_templates(self.websocket_component.templates.clone());
+            }
+            self.flush_websocket();
+            // Everything typed into the composer belongs to the message.
+            if self.websocket_component.is_composing || key == KeyCode::Esc {
+                return false;
+            }
+        }
+
+        if self.active_block == ActiveBlock::Message && self.sse_component.visible {
+            self.sse_component.keybinds(key);
+            if let Some(subscription) = &self.event_stream {
+                for command in self.sse_component.commands.drain(..) {
+                    let _ = subscription.commands.send(command);
+                }
+            }
+            self.sse_component.commands.clear();
+            if self.sse_component.is_editing || key == KeyCode::Esc {
+                return false;
+            }
+        }
+
         match self.active_block {
             ActiveBlock::Method => self.method_component.keybinds(key),
             ActiveBlock::Input => self.input_component.keybinds(key),
+            ActiveBlock::Message if self.websocket_component.visible || self.sse_component.visible => {}
             ActiveBlock::Message => self.message_component.keybinds(key),
             ActiveBlock::Request => self.request_component.keybinds(key),
             ActiveBlock::History => self.history_component.keybinds(key),
+            ActiveBlock::Settings | ActiveBlock::Cookies | ActiveBlock::Diff | ActiveBlock::Graphql | ActiveBlock::Grpc | ActiveBlock::JsonRpc | ActiveBlock::Soap | ActiveBlock::Raw => {}
          /*   ActiveBlock::Modal => self.modal_input_component.keybinds(key), */
         }
 
@@ -70,7 +445,14 @@ impl AppState {
                     ActiveBlock::Message => ActiveBlock::Request,
                     ActiveBlock::Request => ActiveBlock::Input,
                     ActiveBlock::History => ActiveBlock::History,
-                   
+                    ActiveBlock::Settings => ActiveBlock::Settings,
+                    ActiveBlock::Cookies => ActiveBlock::Cookies,
+                    ActiveBlock::Diff => ActiveBlock::Diff,
+                    ActiveBlock::Graphql => ActiveBlock::Graphql,
+                    ActiveBlock::Grpc => ActiveBlock::Grpc,
+                    ActiveBlock::JsonRpc => ActiveBlock::JsonRpc,
+                    ActiveBlock::Soap => ActiveBlock::Soap,
+                    ActiveBlock::Raw => ActiveBlock::Raw,
                 }
             }
         } else if key == KeyCode::Tab {
@@ -81,7 +463,14 @@ impl AppState {
                     ActiveBlock::Request => ActiveBlock::Message,
                     ActiveBlock::Message => ActiveBlock::Method,
                     ActiveBlock::History => ActiveBlock::History,
-                   
+                    ActiveBlock::Settings => ActiveBlock::Settings,
+                    ActiveBlock::Cookies => ActiveBlock::Cookies,
+                    ActiveBlock::Diff => ActiveBlock::Diff,
+                    ActiveBlock::Graphql => ActiveBlock::Graphql,
+                    ActiveBlock::Grpc => ActiveBlock::Grpc,
+                    ActiveBlock::JsonRpc => ActiveBlock::JsonRpc,
+                    ActiveBlock::Soap => ActiveBlock::Soap,
+                    ActiveBlock::Raw => ActiveBlock::Raw,
                 }
             }
         } else if key == KeyCode::Enter {
@@ -91,6 +480,41 @@ impl AppState {
                 self.history_component.history = self.session.get_history();
                 self.active_block = ActiveBlock::History;
             }
+        } else if key == KeyCode::Char('S') && !self.is_typing() {
+            self.active_block = ActiveBlock::Settings;
+        } else if key == KeyCode::Char('C') && !self.is_typing() {
+            self.cookies_component.refresh();
+            self.active_block = ActiveBlock::Cookies;
+        } else if key == KeyCode::Char('D') && !self.is_typing() {
+            let mut sources = self.session.snapshots();
+            if let Some(response) = &self.message_component.response {
+                sources.insert(0, ("current response".to_string(), crate::diff::Snapshot::of(response)));
+            }
+            self.diff_component.set_sources(sources, self.session.diff_ignore.clone());
+            self.active_block = ActiveBlock::Diff;
+        } else if key == KeyCode::Char('W') && !self.is_typing() {
+            self.soap_component.open();
+            self.active_block = ActiveBlock::Soap;
+            if self.soap_component.wants_wsdl {
+                self.soap_component.wants_wsdl = false;
+                self.load_wsdl();
+            }
+        } else if key == KeyCode::Char('R') && !self.is_typing() {
+            self.open_raw();
+        } else if key == KeyCode::Char('E') && !self.is_typing() {
+            let other = self
+                .session
+                .environments
+                .keys()
+                .find(|name| **name != self.session.environment)
+                .cloned()
+                .unwrap_or_default();
+            self.prompt_component.open(
+                "Compare environments (first, second)".to_string(),
+                format!("{}, {}", self.session.environment, other),
+                false,
+            );
+            self.pending_prompt = Some(PendingPrompt::CompareEnvironments);
         } else if key == KeyCode::Esc {
             if self.active_block == ActiveBlock::History {
                 self.active_block = ActiveBlock::Method;
@@ -104,21 +528,855 @@ impl AppState {
                 self.modal_input_component.show_modal = true;
                 self.active_block = ActiveBlock::Modal;
             } */
+        } else if key == KeyCode::Char('t') {
+            if self.active_block == ActiveBlock::Input && !self.input_component.show_modal {
+                self.inspect_tls(self.input_component.value.trim().to_string());
+            }
+        } else if key == KeyCode::Char('s') && !self.is_typing() {
+            if let (ActiveBlock::Message, Some(response), false) =
+                (self.active_block, &self.message_component.response, self.websocket_component.visible || self.sse_component.visible)
+            {
+                let filename = crate::download::default_filename(&response.url, &response.headers);
+                self.prompt_component.open("Save response to (a partly saved copy is resumed for GET)".to_string(), filename, false);
+                self.pending_prompt = Some(PendingPrompt::SavePath);
+            }
+        } else if key == KeyCode::Char('x') && !self.is_typing() {
+            // Kept by name to compare against in the diff view.
+            if let (ActiveBlock::Message, Some(_)) = (self.active_block, &self.message_component.response) {
+                self.prompt_component.open("Save response as the example named".to_string(), String::new(), false);
+                self.pending_prompt = Some(PendingPrompt::ExampleName);
+            }
         } else if key == KeyCode::Char('g') {
             if self.active_block == ActiveBlock::Input {
-                let response = self
-                    .runtime
-                    .block_on(crate::request::send_get_request(&self.input_component.value));
+                self.send();
+            }
+        }
+
+        false
+    }
+
+    fn send(&mut self) {
+        if self.method_component.method == HttpMethod::GRPC {
+            self.call_grpc();
+        } else if let Some(body) = self.subscription_body() {
+            self.open_subscription(body);
+        } else if crate::websocket::is_websocket_url(&self.input_component.value) {
+            self.open_websocket();
+        } else {
+            self.send_request();
+        }
+    }
+
+    // Passphrases for PKCS#12 bundles and encrypted keys are only ever kept
+    // in memory, so ask for one the first time the file is used; `retry` runs again once it's
+    // typed in. True when the prompt was opened.
+    fn ask_passphrase(&mut self, url: &str, settings: &RequestSettings, retry: Retry) -> bool {
+        let Some(client_cert) = ClientCert::for_host(settings, &crate::request::host_of(url)) else {
+            return false;
+        };
+        let file = client_cert.secret_file().to_string();
+        if self.passphrases.contains_key(&file) || !client_cert.needs_passphrase() {
+            return false;
+        }
+        self.prompt_component.open(format!("Passphrase for {}", file), String::new(), true);
+        self.pending_prompt = Some(PendingPrompt::Passphrase(file, retry));
+        true
+    }
+
+    // The client certificate for connections made without reqwest. `None`
+    // when a passphrase is being asked for or the certificate couldn't be
+    // loaded, which is shown on the bottom line.
+    fn client_identity(&mut self, url: &str, settings: &RequestSettings, retry: Retry) -> Option<Option<ClientIdentity>> {
+        if self.ask_passphrase(url, settings, retry) {
+            return None;
+        }
+        match crate::tls::client_identity(settings, &crate::request::host_of(url), &self.passphrases) {
+            Ok(identity) => Some(identity),
+            Err(err) => {
+                self.notice = Some(err.to_string());
+                None
+            }
+        }
+    }
+
+    // The request the tabs describe. JSON-RPC calls get fresh ids each time
+    // they're sent, so replies can be told apart; the ids come along.
+    fn build_request(&mut self) -> Result<(Request, Option<crate::jsonrpc::Ids>), String> {
+        let mut request = Request {
+            method: self.method_component.method.to_string().to_owned(),
+            url: self.input_component.value.clone(),
+            headers: self.request_component.header_pairs(),
+            body: self.request_component.body(),
+        };
+        let mut ids = None;
+        if let (RequestHeaders::JsonRpc, Some(body)) = (self.request_component.selected_body_tab(), &request.body) {
+            let (body, calls) = crate::jsonrpc::parse_calls(body)
+                .and_then(|calls| crate::jsonrpc::request_body(&calls, &mut self.next_rpc_id))?;
+            request.body = Some(body);
+            ids = Some(calls);
+        }
+        Ok((request, ids))
+    }
+
+    fn send_request(&mut self) {
+        self.message_component.jsonrpc = None;
+        let request = match self.build_request() {
+            Ok((request, ids)) => {
+                self.message_component.jsonrpc = ids;
+                request
+            }
+            Err(err) => {
+                self.message_component.set_error(format!("Error: {}", err));
+                return;
+            }
+        };
+        let settings = self.settings_component.effective();
+        self.last_request = Some(request.clone());
+
+        if self.ask_passphrase(&request.url, &settings, Retry::Send) {
+            return;
+        }
+
+        self.close_streams();
+
+        // Sending again replaces whatever is still running.
+        if let Some(previous) = self.in_flight.take() {
+            previous.task.abort();
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let cookies = self.cookies_component.jar.store.clone();
+        let passphrases = self.passphrases.clone();
+        let (sent, effective) = (request.clone(), settings.clone());
+        let task = self.runtime.spawn(async move {
+            let reply = crate::request::send_or_stream(request, &settings, cookies, &passphrases, Some(&sender))
+                .await
+                .map_err(|err| crate::request::describe_error(err.as_ref()))?;
+            let response = match reply {
+                Reply::Complete(response) => response,
+                Reply::EventStream(response) => return Ok(Received::EventStream(response)),
+            };
+            let body = response.body.clone();
+            let preview = tokio::task::spawn_blocking(move || Preview::decode(&body)).await.ok().flatten();
+            Ok(Received::Response(response, preview))
+        });
+        self.message_component.start_stream();
+        self.in_flight = Some(InFlight {
+            method: self.method_component.method.to_string().to_string(),
+            url: self.input_component.value.clone(),
+            settings: self.settings_component.request.clone(),
+            request: sent,
+            effective,
+            progress: receiver,
+            task,
+        });
+    }
+
+    // Called from the event loop: shows the body as it streams in and
+    // finishes up once the request is done.
+    pub fn poll_request(&mut self) {
+        let Some(in_flight) = &mut self.in_flight else {
+            return;
+        };
+        while let Ok(progress) = in_flight.progress.try_recv() {
+            self.message_component.push_stream(progress);
+        }
+        if !in_flight.task.is_finished() {
+            return;
+        }
+        let Some(in_flight) = self.in_flight.take() else {
+            return;
+        };
+
+        let received = self
+            .runtime
+            .block_on(in_flight.task)
+            .unwrap_or_else(|err| Err(err.to_string()));
+        self.save_cookies();
 
-                self.session.push_history(self.method_component.method.to_string(), self.input_component.value.clone());
-                match response {
-                    Ok(body) => self.message_component.message = body,
-                    Err(err) => self.message_component.message = format!("Error: {}", err),
+        let response = match &received {
+            Ok(Received::Response(response, _)) => Some(&**response),
+            _ => None,
+        };
+        self.session.push_history(
+            &in_flight.method,
+            in_flight.url.clone(),
+            in_flight.settings,
+            response.map(|response| response.timings.clone()),
+            response.map(crate::diff::Snapshot::of),
+        );
+        match received {
+            Ok(Received::Response(response, preview)) => self.message_component.set_response(*response, preview),
+            Ok(Received::EventStream(response)) => self.open_event_stream(in_flight.request, response, in_flight.effective),
+            Err(message) => {
+                self.message_component.set_error(format!("Error: {}", message));
+                if in_flight.url.starts_with("https://") && crate::inspector::is_tls_error(&message) {
+                    self.inspect_tls(in_flight.url.clone());
+                    self.message_component.message.push_str("\n\nThe certificate chain is in the TLS tab.");
                 }
             }
         }
+    }
 
-        false
+    // Opens the GraphQL editor on what the tab held before, with the schema
+    // cached for this URL, fetching it the first time.
+    fn open_graphql(&mut self) {
+        let url = self.input_component.value.trim().to_string();
+        let schema = crate::graphql::load_cached(&url);
+        let missing = schema.is_none();
+        let body = self.request_component.stored_body(RequestHeaders::Graphql).map(|body| body.to_string());
+        self.graphql_component.open(url, schema, body.as_deref());
+        self.active_block = ActiveBlock::Graphql;
+        if missing {
+            self.introspect();
+        }
+    }
+
+    // Sends the introspection query with the request's headers, so
+    // endpoints behind auth work too.
+    fn introspect(&mut self) {
+        let url = self.graphql_component.url.clone();
+        if url.is_empty() {
+            return;
+        }
+        let mut headers = self.request_component.header_pairs();
+        headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+        headers.push(("Content-Type".to_string(), "application/json".to_string()));
+        let request = Request {
+            method: "POST".to_string(),
+            url: url.clone(),
+            headers,
+            body: Some(serde_json::json!({ "query": crate::graphql::INTROSPECTION_QUERY }).to_string()),
+        };
+        let settings = self.settings_component.effective();
+        let cookies = self.cookies_component.jar.store.clone();
+        let passphrases = self.passphrases.clone();
+        if let Some(previous) = self.introspection.take() {
+            previous.task.abort();
+        }
+        let task = self.runtime.spawn(async move {
+            let response = crate::request::send_request(request, &settings, cookies, &passphrases, None)
+                .await
+                .map_err(|err| crate::request::describe_error(err.as_ref()))?;
+            crate::graphql::Schema::from_introspection(&response.body)
+                .map_err(|message| format!("{} (status {})", message, response.status))
+        });
+        self.graphql_component.schema_status = "Fetching the schema...".to_string();
+        self.introspection = Some(Introspection { url, task });
+    }
+
+    pub fn poll_introspection(&mut self) {
+        if !self.introspection.as_ref().is_some_and(|introspection| introspection.task.is_finished()) {
+            return;
+        }
+        let Some(introspection) = self.introspection.take() else {
+            return;
+        };
+        let schema = self
+            .runtime
+            .block_on(introspection.task)
+            .unwrap_or_else(|err| Err(err.to_string()));
+        if let Ok(schema) = &schema {
+            if let Err(e) = crate::graphql::save_cached(&introspection.url, schema) {
+                self.notice = Some(format!("Failed to cache the schema: {}", e));
+            }
+        }
+        if introspection.url == self.graphql_component.url {
+            self.graphql_component.set_schema(schema);
+        }
+    }
+
+    fn open_grpc(&mut self) {
+        self.grpc_component.open(self.input_component.value.trim().to_string());
+        self.active_block = ActiveBlock::Grpc;
+    }
+
+    // Lists the services with the request's headers as metadata, for
+    // servers that want auth even for reflection.
+    fn reflect(&mut self) {
+        let url = self.grpc_component.url.clone();
+        if url.is_empty() {
+            return;
+        }
+        let headers = self.request_component.header_pairs();
+        let settings = self.settings_component.effective();
+        let Some(identity) = self.client_identity(&url, &settings, Retry::Reflect) else {
+            return;
+        };
+        if let Some(previous) = self.reflection.take() {
+            previous.task.abort();
+        }
+        let task = {
+            let url = url.clone();
+            self.runtime.spawn(async move {
+                crate::grpc::reflect(&url, &headers, &settings, identity.as_ref())
+                    .await
+                    .map_err(|err| crate::request::describe_error(err.as_ref()))
+            })
+        };
+        self.grpc_component.source = "Asking the server for its services...".to_string();
+        self.reflection = Some(Reflection { url, task });
+    }
+
+    pub fn poll_reflection(&mut self) {
+        if !self.reflection.as_ref().is_some_and(|reflection| reflection.task.is_finished()) {
+            return;
+        }
+        let Some(reflection) = self.reflection.take() else {
+            return;
+        };
+        let pool = self
+            .runtime
+            .block_on(reflection.task)
+            .unwrap_or_else(|err| Err(err.to_string()));
+        self.grpc_component.set_pool(pool, format!("From server reflection on {}", reflection.url));
+    }
+
+    // Reads the WSDL named in the SOAP view: a file straight away, a URL
+    // in the background with the request's headers.
+    fn load_wsdl(&mut self) {
+        let source = self.soap_component.source.clone();
+        if let Some(previous) = self.wsdl_fetch.take() {
+            previous.task.abort();
+        }
+        if !source.starts_with("http://") && !source.starts_with("https://") {
+            let operations = std::fs::read_to_string(&source)
+                .map_err(|err| err.to_string())
+                .and_then(|wsdl| crate::soap::operations(&wsdl));
+            self.soap_component.set_operations(operations);
+            return;
+        }
+        let mut headers = self.request_component.header_pairs();
+        headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type") && !key.eq_ignore_ascii_case("soapaction"));
+        let request = Request {
+            method: "GET".to_string(),
+            url: source.clone(),
+            headers,
+            body: None,
+        };
+        let settings = self.settings_component.effective();
+        let cookies = self.cookies_component.jar.store.clone();
+        let passphrases = self.passphrases.clone();
+        let task = self.runtime.spawn(async move {
+            let response = crate::request::send_request(request, &settings, cookies, &passphrases, None)
+                .await
+                .map_err(|err| crate::request::describe_error(err.as_ref()))?;
+            crate::soap::operations(&String::from_utf8_lossy(&response.body))
+                .map_err(|message| format!("{} (status {})", message, response.status))
+        });
+        self.soap_component.status = "Fetching the WSDL...".to_string();
+        self.wsdl_fetch = Some(WsdlFetch { source, task });
+    }
+
+    pub fn poll_wsdl(&mut self) {
+        if !self.wsdl_fetch.as_ref().is_some_and(|fetch| fetch.task.is_finished()) {
+            return;
+        }
+        let Some(fetch) = self.wsdl_fetch.take() else {
+            return;
+        };
+        let operations = self
+            .runtime
+            .block_on(fetch.task)
+            .unwrap_or_else(|err| Err(err.to_string()));
+        if fetch.source == self.soap_component.source {
+            self.soap_component.set_operations(operations);
+        }
+    }
+
+    // Sets the request up for an operation picked in the SOAP view.
+    fn use_soap_operation(&mut self, operation: crate::soap::Operation) {
+        self.method_component.set_method(HttpMethod::POST);
+        if let Some(endpoint) = &operation.endpoint {
+            self.input_component.value = endpoint.clone();
+            self.input_component.input = tui_input::Input::from(endpoint.clone());
+        }
+        self.request_component
+            .set_header("Content-Type", operation.version.content_type(&operation.action));
+        match operation.version.soap_action(&operation.action) {
+            Some(action) => self.request_component.set_header("SOAPAction", action),
+            None => self.request_component.remove_header("SOAPAction"),
+        }
+        self.request_component.set_body(RequestHeaders::Raw, operation.envelope);
+        self.request_component.select_body_tab(RequestHeaders::Raw);
+    }
+
+    // Opens the raw HTTP/1.1 view on the URL, starting from the current
+    // request the first time.
+    fn open_raw(&mut self) {
+        let target = match crate::raw::Target::parse(&self.input_component.value) {
+            Ok(target) => target.label(),
+            Err(err) => format!("(no target: {})", err),
+        };
+        self.raw_component.open(target);
+        if self.raw_component.editor.value().is_empty() {
+            self.raw_template();
+        }
+        self.active_block = ActiveBlock::Raw;
+    }
+
+    fn raw_template(&mut self) {
+        let Ok(target) = crate::raw::Target::parse(&self.input_component.value) else {
+            return;
+        };
+        let method = match self.method_component.method {
+            HttpMethod::GRPC => HttpMethod::POST,
+            method => method,
+        };
+        let text = crate::raw::template(
+            method.to_string(),
+            &target,
+            &self.request_component.header_pairs(),
+            self.request_component.body().as_deref(),
+        );
+        self.raw_component.set_template(&text);
+    }
+
+    fn send_raw(&mut self) {
+        let url = self.input_component.value.trim().to_string();
+        let target = match crate::raw::Target::parse(&url) {
+            Ok(target) => target,
+            Err(err) => {
+                self.raw_component.set_exchange(Err(err));
+                return;
+            }
+        };
+        let request = match self.raw_component.request() {
+            Ok(request) => request,
+            Err(err) => {
+                self.raw_component.set_exchange(Err(err));
+                return;
+            }
+        };
+        let settings = self.settings_component.effective();
+        let Some(identity) = self.client_identity(&url, &settings, Retry::Raw) else {
+            return;
+        };
+        if let Some(previous) = self.raw_send.take() {
+            previous.task.abort();
+        }
+        self.session.push_history("RAW", url, self.settings_component.request.clone(), None, None);
+        self.raw_component.status = format!("Sent to {}, waiting for the response...", target.label());
+        let task = self.runtime.spawn(async move {
+            crate::raw::send(&target, request, &settings, identity.as_ref())
+                .await
+                .map_err(|err| crate::request::describe_error(err.as_ref()))
+        });
+        self.raw_send = Some(RawSend { task });
+    }
+
+    pub fn poll_raw(&mut self) {
+        if !self.raw_send.as_ref().is_some_and(|send| send.task.is_finished()) {
+            return;
+        }
+        let Some(send) = self.raw_send.take() else {
+            return;
+        };
+        let exchange = self
+            .runtime
+            .block_on(send.task)
+            .unwrap_or_else(|err| Err(err.to_string()));
+        self.raw_component.set_exchange(exchange);
+    }
+
+    // Calls the method picked in the gRPC view; the log shows in the same
+    // view as WebSocket frames.
+    fn call_grpc(&mut self) {
+        let Some(method) = self.grpc_component.method.clone() else {
+            self.open_grpc();
+            return;
+        };
+        let url = self.input_component.value.trim().to_string();
+        let settings = self.settings_component.effective();
+        let Some(identity) = self.client_identity(&url, &settings, Retry::Send) else {
+            return;
+        };
+        self.close_streams();
+        self.session.push_history(
+            self.method_component.method.to_string(),
+            url.clone(),
+            self.settings_component.request.clone(),
+            None,
+            None,
+        );
+        self.websocket_component
+            .start("gRPC", format!("{} {}", url, crate::grpc::method_name(&method)));
+        self.websocket = Some(crate::grpc::call(
+            &self.runtime,
+            url,
+            method,
+            self.request_component.header_pairs(),
+            settings,
+            identity,
+            self.grpc_component.message.value(),
+        ));
+        self.active_block = ActiveBlock::Message;
+    }
+
+    // Whatever is open in the output panel goes when something else is sent.
+    fn close_streams(&mut self) {
+        self.websocket = None;
+        self.websocket_component.visible = false;
+        self.event_stream = None;
+        self.sse_component.visible = false;
+    }
+
+    // Responses of type text/event-stream stay open and list the events as
+    // they come, reconnecting when the stream drops.
+    fn open_event_stream(&mut self, request: Request, response: reqwest::Response, settings: RequestSettings) {
+        self.message_component.clear();
+        self.sse_component.start(request.url.clone());
+        self.event_stream = Some(crate::sse::subscribe(
+            &self.runtime,
+            request,
+            Some(response),
+            settings,
+            self.cookies_component.jar.store.clone(),
+            self.passphrases.clone(),
+        ));
+        self.active_block = ActiveBlock::Message;
+    }
+
+    pub fn poll_event_stream(&mut self) {
+        let Some(subscription) = &mut self.event_stream else {
+            return;
+        };
+        let mut closed = false;
+        while let Ok(event) = subscription.events.try_recv() {
+            match event {
+                crate::sse::Event::Frame(frame) => self.sse_component.push(frame),
+                crate::sse::Event::Status(status) => {
+                    self.sse_component.open = false;
+                    self.sse_component.status = status;
+                }
+                crate::sse::Event::Open => self.sse_component.open = true,
+                crate::sse::Event::Closed => closed = true,
+            }
+        }
+        if closed {
+            self.event_stream = None;
+            self.sse_component.open = false;
+            self.save_cookies();
+        }
+    }
+
+    // Connects to the ws:// or wss:// URL, using the headers from the request
+    // panel for the handshake. An open connection is replaced.
+    fn open_websocket(&mut self) {
+        let url = self.input_component.value.trim().to_string();
+        let settings = self.settings_component.effective();
+        let Some(identity) = self.client_identity(&url, &settings, Retry::Send) else {
+            return;
+        };
+        self.close_streams();
+        self.websocket_component.start("WebSocket", url.clone());
+        self.websocket = Some(crate::websocket::connect(
+            &self.runtime,
+            url,
+            self.request_component.header_pairs(),
+            settings,
+            identity,
+            self.cookies_component.jar.store.clone(),
+        ));
+        self.active_block = ActiveBlock::Message;
+    }
+
+    // The GraphQL body when it's what would be sent and its operation is a
+    // subscription, which has to go over a WebSocket instead.
+    fn subscription_body(&self) -> Option<String> {
+        if self.request_component.selected_body_tab() != RequestHeaders::Graphql {
+            return None;
+        }
+        self.request_component
+            .body()
+            .filter(|body| crate::graphql::is_subscription(body))
+    }
+
+    // Runs the subscription over graphql-transport-ws on the same endpoint,
+    // logging its messages in the WebSocket view.
+    fn open_subscription(&mut self, body: String) {
+        let url = self.input_component.value.trim().to_string();
+        let settings = self.settings_component.effective();
+        let Some(identity) = self.client_identity(&url, &settings, Retry::Send) else {
+            return;
+        };
+        self.close_streams();
+        self.session.push_history(
+            self.method_component.method.to_string(),
+            url.clone(),
+            self.settings_component.request.clone(),
+            None,
+            None,
+        );
+        self.websocket_component
+            .start("GraphQL subscription", crate::subscription::websocket_url(&url));
+        self.websocket = Some(crate::subscription::subscribe(
+            &self.runtime,
+            url,
+            self.request_component.header_pairs(),
+            settings,
+            identity,
+            self.cookies_component.jar.store.clone(),
+            body,
+        ));
+        self.active_block = ActiveBlock::Message;
+    }
+
+    // Hands the frames queued by the composer to the connection.
+    fn flush_websocket(&mut self) {
+        let outgoing = std::mem::take(&mut self.websocket_component.outgoing);
+        let Some(connection) = &self.websocket else {
+            return;
+        };
+        for message in outgoing {
+            let _ = connection.commands.send(message);
+        }
+    }
+
+    pub fn poll_websocket(&mut self) {
+        let Some(connection) = &mut self.websocket else {
+            return;
+        };
+        let mut closed = false;
+        while let Ok(event) = connection.events.try_recv() {
+            match event {
+                crate::websocket::Event::Log(entry) => self.websocket_component.push(entry),
+                crate::websocket::Event::Open => self.websocket_component.open = true,
+                crate::websocket::Event::Closed => closed = true,
+            }
+        }
+        if closed {
+            self.websocket = None;
+            self.websocket_component.open = false;
+        }
+    }
+
+    // Sends the current request with each environment's settings and cookie
+    // jar at the same time, then shows both responses in the diff view.
+    fn compare_environments(&mut self, first: String, second: String) {
+        if first.is_empty() || second.is_empty() {
+            return;
+        }
+        if first == second {
+            self.notice = Some(format!("Both sides are \"{}\", pick two different environments", first));
+            return;
+        }
+        // Unknown names would quietly get the defaults and compare nothing.
+        let known = |name: &str| name == self.session.environment || self.session.environments.contains_key(name);
+        if let Some(unknown) = [&first, &second].into_iter().find(|name| !known(name)) {
+            let mut names: Vec<&str> = self.session.environments.keys().map(|name| name.as_str()).collect();
+            if !names.contains(&self.session.environment.as_str()) {
+                names.push(&self.session.environment);
+            }
+            self.notice = Some(format!("No environment named \"{}\" (there are: {})", unknown, names.join(", ")));
+            return;
+        }
+        let request = match self.build_request() {
+            Ok((request, _)) => request,
+            Err(err) => {
+                self.notice = Some(format!("Could not build the request: {}", err));
+                return;
+            }
+        };
+        let prepare = |environment: &str| {
+            let settings = self
+                .settings_component
+                .effective_in(&self.session.settings_for(environment));
+            // The live jar for the current environment, so both stay in sync.
+            let jar = if environment == self.session.environment {
+                CookieJar {
+                    environment: environment.to_string(),
+                    store: self.cookies_component.jar.store.clone(),
+                }
+            } else {
+                CookieJar::load(environment)
+            };
+            (settings, jar)
+        };
+        let (first_settings, first_jar) = prepare(&first);
+        let (second_settings, second_jar) = prepare(&second);
+        let retry = Retry::Compare(first.clone(), second.clone());
+        for settings in [&first_settings, &second_settings] {
+            if self.ask_passphrase(&request.url, settings, retry.clone()) {
+                return;
+            }
+        }
+        let passphrases = self.passphrases.clone();
+        let (first_store, second_store) = (first_jar.store.clone(), second_jar.store.clone());
+
+        if let Some(previous) = self.comparison.take() {
+            previous.task.abort();
+        }
+        let task = self.runtime.spawn(async move {
+            let send = |request: Request, settings: RequestSettings, store, passphrases: HashMap<String, String>| async move {
+                crate::request::send_request(request, &settings, store, &passphrases, None)
+                    .await
+                    .map_err(|err| crate::request::describe_error(err.as_ref()))
+            };
+            tokio::join!(
+                send(request.clone(), first_settings, first_store, passphrases.clone()),
+                send(request, second_settings, second_store, passphrases),
+            )
+        });
+
+        self.diff_component.status = Some(format!("Sending {} to {} and {}...", self.input_component.value, first, second));
+        self.active_block = ActiveBlock::Diff;
+        self.comparison = Some(Comparison {
+            environments: (first, second),
+            jars: (first_jar, second_jar),
+            task,
+        });
+    }
+
+    fn save_cookies(&mut self) {
+        if let Err(e) = self.cookies_component.jar.save() {
+            self.notice = Some(format!("Failed to save cookies: {}", e));
+        }
+    }
+
+    pub fn poll_comparison(&mut self) {
+        if !self.comparison.as_ref().is_some_and(|comparison| comparison.task.is_finished()) {
+            return;
+        }
+        let Some(comparison) = self.comparison.take() else {
+            return;
+        };
+        let snapshot = |result: Result<Response, String>| match result {
+            Ok(response) => crate::diff::Snapshot::of(&response),
+            Err(message) => crate::diff::Snapshot {
+                body: format!("Error: {}", message),
+                ..Default::default()
+            },
+        };
+        let (first, second) = comparison.environments;
+        for jar in [&comparison.jars.0, &comparison.jars.1] {
+            if let Err(e) = jar.save() {
+                self.notice = Some(format!("Failed to save cookies: {}", e));
+            }
+        }
+        let sources = match self.runtime.block_on(comparison.task) {
+            Ok((first_response, second_response)) => vec![
+                (format!("environment {}", second), snapshot(second_response)),
+                (format!("environment {}", first), snapshot(first_response)),
+            ],
+            Err(err) => {
+                self.diff_component.status = Some(format!("Error: {}", err));
+                return;
+            }
+        };
+        self.diff_component.set_sources(sources, self.session.diff_ignore.clone());
+    }
+
+    // Writes out the body of the shown response. A file that holds the start
+    // of that body is resumed for a GET, with a Range request for the missing
+    // part; sending anything else again could repeat what it did. Any other
+    // file is written over.
+    fn save_response(&mut self, path: std::path::PathBuf) {
+        let Some(response) = &self.message_component.response else {
+            return;
+        };
+        let get = response.method.eq_ignore_ascii_case("GET");
+        let validator = crate::download::validator(&response.headers);
+        let request = Request {
+            method: response.method.clone(),
+            url: response.url.clone(),
+            headers: self.last_request.as_ref().map(|request| request.headers.clone()).unwrap_or_default(),
+            body: None,
+        };
+        let body = response.body.clone();
+        let spooled = response.spooled.clone();
+        if let Some(previous) = self.download.take() {
+            previous.task.abort();
+        }
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let settings = self.settings_component.effective();
+        let cookies = self.cookies_component.jar.store.clone();
+        let passphrases = self.passphrases.clone();
+        let task = self.runtime.spawn(async move {
+            let resume = get && crate::download::holds_start_of(&path, &body, spooled.as_deref()).await.unwrap_or(false);
+            let saved = if resume {
+                crate::download::save(request, validator, &settings, cookies, &passphrases, &path, &sender).await
+            } else {
+                crate::download::write(&body, spooled, &path).await
+            };
+            saved.map_err(|err| crate::request::describe_error(err.as_ref()))
+        });
+        self.message_component.download_status = Some("Saving...".to_string());
+        self.download = Some(Transfer { progress: receiver, task });
+    }
+
+    pub fn poll_download(&mut self) {
+        let Some(download) = &mut self.download else {
+            return;
+        };
+        while let Ok(progress) = download.progress.try_recv() {
+            self.message_component.download_status = Some(format!("Saving {}", progress.render()));
+        }
+        if !download.task.is_finished() {
+            return;
+        }
+        let Some(download) = self.download.take() else {
+            return;
+        };
+        let status = match self.runtime.block_on(download.task) {
+            Ok(Ok(summary)) => summary,
+            Ok(Err(message)) => format!("Save failed: {}", message),
+            Err(err) => format!("Save failed: {}", err),
+        };
+        self.message_component.download_status = Some(status);
+    }
+
+    // Whether something is running that the UI has to keep redrawing for.
+    pub fn is_busy(&self) -> bool {
+        self.in_flight.is_some()
+            || self.download.is_some()
+            || self.comparison.is_some()
+            || self.websocket.is_some()
+            || self.event_stream.is_some()
+            || self.introspection.is_some()
+            || self.reflection.is_some()
+            || self.wsdl_fetch.is_some()
+            || self.raw_send.is_some()
+            || self.inspection.is_some()
+    }
+
+    // The handshake can take as long as the connect timeout, so it runs on
+    // the runtime's blocking threads.
+    fn inspect_tls(&mut self, url: String) {
+        let settings = self.settings_component.effective();
+        if let Some(previous) = self.inspection.take() {
+            previous.abort();
+        }
+        self.message_component
+            .set_tls_report(format!("Connecting to {} to look at its certificates...", url));
+        self.inspection = Some(self.runtime.spawn_blocking(move || {
+            crate::inspector::inspect(&url, &settings).map_err(|err| err.to_string())
+        }));
+    }
+
+    pub fn poll_inspection(&mut self) {
+        if !self.inspection.as_ref().is_some_and(|task| task.is_finished()) {
+            return;
+        }
+        let Some(task) = self.inspection.take() else {
+            return;
+        };
+        let report = self
+            .runtime
+            .block_on(task)
+            .unwrap_or_else(|err| Err(err.to_string()))
+            .unwrap_or_else(|err| format!("Error: {}", err));
+        // Left on whatever tab is open by now.
+        self.message_component.tls_report = Some(report);
+    }
+
+    // Global single-letter shortcuts must not fire while text is being typed
+    // into one of the modals.
+    fn is_typing(&self) -> bool {
+        self.input_component.show_modal
+            || self.request_component.adding_header
+            || self.request_component.is_editing
+            || self.request_component.writable
     }
 }
 
@@ -126,12 +1384,38 @@ pub fn draw_ui<B: Backend>(
     terminal: &mut Terminal<B>,
     app_state: &mut AppState,
 ) -> std::io::Result<()> {
+    // The output panel sets this again if it still shows an image.
+    app_state.message_component.image_area.set(None);
     terminal.draw(|f| {
         let size = f.size();
 
         if app_state.active_block == ActiveBlock::History {
             let history_chunk = Rect::new(0, 0, size.width, size.height);
             app_state.history_component.draw::<B>(f, history_chunk, true);
+        } else if app_state.active_block == ActiveBlock::Settings {
+            let settings_chunk = Rect::new(0, 0, size.width, size.height);
+            app_state.settings_component.draw::<B>(f, settings_chunk, true);
+        } else if app_state.active_block == ActiveBlock::Cookies {
+            let cookies_chunk = Rect::new(0, 0, size.width, size.height);
+            app_state.cookies_component.draw::<B>(f, cookies_chunk, true);
+        } else if app_state.active_block == ActiveBlock::Diff {
+            let diff_chunk = Rect::new(0, 0, size.width, size.height);
+            app_state.diff_component.draw::<B>(f, diff_chunk, true);
+        } else if app_state.active_block == ActiveBlock::Graphql {
+            let graphql_chunk = Rect::new(0, 0, size.width, size.height);
+            app_state.graphql_component.draw::<B>(f, graphql_chunk, true);
+        } else if app_state.active_block == ActiveBlock::Grpc {
+            let grpc_chunk = Rect::new(0, 0, size.width, size.height);
+            app_state.grpc_component.draw::<B>(f, grpc_chunk, true);
+        } else if app_state.active_block == ActiveBlock::JsonRpc {
+            let jsonrpc_chunk = Rect::new(0, 0, size.width, size.height);
+            app_state.jsonrpc_component.draw::<B>(f, jsonrpc_chunk, true);
+        } else if app_state.active_block == ActiveBlock::Soap {
+            let soap_chunk = Rect::new(0, 0, size.width, size.height);
+            app_state.soap_component.draw::<B>(f, soap_chunk, true);
+        } else if app_state.active_block == ActiveBlock::Raw {
+            let raw_chunk = Rect::new(0, 0, size.width, size.height);
+            app_state.raw_component.draw::<B>(f, raw_chunk, true);
         } else {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
@@ -153,10 +1437,32 @@ pub fn draw_ui<B: Backend>(
             app_state.method_component.draw::<B>(f, top_chunks[0], app_state.active_block == ActiveBlock::Method);
             app_state.input_component.draw::<B>(f, top_chunks[1], app_state.active_block == ActiveBlock::Input);
             app_state.request_component.draw::<B>(f, chunks[1], app_state.active_block == ActiveBlock::Request);
-            app_state.message_component.draw::<B>(f, chunks[2], app_state.active_block == ActiveBlock::Message);
+            if app_state.websocket_component.visible {
+                app_state.websocket_component.draw::<B>(f, chunks[2], app_state.active_block == ActiveBlock::Message);
+            } else if app_state.sse_component.visible {
+                app_state.sse_component.draw::<B>(f, chunks[2], app_state.active_block == ActiveBlock::Message);
+            } else {
+                app_state.message_component.draw::<B>(f, chunks[2], app_state.active_block == ActiveBlock::Message);
+            }
+        }
 
-          
+        if app_state.prompt_component.show_modal {
+            app_state.message_component.image_area.set(None);
+            app_state.prompt_component.draw::<B>(f, size, true);
+        }
+        if let (Some(notice), true) = (&app_state.notice, size.height > 0) {
+            let area = Rect::new(0, size.height - 1, size.width, 1);
+            f.render_widget(Clear, area);
+            f.render_widget(
+                Paragraph::new(notice.as_str()).style(Style::default().fg(Color::White).bg(Color::Red)),
+                area,
+            );
         }
     })?;
+
+    if app_state.message_component.flush_graphics(&mut std::io::stdout())? {
+        terminal.clear()?;
+        return draw_ui(terminal, app_state);
+    }
     Ok(())
 }
@@ -0,0 +1,224 @@
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use tokio::net::UnixStream;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::request::{Request, Response};
+use crate::settings::RequestSettings;
+use crate::stream::{self, BodySink, Progress};
+use crate::timing::Timings;
+use crate::wire;
+
+// Where to send a request that goes over a Unix domain socket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub socket: String,
+    pub path: String,
+    pub host: String,
+}
+
+// Either a `unix://` URL, or a normal URL with the `unix_socket` setting,
+// whose host only ends up in the Host header. The socket in a `unix://` URL
+// is given as `unix:///path/to.sock:/request/path`, ending at the first
+// `:/`, or percent-encoded up to the first `/`, as in
+// `unix://%2Fpath%2Fto.sock/request/path`, for paths that have `:/` in them.
+pub fn target(url: &str, settings: &RequestSettings) -> Option<Target> {
+    if let Some(rest) = url.strip_prefix("unix://") {
+        let (socket, path) = if rest.starts_with('/') {
+            match rest.split_once(":/") {
+                Some((socket, path)) => (socket.to_string(), format!("/{}", path)),
+                None => (rest.to_string(), "/".to_string()),
+            }
+        } else {
+            let (socket, path) = rest.find('/').map(|i| rest.split_at(i)).unwrap_or((rest, "/"));
+            (percent_decode_str(socket).decode_utf8_lossy().to_string(), path.to_string())
+        };
+        return Some(Target {
+            socket,
+            path,
+            host: "localhost".to_string(),
+        });
+    }
+
+    let socket = settings.unix_socket.clone()?;
+    let url = Url::parse(url).ok()?;
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    Some(Target {
+        socket,
+        path,
+        host: url.host_str().unwrap_or("localhost").to_string(),
+    })
+}
+
+pub async fn send(
+    request: Request,
+    settings: &RequestSettings,
+    target: Target,
+    progress: Option<&UnboundedSender<Progress>>,
+) -> Result<Response, Box<dyn std::error::Error>> {
+    let exchange = exchange(request, settings, &target, progress);
+    match settings.timeout_ms {
+        Some(ms) => tokio::time::timeout(Duration::from_millis(ms), exchange)
+            .await
+            .map_err(|_| format!("timed out after {} ms", ms))?,
+        None => exchange.await,
+    }
+}
+
+async fn exchange(
+    request: Request,
+    settings: &RequestSettings,
+    target: &Target,
+    progress: Option<&UnboundedSender<Progress>>,
+) -> Result<Response, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let mut timings = Timings::default();
+    let stream = UnixStream::connect(&target.socket)
+        .await
+        .map_err(|e| format!("could not connect to {}: {}", target.socket, e))?;
+    timings.connect = Some(started.elapsed());
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+    tokio::spawn(connection);
+
+    let mut builder = hyper::Request::builder()
+        .method(request.method.as_str())
+        .uri(target.path.as_str());
+    if !request.headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("host")) {
+        builder = builder.header("Host", target.host.as_str());
+    }
+    for (key, value) in &request.headers {
+        builder = builder.header(key.as_str(), value.as_str());
+    }
+    let body = request.body.clone().unwrap_or_default();
+    let sent_headers = builder
+        .headers_ref()
+        .map(|headers| {
+            headers
+                .iter()
+                .map(|(key, value)| (key.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut wire_log = format!("* Connected to unix socket {}\n", target.socket);
+    if let Ok(url) = Url::parse(&format!("http://{}{}", target.host, target.path)) {
+        wire_log.push_str(&wire::request(
+            &request.method,
+            &url,
+            reqwest::Version::HTTP_11,
+            &sent_headers,
+            request.body.as_deref().map(str::as_bytes),
+        ));
+    }
+    let response = sender.send_request(builder.body(Full::new(Bytes::from(body)))?).await?;
+    timings.ttfb = started.elapsed();
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(key, value)| (key.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+        .collect::<Vec<_>>();
+    if let Ok(status) = reqwest::StatusCode::from_u16(status) {
+        wire_log.push_str(&wire::response(reqwest::Version::HTTP_11, status, &headers));
+    }
+
+    let content_type = response
+        .headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let mut sink = BodySink::new(
+        settings.memory_cap_kb.unwrap_or(stream::DEFAULT_MEMORY_CAP_KB),
+        content_type.as_deref(),
+    );
+    let mut body = response.into_body();
+    while let Some(frame) = body.frame().await {
+        if let Ok(chunk) = frame?.into_data() {
+            sink.push(&chunk).await?;
+            sink.report(progress);
+        }
+    }
+    let size = sink.received();
+    let (body, spooled) = sink.finish().await?;
+    timings.download = started.elapsed() - timings.ttfb;
+
+    Ok(Response {
+        method: request.method,
+        status,
+        url: request.url,
+        route: format!("unix socket {}", target.socket),
+        resolved: None,
+        headers,
+        body,
+        size,
+        spooled,
+        elapsed: started.elapsed(),
+        timings,
+        redirects: Vec::new(),
+        signing: None,
+        wire: wire_log,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unix(url: &str) -> (String, String) {
+        let target = target(url, &RequestSettings::default()).unwrap();
+        assert_eq!(target.host, "localhost");
+        (target.socket, target.path)
+    }
+
+    fn pair(socket: &str, path: &str) -> (String, String) {
+        (socket.to_string(), path.to_string())
+    }
+
+    #[test]
+    fn socket_and_path_are_split_at_the_first_colon_slash() {
+        assert_eq!(
+            unix("unix:///var/run/docker.sock:/v1.43/containers/json?all=1"),
+            pair("/var/run/docker.sock", "/v1.43/containers/json?all=1")
+        );
+        assert_eq!(unix("unix:///var/run/docker.sock"), pair("/var/run/docker.sock", "/"));
+        // Colons elsewhere belong to the socket, or to the request path.
+        assert_eq!(unix("unix:///tmp/app:8080.sock:/health"), pair("/tmp/app:8080.sock", "/health"));
+        assert_eq!(unix("unix:///tmp/a.sock:/redirect?to=http://x/"), pair("/tmp/a.sock", "/redirect?to=http://x/"));
+    }
+
+    #[test]
+    fn percent_encoded_sockets_may_hold_anything() {
+        assert_eq!(
+            unix("unix://%2Fvar%2Frun%2Fdocker.sock/v1.43/info"),
+            pair("/var/run/docker.sock", "/v1.43/info")
+        );
+        assert_eq!(unix("unix://%2Ftmp%2Fa%3A%2Fb.sock/x:/y"), pair("/tmp/a:/b.sock", "/x:/y"));
+        assert_eq!(unix("unix://%2Ftmp%2Fa.sock"), pair("/tmp/a.sock", "/"));
+        assert_eq!(unix("unix://relative.sock/ping"), pair("relative.sock", "/ping"));
+    }
+
+    #[test]
+    fn the_setting_sends_normal_urls_over_the_socket() {
+        let settings = RequestSettings {
+            unix_socket: Some("/run/app.sock".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            target("http://api.local/v1/items?page=2", &settings),
+            Some(Target {
+                socket: "/run/app.sock".to_string(),
+                path: "/v1/items?page=2".to_string(),
+                host: "api.local".to_string(),
+            })
+        );
+        assert_eq!(target("http://api.local/", &RequestSettings::default()), None);
+    }
+}
@@ -0,0 +1,392 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use reqwest::cookie::CookieStore;
+use reqwest_cookie_store::CookieStoreMutex;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+
+use crate::proxy;
+use crate::request::describe_error;
+use crate::settings::RequestSettings;
+use crate::tls::{self, ClientIdentity};
+
+// How much of a binary frame is shown in the log.
+const BINARY_PREVIEW: usize = 32;
+
+pub fn is_websocket_url(url: &str) -> bool {
+    let url = url.trim().to_lowercase();
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+// A message kept for sending again later.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    pub binary: bool,
+    pub payload: String,
+}
+
+impl Template {
+    // Binary payloads are written as hex, spaces allowed.
+    pub fn to_outgoing(&self) -> Result<Outgoing, String> {
+        if !self.binary {
+            return Ok(Outgoing::Text(self.payload.clone()));
+        }
+        let digits: String = self.payload.chars().filter(|c| !c.is_whitespace()).collect();
+        hex::decode(&digits)
+            .map(Outgoing::Binary)
+            .map_err(|_| format!("{} is not valid hex", self.payload))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outgoing {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping,
+    Close,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+    Info,
+}
+
+// One line of the connection log.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub time: String,
+    pub direction: Direction,
+    pub kind: &'static str,
+    pub text: String,
+}
+
+impl Entry {
+    pub fn new(direction: Direction, kind: &'static str, text: String) -> Entry {
+        Entry {
+            time: chrono::Local::now().format("%H:%M:%S%.3f").to_string(),
+            direction,
+            kind,
+            text,
+        }
+    }
+
+    pub fn of(direction: Direction, message: &Message) -> Entry {
+        match message {
+            Message::Text(text) => Entry::new(direction, "text", text.clone()),
+            Message::Binary(bytes) => Entry::new(direction, "binary", describe_binary(bytes)),
+            Message::Ping(bytes) => Entry::new(direction, "ping", describe_binary(bytes)),
+            Message::Pong(bytes) => Entry::new(direction, "pong", describe_binary(bytes)),
+            Message::Close(Some(frame)) => Entry::new(
+                direction,
+                "close",
+                format!("{} ({:?}) {}", u16::from(frame.code), frame.code, frame.reason),
+            ),
+            Message::Close(None) => Entry::new(direction, "close", "no status code".to_string()),
+            Message::Frame(_) => Entry::new(direction, "frame", String::new()),
+        }
+    }
+}
+
+fn describe_binary(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "no payload".to_string();
+    }
+    let preview = hex::encode(&bytes[..bytes.len().min(BINARY_PREVIEW)]);
+    if bytes.len() > BINARY_PREVIEW {
+        format!("{} bytes: {}...", bytes.len(), preview)
+    } else {
+        format!("{} bytes: {}", bytes.len(), preview)
+    }
+}
+
+pub enum Event {
+    Log(Entry),
+    Open,
+    Closed,
+}
+
+// A connection running on the runtime. Dropping it closes the socket
+// cleanly, with a close frame.
+pub struct Connection {
+    pub commands: UnboundedSender<Outgoing>,
+    pub events: UnboundedReceiver<Event>,
+}
+
+pub fn connect(
+    runtime: &tokio::runtime::Runtime,
+    url: String,
+    headers: Vec<(String, String)>,
+    settings: RequestSettings,
+    identity: Option<ClientIdentity>,
+    cookies: Arc<CookieStoreMutex>,
+) -> Connection {
+    let (commands, mut receiver) = mpsc::unbounded_channel();
+    let (events, event_receiver) = mpsc::unbounded_channel();
+    runtime.spawn(async move {
+        if let Err(err) = run(&url, &headers, &settings, identity.as_ref(), &cookies, &mut receiver, &events).await {
+            let _ = events.send(Event::Log(Entry::new(Direction::Info, "error", describe_error(err.as_ref()))));
+        }
+        let _ = events.send(Event::Closed);
+    });
+    Connection {
+        commands,
+        events: event_receiver,
+    }
+}
+
+fn connector(settings: &RequestSettings, identity: Option<&ClientIdentity>) -> Result<Connector, Box<dyn std::error::Error>> {
+    Ok(Connector::NativeTls(tls::native_tls_builder(settings, identity)?.build()?))
+}
+
+pub type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// Opens the connection and logs how it went. `protocol` is asked for as the
+// subprotocol, which the server has to agree to.
+pub async fn handshake(
+    url: &str,
+    headers: &[(String, String)],
+    settings: &RequestSettings,
+    identity: Option<&ClientIdentity>,
+    cookies: &CookieStoreMutex,
+    protocol: Option<&str>,
+    events: &UnboundedSender<Event>,
+) -> Result<Socket, Box<dyn std::error::Error>> {
+    let log = |entry: Entry| {
+        let _ = events.send(Event::Log(entry));
+    };
+
+    let url = with_scheme(url, |scheme| scheme.to_lowercase());
+    let mut request = url.as_str().into_client_request()?;
+    // Repeated headers all go out, as they would on an HTTP request.
+    for (key, value) in headers {
+        request
+            .headers_mut()
+            .append(HeaderName::from_bytes(key.as_bytes())?, HeaderValue::from_str(value)?);
+    }
+    // Cookies are stored against http(s) URLs.
+    let cookie_url = reqwest::Url::parse(&http_url(&url))?;
+    if let (Some(cookie), false) = (cookies.cookies(&cookie_url), request.headers().contains_key("cookie")) {
+        request.headers_mut().insert("cookie", HeaderValue::from_bytes(cookie.as_bytes())?);
+    }
+    if let Some(protocol) = protocol {
+        request.headers_mut().insert("sec-websocket-protocol", HeaderValue::from_str(protocol)?);
+    }
+
+    // The connection is made here so it goes the way an HTTP request would:
+    // through the proxy, or to the address the resolve setting pins.
+    log(Entry::new(Direction::Info, "connect", format!("{}, {}", url, proxy::route(settings, cookie_url.as_str()).describe())));
+    let host = cookie_url.host_str().ok_or("URL has no host")?;
+    let port = cookie_url.port_or_known_default().ok_or("URL has no port")?;
+    let connector = connector(settings, identity)?;
+    let handshake = async {
+        let stream = proxy::connect(settings, cookie_url.as_str(), host, port).await?;
+        stream.set_nodelay(true)?;
+        Ok::<_, Box<dyn std::error::Error>>(
+            tokio_tungstenite::client_async_tls_with_config(request, stream, None, Some(connector)).await?,
+        )
+    };
+    let (socket, response) = match settings.connect_timeout_ms {
+        Some(ms) => tokio::time::timeout(Duration::from_millis(ms), handshake)
+            .await
+            .map_err(|_| format!("timed out after {} ms", ms))??,
+        None => handshake.await?,
+    };
+    // Like any other response, cookies set on the upgrade are kept.
+    cookies.set_cookies(&mut response.headers().get_all("set-cookie").iter(), &cookie_url);
+    let protocol = response
+        .headers()
+        .get("sec-websocket-protocol")
+        .and_then(|value| value.to_str().ok())
+        .map(|protocol| format!(", protocol {}", protocol))
+        .unwrap_or_default();
+    log(Entry::new(Direction::Info, "open", format!("{}{}", response.status(), protocol)));
+    let _ = events.send(Event::Open);
+    Ok(socket)
+}
+
+// `url` with its scheme passed through `change`; the rest is left alone.
+fn with_scheme(url: &str, change: impl Fn(&str) -> String) -> String {
+    let url = url.trim();
+    match url.split_once("://") {
+        Some((scheme, rest)) => format!("{}://{}", change(scheme), rest),
+        None => url.to_string(),
+    }
+}
+
+// The http(s) URL a ws(s) one is upgraded from, whatever case its scheme
+// is written in.
+fn http_url(url: &str) -> String {
+    with_scheme(url, |scheme| match scheme.to_lowercase().as_str() {
+        "wss" => "https".to_string(),
+        "ws" => "http".to_string(),
+        other => other.to_string(),
+    })
+}
+
+pub fn close() -> Message {
+    Message::Close(Some(CloseFrame {
+        code: CloseCode::Normal,
+        reason: Cow::Borrowed(""),
+    }))
+}
+
+async fn run(
+    url: &str,
+    headers: &[(String, String)],
+    settings: &RequestSettings,
+    identity: Option<&ClientIdentity>,
+    cookies: &CookieStoreMutex,
+    commands: &mut UnboundedReceiver<Outgoing>,
+    events: &UnboundedSender<Event>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let log = |entry: Entry| {
+        let _ = events.send(Event::Log(entry));
+    };
+    let socket = handshake(url, headers, settings, identity, cookies, None, events).await?;
+
+    let (mut sink, mut stream) = socket.split();
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                let Some(command) = command else {
+                    // Nobody is left to read the log once the UI is gone.
+                    sink.send(close()).await?;
+                    break;
+                };
+                let message = match command {
+                    Outgoing::Text(text) => Message::Text(text),
+                    Outgoing::Binary(bytes) => Message::Binary(bytes),
+                    Outgoing::Ping => Message::Ping(Vec::new()),
+                    Outgoing::Close => close(),
+                };
+                log(Entry::of(Direction::Sent, &message));
+                sink.send(message).await?;
+            }
+            incoming = stream.next() => {
+                let Some(message) = incoming else {
+                    break;
+                };
+                let message = message?;
+                log(Entry::of(Direction::Received, &message));
+                // Pongs and the reply to a close are queued by the library
+                // and only go out with the next write. The socket may
+                // already be gone after a close, which is fine.
+                if matches!(message, Message::Ping(_) | Message::Close(_)) {
+                    let _ = sink.flush().await;
+                }
+            }
+        }
+    }
+    log(Entry::new(Direction::Info, "closed", "connection closed".to_string()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+
+    // Accepts one connection, setting a cookie on the upgrade, and echoes
+    // text frames until the client closes. Returns the port and the headers
+    // of the upgrade request.
+    async fn echo_server() -> (u16, Arc<Mutex<Vec<(String, String)>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let headers = seen.clone();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            // The error type is tungstenite's, however big clippy finds it.
+            #[allow(clippy::result_large_err)]
+            let upgrade = move |request: &Request, mut response: Response| -> Result<Response, ErrorResponse> {
+                *headers.lock().unwrap() = request
+                    .headers()
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_str().unwrap().to_string()))
+                    .collect();
+                response.headers_mut().append("set-cookie", "session=abc; Path=/".parse().unwrap());
+                Ok(response)
+            };
+            let mut socket = tokio_tungstenite::accept_hdr_async(stream, upgrade).await.unwrap();
+            while let Some(Ok(message)) = socket.next().await {
+                match message {
+                    Message::Text(text) => socket.send(Message::Text(text)).await.unwrap(),
+                    Message::Close(_) => {
+                        let _ = socket.flush().await;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        });
+        (port, seen)
+    }
+
+    #[test]
+    fn schemes_are_swapped_whatever_their_case() {
+        assert_eq!(http_url("ws://example.test/chat"), "http://example.test/chat");
+        assert_eq!(http_url("WS://example.test/chat"), "http://example.test/chat");
+        assert_eq!(http_url(" Wss://example.test/ws?a=wss "), "https://example.test/ws?a=wss");
+        assert_eq!(with_scheme("WSS://Example.test/Path", |scheme| scheme.to_lowercase()), "wss://Example.test/Path");
+        assert!(is_websocket_url("Wss://example.test"));
+    }
+
+    #[tokio::test]
+    async fn talks_to_a_local_server_and_keeps_its_cookies() {
+        let (port, seen) = echo_server().await;
+        let settings = RequestSettings {
+            proxy: Some("none".to_string()),
+            ..RequestSettings::default()
+        };
+        let cookies = CookieStoreMutex::default();
+        let http = reqwest::Url::parse(&format!("http://127.0.0.1:{}/", port)).unwrap();
+        cookies.set_cookies(&mut [HeaderValue::from_static("earlier=1; Path=/")].iter(), &http);
+        let headers = vec![("X-Tag".to_string(), "a".to_string()), ("X-Tag".to_string(), "b".to_string())];
+
+        let (sender, mut commands) = mpsc::unbounded_channel();
+        sender.send(Outgoing::Text("hello".to_string())).unwrap();
+        let (events, mut log) = mpsc::unbounded_channel();
+        let url = format!("WS://127.0.0.1:{}/chat", port);
+        let connection = run(&url, &headers, &settings, None, &cookies, &mut commands, &events);
+        let close = async {
+            // Closed once the echo is back.
+            loop {
+                match log.recv().await {
+                    Some(Event::Log(entry)) if entry.direction == Direction::Received && entry.kind == "text" => {
+                        assert_eq!(entry.text, "hello");
+                        sender.send(Outgoing::Close).unwrap();
+                        return;
+                    }
+                    Some(_) => {}
+                    None => panic!("the log ended before the echo came back"),
+                }
+            }
+        };
+        let (result, ()) = tokio::join!(connection, close);
+        result.unwrap();
+
+        let seen = seen.lock().unwrap().clone();
+        let values = |name: &str| -> Vec<String> {
+            seen.iter().filter(|(key, _)| key == name).map(|(_, value)| value.clone()).collect()
+        };
+        assert_eq!(values("x-tag"), vec!["a", "b"]);
+        assert_eq!(values("cookie"), vec!["earlier=1"]);
+        let stored = cookies.cookies(&http).unwrap();
+        let stored = stored.to_str().unwrap();
+        assert!(stored.contains("session=abc") && stored.contains("earlier=1"), "{}", stored);
+    }
+}
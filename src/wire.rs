@@ -0,0 +1,102 @@
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::cookie::CookieStore;
+use reqwest::Version;
+use reqwest_cookie_store::CookieStoreMutex;
+
+use crate::settings::RequestSettings;
+
+// How much of a text body the Verbose tab shows before cutting it off.
+const BODY_PREVIEW: usize = 4096;
+
+// reqwest adds these while sending. Putting them on the request up front
+// makes them part of what the Verbose tab shows, and reqwest leaves headers
+// that are already set alone.
+pub fn add_client_headers(headers: &mut HeaderMap, url: &reqwest::Url, settings: &RequestSettings, cookies: &CookieStoreMutex) {
+    if !headers.contains_key(header::ACCEPT) {
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+    }
+    if !headers.contains_key(header::COOKIE) {
+        if let Some(cookie) = cookies.cookies(url) {
+            headers.insert(header::COOKIE, cookie);
+        }
+    }
+    if settings.decompress != Some(false) && !headers.contains_key(header::ACCEPT_ENCODING) && !headers.contains_key(header::RANGE) {
+        headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip, br, deflate"));
+    }
+}
+
+fn version(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_2 => "HTTP/2",
+        Version::HTTP_3 => "HTTP/3",
+        _ => "HTTP/1.1",
+    }
+}
+
+// `> ` lines for what went out, like `curl -v`. Host and Content-Length are
+// written by the connection itself, so they're filled in here the same way.
+pub fn request(
+    method: &str,
+    url: &reqwest::Url,
+    http_version: Version,
+    headers: &[(String, String)],
+    body: Option<&[u8]>,
+) -> String {
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    let mut lines = format!("> {} {} {}\n", method, path, version(http_version));
+
+    let has = |name: &str| headers.iter().any(|(key, _)| key.eq_ignore_ascii_case(name));
+    if !has("host") {
+        let host = url.host_str().unwrap_or_default();
+        match url.port() {
+            Some(port) => lines.push_str(&format!("> host: {}:{}\n", host, port)),
+            None => lines.push_str(&format!("> host: {}\n", host)),
+        }
+    }
+    for (key, value) in headers {
+        lines.push_str(&format!("> {}: {}\n", key, value));
+    }
+    if let (Some(body), false) = (body, has("content-length")) {
+        lines.push_str(&format!("> content-length: {}\n", body.len()));
+    }
+    lines.push_str(">\n");
+    if let Some(body) = body {
+        lines.push_str(&describe_body(body));
+    }
+    lines
+}
+
+// `< ` lines for the status line and headers that came back.
+pub fn response(http_version: Version, status: reqwest::StatusCode, headers: &[(String, String)]) -> String {
+    let mut lines = format!(
+        "< {} {} {}\n",
+        version(http_version),
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default()
+    );
+    for (key, value) in headers {
+        lines.push_str(&format!("< {}: {}\n", key, value));
+    }
+    lines.push_str("<\n");
+    lines
+}
+
+fn describe_body(body: &[u8]) -> String {
+    match std::str::from_utf8(body) {
+        Ok(text) if !text.chars().any(|c| c.is_control() && !c.is_whitespace()) => {
+            if text.len() > BODY_PREVIEW {
+                let end = (0..=BODY_PREVIEW).rev().find(|i| text.is_char_boundary(*i)).unwrap_or(0);
+                format!("{}\n[{} more bytes]\n", &text[..end], text.len() - end)
+            } else {
+                format!("{}\n", text)
+            }
+        }
+        _ => format!("[{} bytes of binary data]\n", body.len()),
+    }
+}